@@ -0,0 +1,105 @@
+use crate::mpv::chapters::Chapter;
+use crate::mpv::crossfade::CrossfadeSettings;
+use crate::mpv::equalizer::EqualizerSettings;
+use crate::mpv::events::EventSink;
+use crate::mpv::logs::{LogEntry, LogLevel};
+use crate::mpv::loudness::NormalizationSettings;
+use crate::mpv::options::MpvOptions;
+use crate::mpv::properties::PropertyAllowlist;
+use crate::mpv::repeat::RepeatMode;
+use crate::mpv::replaygain::ReplayGainSettings;
+use crate::mpv::sleep_timer::SleepTimerSettings;
+use crate::mpv::speed::PitchCorrection;
+use crate::mpv::volume::VolumeSettings;
+use crate::mpv::{AudioDevice, MpvStatus, PlaylistEntry};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+
+pub type SharedBackend = Arc<Mutex<Box<dyn Backend>>>;
+
+// Transport, queue and volume, what any player behind the UI has to offer
+pub trait PlaybackBackend: Send {
+    fn init(&mut self, custom_path: Option<String>) -> Result<bool, String>;
+    fn set_event_sink(&mut self, sink: EventSink);
+    fn get_status(&self) -> MpvStatus;
+
+    fn load(&mut self, url: &str) -> Result<(), String>;
+    fn play(&mut self) -> Result<(), String>;
+    fn pause(&mut self) -> Result<(), String>;
+    fn stop(&mut self) -> Result<(), String>;
+    fn seek(&mut self, position: f64) -> Result<(), String>;
+    fn set_volume(&mut self, volume: f64) -> Result<(), String>;
+    fn set_mute(&mut self, muted: bool) -> Result<(), String>;
+    fn set_volume_settings(&mut self, settings: VolumeSettings) -> Result<(), String>;
+
+    fn load_playlist(&mut self, urls: Vec<String>) -> Result<(), String>;
+    fn load_playlist_optimized(&mut self, urls: Vec<String>) -> Result<(), String>;
+    fn sync_playlist(&mut self, urls: Vec<String>, current_index: usize) -> Result<(), String>;
+    fn playlist_entries(&self) -> Result<Vec<PlaylistEntry>, String>;
+    fn playlist_next(&mut self) -> Result<(), String>;
+    fn playlist_prev(&mut self) -> Result<(), String>;
+    fn playlist_play_index(&mut self, index: usize) -> Result<(), String>;
+    fn set_playlist_position(&mut self, index: usize) -> Result<(), String>;
+    fn playlist_jump_to_index(&mut self, index: usize) -> Result<(), String>;
+    fn playlist_insert(&mut self, url: &str, index: usize) -> Result<(), String>;
+    fn playlist_move(&mut self, from: usize, to: usize) -> Result<(), String>;
+    fn playlist_remove(&mut self, index: usize) -> Result<(), String>;
+    fn playlist_clear_after_current(&mut self) -> Result<(), String>;
+
+    fn chapters(&self) -> Result<Vec<Chapter>, String>;
+    fn chapter_next(&mut self) -> Result<(), String>;
+    fn chapter_prev(&mut self) -> Result<(), String>;
+    fn set_chapter(&mut self, index: usize) -> Result<(), String>;
+
+    fn set_repeat(&mut self, mode: RepeatMode) -> Result<(), String>;
+    fn set_ab_loop(&mut self, a: Option<f64>, b: Option<f64>) -> Result<(), String>;
+    fn set_sleep_timer(&mut self, settings: SleepTimerSettings) -> Result<(), String>;
+    fn cancel_sleep_timer(&mut self) -> Result<(), String>;
+
+    fn audio_devices(&self) -> Result<Vec<AudioDevice>, String>;
+    fn set_audio_device(&mut self, name: &str) -> Result<(), String>;
+
+    fn shutdown(&mut self);
+}
+
+// Processing applied to the decoded audio
+pub trait AudioEffects {
+    fn set_speed(&mut self, speed: f64, pitch_correction: PitchCorrection) -> Result<(), String>;
+    fn set_pitch(&mut self, semitones: f64) -> Result<(), String>;
+
+    fn set_replaygain(&mut self, settings: ReplayGainSettings) -> Result<(), String>;
+    fn set_normalization(&mut self, settings: NormalizationSettings) -> Result<(), String>;
+
+    fn set_equalizer(&mut self, settings: EqualizerSettings) -> Result<(), String>;
+    fn equalizer(&self) -> EqualizerSettings;
+    fn set_genre_equalizers(
+        &mut self,
+        presets: HashMap<String, EqualizerSettings>,
+    ) -> Result<(), String>;
+
+    fn set_crossfade(&mut self, settings: CrossfadeSettings) -> Result<(), String>;
+}
+
+// Direct access to the mpv process behind the player
+pub trait MpvExtensions {
+    fn set_mpv_options(&mut self, options: MpvOptions) -> Result<(), String>;
+
+    fn get_property(&self, name: &str) -> Result<serde_json::Value, String>;
+    fn set_property(&mut self, name: &str, value: serde_json::Value) -> Result<(), String>;
+    fn observe_property(&mut self, name: &str) -> Result<(), String>;
+    fn unobserve_property(&mut self, name: &str) -> Result<(), String>;
+    // Not exposed as a command, the webview must not be able to widen its own access
+    fn set_property_allowlist(&mut self, allowlist: PropertyAllowlist) -> Result<(), String>;
+
+    fn logs(&self) -> Vec<LogEntry>;
+    fn set_log_level(&mut self, level: LogLevel) -> Result<(), String>;
+    // Adds one of the app's own diagnostics to the log, next to mpv's
+    fn report(&mut self, level: LogLevel, text: &str);
+}
+
+pub trait Backend: PlaybackBackend + AudioEffects + MpvExtensions {}
+
+impl<T: PlaybackBackend + AudioEffects + MpvExtensions> Backend for T {}
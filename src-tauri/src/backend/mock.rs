@@ -0,0 +1,972 @@
+use super::{AudioEffects, MpvExtensions, PlaybackBackend};
+use crate::mpv::chapters::{self, Chapter};
+use crate::mpv::crossfade::CrossfadeSettings;
+use crate::mpv::equalizer::{EqualizerSettings, EqualizerState};
+use crate::mpv::events::{
+    AudioDevicesChange, EqualizerChange, EventSink, PlaybackEnded, PlaybackError, PlayerEvent,
+    PlayerEvents, PlaylistChange, PropertyChange, TrackChange,
+};
+use crate::mpv::logs::{LogBuffer, LogEntry, LogLevel};
+use crate::mpv::loudness::NormalizationSettings;
+use crate::mpv::options::MpvOptions;
+use crate::mpv::properties::PropertyAllowlist;
+use crate::mpv::repeat::{self, RepeatMode};
+use crate::mpv::replaygain::ReplayGainSettings;
+use crate::mpv::sleep_timer::{SleepTimer, SleepTimerSettings};
+use crate::mpv::speed::{PitchCorrection, SpeedSettings};
+use crate::mpv::volume::VolumeSettings;
+use crate::mpv::{AudioDevice, MpvStatus, PlaylistEntry};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+const DEFAULT_TRACK_DURATION: f64 = 180.0;
+// Going back within this many seconds of a chapter's start goes to the one before, like mpv
+const SEEK_BACK_THRESHOLD: f64 = 5.0;
+
+pub struct MockBackend {
+    status: Arc<Mutex<MpvStatus>>,
+    events: PlayerEvents,
+    playlist: Vec<String>,
+    track_duration: f64,
+    unplayable: HashSet<String>,
+    chapters: HashMap<String, Vec<Chapter>>,
+    properties: HashMap<String, serde_json::Value>,
+    observed: HashSet<String>,
+    property_allowlist: PropertyAllowlist,
+    logs: LogBuffer,
+    log_level: LogLevel,
+    pending_error: Option<String>,
+    commands: Vec<String>,
+    audio_devices: Vec<AudioDevice>,
+    preferred_audio_device: Option<String>,
+    equalizer: EqualizerState,
+    speed: SpeedSettings,
+    volume: VolumeSettings,
+    crossfade: CrossfadeSettings,
+    replaygain: ReplayGainSettings,
+    normalization: NormalizationSettings,
+    sleep_timer: Option<SleepTimer>,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        let status = Arc::new(Mutex::new(MpvStatus::default()));
+        let events = PlayerEvents::new(Arc::clone(&status));
+
+        Self {
+            status,
+            events,
+            playlist: Vec::new(),
+            track_duration: DEFAULT_TRACK_DURATION,
+            unplayable: HashSet::new(),
+            chapters: HashMap::new(),
+            properties: HashMap::new(),
+            observed: HashSet::new(),
+            property_allowlist: PropertyAllowlist::default(),
+            logs: LogBuffer::default(),
+            log_level: LogLevel::default(),
+            pending_error: None,
+            commands: Vec::new(),
+            audio_devices: vec![AudioDevice {
+                name: "auto".to_string(),
+                description: "Autoselect device".to_string(),
+            }],
+            preferred_audio_device: None,
+            equalizer: EqualizerState::default(),
+            speed: SpeedSettings::default(),
+            volume: VolumeSettings::default(),
+            crossfade: CrossfadeSettings::default(),
+            replaygain: ReplayGainSettings::default(),
+            normalization: NormalizationSettings::default(),
+            sleep_timer: None,
+        }
+    }
+
+    pub fn set_track_duration(&mut self, duration: f64) {
+        self.track_duration = duration;
+    }
+
+    pub fn set_unplayable(&mut self, url: &str) {
+        self.unplayable.insert(url.to_string());
+    }
+
+    pub fn set_chapters(&mut self, url: &str, chapters: Vec<Chapter>) {
+        self.chapters.insert(url.to_string(), chapters);
+    }
+
+    // Logs like mpv would, dropping messages below the requested level
+    pub fn log(&mut self, level: LogLevel, prefix: &str, text: &str) {
+        if self.log_level == LogLevel::No || level > self.log_level {
+            return;
+        }
+        let entry = LogEntry::new(level, prefix, text);
+        self.logs.push(entry.clone());
+        self.events.emit(PlayerEvent::Log(vec![entry]));
+    }
+
+    pub fn fail_next_command(&mut self, message: &str) {
+        self.pending_error = Some(message.to_string());
+    }
+
+    pub fn commands(&self) -> &[String] {
+        &self.commands
+    }
+
+    pub fn playlist(&self) -> &[String] {
+        &self.playlist
+    }
+
+    pub fn crossfade(&self) -> &CrossfadeSettings {
+        &self.crossfade
+    }
+
+    pub fn set_audio_devices(&mut self, devices: Vec<AudioDevice>) {
+        self.audio_devices = devices;
+
+        let wanted = self
+            .preferred_audio_device
+            .clone()
+            .filter(|name| self.audio_devices.iter().any(|device| &device.name == name))
+            .unwrap_or_else(|| "auto".to_string());
+        self.status.lock().unwrap().audio_device = Some(wanted);
+        self.events.status_changed();
+
+        self.events
+            .emit(PlayerEvent::AudioDevicesChanged(AudioDevicesChange {
+                devices: self.audio_devices.clone(),
+            }));
+    }
+
+    pub fn set_track_genre(&mut self, genre: Option<&str>) {
+        let previous = self.equalizer.active().clone();
+        self.equalizer.set_genre(genre.map(String::from));
+        if *self.equalizer.active() != previous {
+            self.equalizer_changed();
+        }
+    }
+
+    // Takes wall-clock seconds, so the playback speed is applied like mpv would
+    pub fn advance(&mut self, seconds: f64) {
+        // The sleep timer's countdown runs on wall-clock time too and cuts playback short
+        let countdown = self.sleep_timer.as_ref().and_then(SleepTimer::countdown);
+        self.play_for(countdown.map_or(seconds, |left| left.clamp(0.0, seconds)));
+
+        let Some(timer) = &mut self.sleep_timer else {
+            return;
+        };
+        timer.elapse(seconds);
+        if timer.countdown().is_some_and(|left| left <= 0.0) {
+            self.fall_asleep();
+        } else {
+            self.sleep_timer_changed();
+        }
+    }
+
+    fn play_for(&mut self, seconds: f64) {
+        let mut remaining = seconds * self.speed.speed;
+
+        loop {
+            let (pos, position, duration, repeat, ab_loop) = {
+                let status = self.status.lock().unwrap();
+                if status.state != "playing" {
+                    return;
+                }
+                (
+                    status.playlist_pos,
+                    status.position,
+                    status.duration,
+                    status.repeat,
+                    status.ab_loop_a.zip(status.ab_loop_b),
+                )
+            };
+
+            if let Some((a, b)) = ab_loop.filter(|(_, b)| position < *b) {
+                if position + remaining >= b {
+                    remaining -= b - position;
+                    self.set_position(a);
+                    continue;
+                }
+            }
+            if position + remaining < duration {
+                self.set_position(position + remaining);
+                self.events.status_changed();
+                return;
+            }
+
+            remaining -= duration - position;
+            // mpv loops a file by seeking back, without ending it
+            if repeat == RepeatMode::One {
+                self.set_position(0.0);
+                continue;
+            }
+            // The file is kept open at its end, like mpv's keep-open=always
+            if self
+                .sleep_timer
+                .as_ref()
+                .is_some_and(SleepTimer::is_last_track)
+            {
+                self.set_position(duration);
+                self.fall_asleep();
+                return;
+            }
+            self.events.emit(PlayerEvent::Ended(PlaybackEnded {
+                reason: "eof".to_string(),
+                playlist_entry_id: Some(pos as i64 + 1),
+            }));
+            if let Some(timer) = &mut self.sleep_timer {
+                timer.track_finished();
+            }
+
+            let next = pos as usize + 1;
+            if next < self.playlist.len() {
+                self.start_track(next);
+            } else if repeat == RepeatMode::All {
+                self.start_track(0);
+            } else {
+                let mut status = self.status.lock().unwrap();
+                status.position = duration;
+                status.playback_time = Some(duration);
+                status.state = "ended".to_string();
+                drop(status);
+                self.events.status_changed();
+                return;
+            }
+        }
+    }
+
+    fn fall_asleep(&mut self) {
+        self.sleep_timer = None;
+        {
+            let mut status = self.status.lock().unwrap();
+            status.pause = Some(true);
+            status.state = "paused".to_string();
+            status.sleep_timer = None;
+        }
+        self.events.status_changed();
+    }
+
+    fn sleep_timer_changed(&self) {
+        {
+            let mut status = self.status.lock().unwrap();
+            status.sleep_timer = self.sleep_timer.as_ref().map(|timer| timer.status(&status));
+        }
+        self.events.status_changed();
+    }
+
+    fn set_position(&mut self, position: f64) {
+        let mut status = self.status.lock().unwrap();
+        status.position = position;
+        status.playback_time = Some(position);
+
+        let chapters = self.current_chapters(&status);
+        if !chapters.is_empty() {
+            let chapter = chapter_at(chapters, position);
+            status.chapter = Some(chapter);
+            status.chapter_title = chapters::title(chapters, status.chapter);
+        }
+    }
+
+    fn current_chapters(&self, status: &MpvStatus) -> &[Chapter] {
+        status
+            .current_url
+            .as_ref()
+            .and_then(|url| self.chapters.get(url))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    fn check(&mut self, command: &str) -> Result<(), String> {
+        self.commands.push(command.to_string());
+
+        match self.pending_error.take() {
+            Some(message) => {
+                self.events.emit(PlayerEvent::Error(PlaybackError {
+                    message: message.clone(),
+                }));
+                Err(message)
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn start_track(&mut self, index: usize) {
+        let url = self.playlist[index].clone();
+        let track_change = {
+            let mut status = self.status.lock().unwrap();
+            let changed = status.playlist_pos != index as i32;
+
+            status.playlist_pos = index as i32;
+            status.playlist_count = self.playlist.len() as i32;
+            status.position = 0.0;
+            status.playback_time = Some(0.0);
+            status.media_title = Some(url.clone());
+            status.current_url = Some(url.clone());
+            status.ab_loop_a = None;
+            status.ab_loop_b = None;
+            let chapters = self.current_chapters(&status);
+            status.chapter_count = Some(chapters.len() as i32);
+            status.chapter = (!chapters.is_empty()).then(|| chapter_at(chapters, 0.0));
+            status.chapter_title = chapters::title(chapters, status.chapter);
+
+            if self.unplayable.contains(&url) {
+                status.duration = 0.0;
+                status.state = "error".to_string();
+            } else {
+                status.duration = self.track_duration;
+                status.state = if status.pause == Some(true) {
+                    "paused".to_string()
+                } else {
+                    "playing".to_string()
+                };
+            }
+
+            changed.then(|| TrackChange {
+                playlist_pos: status.playlist_pos,
+                playlist_count: status.playlist_count,
+            })
+        };
+
+        self.events.status_changed();
+        if let Some(change) = track_change {
+            self.events.emit(PlayerEvent::TrackChanged(change));
+        }
+        if self.unplayable.contains(&url) {
+            self.events.emit(PlayerEvent::Error(PlaybackError {
+                message: format!("Failed to load {}", url),
+            }));
+        }
+    }
+
+    fn replace_playlist(&mut self, urls: Vec<String>) {
+        self.playlist = urls;
+        {
+            let mut status = self.status.lock().unwrap();
+            status.playlist_pos = -1;
+            status.playlist_count = self.playlist.len() as i32;
+        }
+
+        if self.playlist.is_empty() {
+            self.reset_playback("idle");
+        } else {
+            self.start_track(0);
+        }
+        self.playlist_changed();
+    }
+
+    fn playlist_changed(&mut self) {
+        let playlist_pos = {
+            let mut status = self.status.lock().unwrap();
+            status.playlist_count = self.playlist.len() as i32;
+            status.playlist_pos
+        };
+        self.events.status_changed();
+        self.events
+            .emit(PlayerEvent::PlaylistChanged(PlaylistChange {
+                entries: self.playlist.clone(),
+                playlist_pos,
+            }));
+    }
+
+    fn update_speed(&mut self, command: &str, settings: SpeedSettings) -> Result<(), String> {
+        self.check(command)?;
+        settings.validate()?;
+
+        {
+            let mut status = self.status.lock().unwrap();
+            status.speed = settings.speed;
+            status.pitch = settings.pitch;
+        }
+        self.speed = settings;
+        self.events.status_changed();
+        Ok(())
+    }
+
+    fn replaygain_changed(&self) {
+        let gain = (!self.normalization.is_active())
+            .then(|| self.replaygain.effective_gain(None))
+            .flatten();
+        self.status.lock().unwrap().replaygain_gain = gain;
+        self.events.status_changed();
+    }
+
+    fn equalizer_changed(&self) {
+        self.events
+            .emit(PlayerEvent::EqualizerChanged(EqualizerChange {
+                settings: self.equalizer.active().clone(),
+                genre: self.equalizer.active_genre(),
+            }));
+    }
+
+    fn current_pos(&self) -> i32 {
+        self.status.lock().unwrap().playlist_pos
+    }
+
+    fn set_current_pos(&mut self, pos: i32) {
+        self.status.lock().unwrap().playlist_pos = pos;
+    }
+
+    fn reset_playback(&mut self, state: &str) {
+        {
+            let mut status = self.status.lock().unwrap();
+            status.state = state.to_string();
+            status.position = 0.0;
+            status.duration = 0.0;
+            status.playback_time = None;
+            status.media_title = None;
+            status.current_url = None;
+            status.playlist_pos = -1;
+            status.playlist_count = self.playlist.len() as i32;
+        }
+        self.events.status_changed();
+    }
+
+    fn playlist_index(&self, index: usize) -> Result<usize, String> {
+        if self.playlist.is_empty() {
+            return Err("Playlist is empty".to_string());
+        }
+
+        if index >= self.playlist.len() {
+            return Err(format!(
+                "Index {} out of range, playlist has {} items",
+                index,
+                self.playlist.len()
+            ));
+        }
+
+        Ok(index)
+    }
+}
+
+// mpv reports -1 while playback is still before the first chapter
+fn chapter_at(chapters: &[Chapter], position: f64) -> i32 {
+    chapters
+        .iter()
+        .rposition(|chapter| chapter.time <= position)
+        .map_or(-1, |index| index as i32)
+}
+
+fn previous_chapter(chapters: &[Chapter], position: f64) -> usize {
+    let current = chapter_at(chapters, position);
+    match usize::try_from(current) {
+        Ok(index) if position - chapters[index].time > SEEK_BACK_THRESHOLD => index,
+        Ok(index) => index.saturating_sub(1),
+        Err(_) => 0,
+    }
+}
+
+impl Default for MockBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PlaybackBackend for MockBackend {
+    fn init(&mut self, _custom_path: Option<String>) -> Result<bool, String> {
+        self.check("init")?;
+        self.status.lock().unwrap().initialized = true;
+        self.events.status_changed();
+        Ok(true)
+    }
+
+    fn set_event_sink(&mut self, sink: EventSink) {
+        self.events.set_sink(sink);
+    }
+
+    fn get_status(&self) -> MpvStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    fn load(&mut self, url: &str) -> Result<(), String> {
+        self.check("load")?;
+        self.replace_playlist(vec![url.to_string()]);
+        Ok(())
+    }
+
+    fn play(&mut self) -> Result<(), String> {
+        self.check("play")?;
+        {
+            let mut status = self.status.lock().unwrap();
+            status.pause = Some(false);
+            if status.state == "paused" {
+                status.state = "playing".to_string();
+            }
+        }
+        self.events.status_changed();
+        Ok(())
+    }
+
+    fn pause(&mut self) -> Result<(), String> {
+        self.check("pause")?;
+        {
+            let mut status = self.status.lock().unwrap();
+            status.pause = Some(true);
+            if status.state == "playing" {
+                status.state = "paused".to_string();
+            }
+        }
+        self.events.status_changed();
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), String> {
+        self.check("stop")?;
+        self.playlist.clear();
+        self.reset_playback("idle");
+        Ok(())
+    }
+
+    fn seek(&mut self, position: f64) -> Result<(), String> {
+        self.check("seek")?;
+        let position = {
+            let status = self.status.lock().unwrap();
+            if status.playlist_pos < 0 {
+                return Err("No file loaded".to_string());
+            }
+            position.clamp(0.0, status.duration)
+        };
+        self.set_position(position);
+        self.events.status_changed();
+        Ok(())
+    }
+
+    fn chapters(&self) -> Result<Vec<Chapter>, String> {
+        let status = self.status.lock().unwrap();
+        Ok(self.current_chapters(&status).to_vec())
+    }
+
+    fn chapter_next(&mut self) -> Result<(), String> {
+        self.check("chapter_next")?;
+        let (chapters, chapter) = {
+            let status = self.status.lock().unwrap();
+            (self.current_chapters(&status).to_vec(), status.chapter)
+        };
+        if chapters.is_empty() {
+            return Err("The current file has no chapters".to_string());
+        }
+
+        let next = (chapter.unwrap_or(-1) + 1) as usize;
+        match chapters.get(next) {
+            Some(chapter) => self.set_position(chapter.time),
+            // Past the last chapter mpv moves on to the next file
+            None => {
+                let next_track = self.current_pos() as usize + 1;
+                if next_track < self.playlist.len() {
+                    self.start_track(next_track);
+                }
+            }
+        }
+        self.events.status_changed();
+        Ok(())
+    }
+
+    fn chapter_prev(&mut self) -> Result<(), String> {
+        self.check("chapter_prev")?;
+        let (chapters, position) = {
+            let status = self.status.lock().unwrap();
+            (self.current_chapters(&status).to_vec(), status.position)
+        };
+        if chapters.is_empty() {
+            return Err("The current file has no chapters".to_string());
+        }
+
+        let previous = previous_chapter(&chapters, position);
+        self.set_position(chapters[previous].time);
+        self.events.status_changed();
+        Ok(())
+    }
+
+    fn set_chapter(&mut self, index: usize) -> Result<(), String> {
+        self.check("set_chapter")?;
+        let chapters = self.chapters()?;
+        chapters::validate_index(index, chapters.len())?;
+        self.set_position(chapters[index].time);
+        self.events.status_changed();
+        Ok(())
+    }
+
+    fn set_volume(&mut self, volume: f64) -> Result<(), String> {
+        self.check("set_volume")?;
+        self.volume.validate_level(volume)?;
+        self.status.lock().unwrap().volume = volume;
+        self.events.status_changed();
+        Ok(())
+    }
+
+    fn set_mute(&mut self, muted: bool) -> Result<(), String> {
+        self.check("set_mute")?;
+        self.status.lock().unwrap().muted = muted;
+        self.events.status_changed();
+        Ok(())
+    }
+
+    fn set_volume_settings(&mut self, settings: VolumeSettings) -> Result<(), String> {
+        self.check("set_volume_settings")?;
+        settings.validate()?;
+        {
+            let mut status = self.status.lock().unwrap();
+            status.max_volume = settings.max_level();
+            status.volume = status.volume.min(status.max_volume);
+        }
+        self.volume = settings;
+        self.events.status_changed();
+        Ok(())
+    }
+
+    fn load_playlist(&mut self, urls: Vec<String>) -> Result<(), String> {
+        self.check("load_playlist")?;
+        self.replace_playlist(urls);
+        Ok(())
+    }
+
+    fn load_playlist_optimized(&mut self, urls: Vec<String>) -> Result<(), String> {
+        self.check("load_playlist_optimized")?;
+        self.replace_playlist(urls);
+        Ok(())
+    }
+
+    fn sync_playlist(&mut self, urls: Vec<String>, current_index: usize) -> Result<(), String> {
+        self.check("sync_playlist")?;
+        if urls.is_empty() {
+            self.playlist.clear();
+            self.reset_playback("idle");
+            self.playlist_changed();
+            return Ok(());
+        }
+        if current_index >= urls.len() {
+            return Err(format!(
+                "Index {} out of range, playlist has {} items",
+                current_index,
+                urls.len()
+            ));
+        }
+
+        let playing = usize::try_from(self.current_pos())
+            .ok()
+            .and_then(|pos| self.playlist.get(pos))
+            .cloned();
+        self.playlist = urls;
+        if playing.as_ref() == Some(&self.playlist[current_index]) {
+            self.set_current_pos(current_index as i32);
+        } else {
+            self.start_track(current_index);
+        }
+        self.playlist_changed();
+        Ok(())
+    }
+
+    fn playlist_entries(&self) -> Result<Vec<PlaylistEntry>, String> {
+        let pos = self.status.lock().unwrap().playlist_pos;
+        Ok(self
+            .playlist
+            .iter()
+            .enumerate()
+            .map(|(i, url)| PlaylistEntry {
+                id: Some(i as i64 + 1),
+                filename: url.clone(),
+                title: None,
+                current: i as i32 == pos,
+                playing: i as i32 == pos,
+            })
+            .collect())
+    }
+
+    fn playlist_next(&mut self) -> Result<(), String> {
+        self.check("playlist_next")?;
+        let next = (self.status.lock().unwrap().playlist_pos + 1) as usize;
+        let index = self.playlist_index(next)?;
+        self.start_track(index);
+        Ok(())
+    }
+
+    fn playlist_prev(&mut self) -> Result<(), String> {
+        self.check("playlist_prev")?;
+        let pos = self.status.lock().unwrap().playlist_pos;
+        if pos <= 0 {
+            return Err("No previous playlist entry".to_string());
+        }
+        self.start_track(pos as usize - 1);
+        Ok(())
+    }
+
+    fn playlist_play_index(&mut self, index: usize) -> Result<(), String> {
+        self.check("playlist_play_index")?;
+        let index = self.playlist_index(index)?;
+        self.start_track(index);
+        Ok(())
+    }
+
+    fn set_playlist_position(&mut self, index: usize) -> Result<(), String> {
+        self.check("set_playlist_position")?;
+        let index = self.playlist_index(index)?;
+        self.start_track(index);
+        Ok(())
+    }
+
+    fn playlist_jump_to_index(&mut self, index: usize) -> Result<(), String> {
+        self.check("playlist_jump_to_index")?;
+        let index = self.playlist_index(index)?;
+        self.start_track(index);
+        Ok(())
+    }
+
+    fn playlist_insert(&mut self, url: &str, index: usize) -> Result<(), String> {
+        self.check("playlist_insert")?;
+        if index > self.playlist.len() {
+            return Err(format!(
+                "Index {} out of range, playlist has {} items",
+                index,
+                self.playlist.len()
+            ));
+        }
+
+        self.playlist.insert(index, url.to_string());
+        let pos = self.current_pos();
+        if pos >= 0 && index as i32 <= pos {
+            self.set_current_pos(pos + 1);
+        }
+        self.playlist_changed();
+        Ok(())
+    }
+
+    fn playlist_move(&mut self, from: usize, to: usize) -> Result<(), String> {
+        self.check("playlist_move")?;
+        let from = self.playlist_index(from)?;
+        let to = self.playlist_index(to)?;
+
+        let url = self.playlist.remove(from);
+        self.playlist.insert(to, url);
+
+        let pos = self.current_pos();
+        let (from, to) = (from as i32, to as i32);
+        if pos == from {
+            self.set_current_pos(to);
+        } else if from < pos && to >= pos {
+            self.set_current_pos(pos - 1);
+        } else if from > pos && to <= pos {
+            self.set_current_pos(pos + 1);
+        }
+        self.playlist_changed();
+        Ok(())
+    }
+
+    fn playlist_remove(&mut self, index: usize) -> Result<(), String> {
+        self.check("playlist_remove")?;
+        let index = self.playlist_index(index)?;
+
+        self.playlist.remove(index);
+        let pos = self.current_pos();
+        if (index as i32) < pos {
+            self.set_current_pos(pos - 1);
+        } else if index as i32 == pos {
+            if index < self.playlist.len() {
+                self.start_track(index);
+            } else {
+                self.reset_playback("idle");
+            }
+        }
+        self.playlist_changed();
+        Ok(())
+    }
+
+    fn playlist_clear_after_current(&mut self) -> Result<(), String> {
+        self.check("playlist_clear_after_current")?;
+        let keep = (self.current_pos() + 1).max(0) as usize;
+        self.playlist.truncate(keep);
+        self.playlist_changed();
+        Ok(())
+    }
+
+    fn set_repeat(&mut self, mode: RepeatMode) -> Result<(), String> {
+        self.check("set_repeat")?;
+        self.status.lock().unwrap().repeat = mode;
+        self.events.status_changed();
+        Ok(())
+    }
+
+    fn set_ab_loop(&mut self, a: Option<f64>, b: Option<f64>) -> Result<(), String> {
+        self.check("set_ab_loop")?;
+        repeat::validate_ab_loop(a, b)?;
+
+        {
+            let mut status = self.status.lock().unwrap();
+            status.ab_loop_a = a;
+            status.ab_loop_b = b;
+        }
+        self.events.status_changed();
+        Ok(())
+    }
+
+    fn set_sleep_timer(&mut self, settings: SleepTimerSettings) -> Result<(), String> {
+        self.check("set_sleep_timer")?;
+        settings.validate()?;
+        self.sleep_timer = Some(SleepTimer::new(settings));
+        self.sleep_timer_changed();
+        Ok(())
+    }
+
+    fn cancel_sleep_timer(&mut self) -> Result<(), String> {
+        self.check("cancel_sleep_timer")?;
+        self.sleep_timer = None;
+        self.sleep_timer_changed();
+        Ok(())
+    }
+
+    fn audio_devices(&self) -> Result<Vec<AudioDevice>, String> {
+        Ok(self.audio_devices.clone())
+    }
+
+    fn set_audio_device(&mut self, name: &str) -> Result<(), String> {
+        self.check("set_audio_device")?;
+        if !self.audio_devices.iter().any(|device| device.name == name) {
+            return Err(format!("Unknown audio device '{}'", name));
+        }
+
+        self.preferred_audio_device = (name != "auto").then(|| name.to_string());
+        self.status.lock().unwrap().audio_device = Some(name.to_string());
+        self.events.status_changed();
+        Ok(())
+    }
+
+    fn shutdown(&mut self) {
+        self.commands.push("shutdown".to_string());
+        self.playlist.clear();
+        *self.status.lock().unwrap() = MpvStatus::default();
+        self.events.status_changed();
+    }
+}
+
+impl AudioEffects for MockBackend {
+    fn set_speed(&mut self, speed: f64, pitch_correction: PitchCorrection) -> Result<(), String> {
+        let settings = SpeedSettings {
+            speed,
+            pitch_correction,
+            ..self.speed.clone()
+        };
+        self.update_speed("set_speed", settings)
+    }
+
+    fn set_pitch(&mut self, semitones: f64) -> Result<(), String> {
+        let settings = SpeedSettings {
+            pitch: semitones,
+            ..self.speed.clone()
+        };
+        self.update_speed("set_pitch", settings)
+    }
+
+    fn set_replaygain(&mut self, settings: ReplayGainSettings) -> Result<(), String> {
+        self.check("set_replaygain")?;
+        self.replaygain = settings;
+        self.replaygain_changed();
+        Ok(())
+    }
+
+    fn set_normalization(&mut self, settings: NormalizationSettings) -> Result<(), String> {
+        self.check("set_normalization")?;
+        settings.validate()?;
+        self.status.lock().unwrap().normalization = settings.mode;
+        self.normalization = settings;
+        self.replaygain_changed();
+        Ok(())
+    }
+
+    fn set_equalizer(&mut self, settings: EqualizerSettings) -> Result<(), String> {
+        self.check("set_equalizer")?;
+        settings.validate()?;
+        self.equalizer.set_manual(settings);
+        self.equalizer_changed();
+        Ok(())
+    }
+
+    fn equalizer(&self) -> EqualizerSettings {
+        self.equalizer.active().clone()
+    }
+
+    fn set_genre_equalizers(
+        &mut self,
+        presets: HashMap<String, EqualizerSettings>,
+    ) -> Result<(), String> {
+        for settings in presets.values() {
+            settings.validate()?;
+        }
+
+        let previous = self.equalizer.active().clone();
+        self.equalizer.set_genre_presets(presets);
+        if *self.equalizer.active() != previous {
+            self.equalizer_changed();
+        }
+        Ok(())
+    }
+
+    fn set_crossfade(&mut self, settings: CrossfadeSettings) -> Result<(), String> {
+        self.check("set_crossfade")?;
+        settings.validate()?;
+        self.crossfade = settings;
+        Ok(())
+    }
+}
+
+impl MpvExtensions for MockBackend {
+    fn set_mpv_options(&mut self, options: MpvOptions) -> Result<(), String> {
+        self.check("set_mpv_options")?;
+        options.validate()
+    }
+
+    fn get_property(&self, name: &str) -> Result<serde_json::Value, String> {
+        self.property_allowlist.check_readable(name)?;
+        self.properties
+            .get(name)
+            .cloned()
+            .ok_or_else(|| "property unavailable".to_string())
+    }
+
+    fn set_property(&mut self, name: &str, value: serde_json::Value) -> Result<(), String> {
+        self.check("set_property")?;
+        self.property_allowlist.check_writable(name)?;
+        self.properties.insert(name.to_string(), value.clone());
+        if self.observed.contains(name) {
+            self.events
+                .emit(PlayerEvent::PropertyChanged(PropertyChange {
+                    name: name.to_string(),
+                    value,
+                }));
+        }
+        Ok(())
+    }
+
+    fn observe_property(&mut self, name: &str) -> Result<(), String> {
+        self.check("observe_property")?;
+        self.property_allowlist.check_readable(name)?;
+        self.observed.insert(name.to_string());
+        Ok(())
+    }
+
+    fn unobserve_property(&mut self, name: &str) -> Result<(), String> {
+        self.check("unobserve_property")?;
+        self.observed.remove(name);
+        Ok(())
+    }
+
+    fn set_property_allowlist(&mut self, allowlist: PropertyAllowlist) -> Result<(), String> {
+        self.check("set_property_allowlist")?;
+        allowlist.validate()?;
+        self.observed
+            .retain(|name| allowlist.check_readable(name).is_ok());
+        self.property_allowlist = allowlist;
+        Ok(())
+    }
+
+    fn logs(&self) -> Vec<LogEntry> {
+        self.logs.entries()
+    }
+
+    fn set_log_level(&mut self, level: LogLevel) -> Result<(), String> {
+        self.check("set_log_level")?;
+        self.log_level = level;
+        Ok(())
+    }
+
+    fn report(&mut self, level: LogLevel, text: &str) {
+        let entry = LogEntry::player(level, text);
+        self.logs.push(entry.clone());
+        self.events.emit(PlayerEvent::Log(vec![entry]));
+    }
+}
@@ -1,4 +1,30 @@
 use discord_rich_presence::{activity, DiscordIpc, DiscordIpcClient};
+use serde::Deserialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Deserialize, Debug, Default)]
+pub struct Presence {
+    pub app_id: Option<String>,
+    pub details: Option<String>,
+    pub state: Option<String>,
+    pub large_image: Option<String>,
+    pub small_image: Option<String>,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    // Timestamps are derived from the track position, which moves at this rate
+    pub speed: Option<f64>,
+}
+
+impl Presence {
+    pub fn is_empty(&self) -> bool {
+        self.details.is_none()
+            && self.state.is_none()
+            && self.large_image.is_none()
+            && self.small_image.is_none()
+            && self.start_time.is_none()
+            && self.end_time.is_none()
+    }
+}
 
 pub struct DiscordClient {
     client: Option<DiscordIpcClient>,
@@ -6,6 +32,12 @@ pub struct DiscordClient {
     connected: bool,
 }
 
+impl Default for DiscordClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DiscordClient {
     pub fn new() -> Self {
         Self {
@@ -56,25 +88,35 @@ impl DiscordClient {
         Ok(())
     }
 
-    fn parse_timestamp(time_str: &str) -> Option<i64> {
-        time_str.parse::<i64>().ok()
+    fn parse_timestamp(time_str: &str, speed: f64) -> Option<i64> {
+        let timestamp = time_str.parse::<i64>().ok()?;
+        if speed == 1.0 || speed <= 0.0 {
+            return Some(timestamp);
+        }
+
+        // Discord counts in wall-clock time, so the distance from now shrinks or grows with speed
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs() as i64)
+            .unwrap_or(timestamp);
+        Some(now + ((timestamp - now) as f64 / speed).round() as i64)
     }
 
-    pub fn update_presence(
-        &mut self,
-        app_id: Option<String>,
-        details: Option<String>,
-        state: Option<String>,
-        large_image: Option<String>,
-        small_image: Option<String>,
-        start_time: Option<String>,
-        end_time: Option<String>,
-    ) -> Result<(), String> {
+    pub fn update_presence(&mut self, presence: Presence) -> Result<(), String> {
+        let Presence {
+            app_id,
+            details,
+            state,
+            large_image,
+            small_image,
+            start_time,
+            end_time,
+            speed,
+        } = presence;
         let app_id_to_use = app_id.unwrap_or_else(|| self.app_id.clone());
+        let speed = speed.unwrap_or(1.0);
 
-        if let Err(e) = self.ensure_connected(app_id_to_use) {
-            return Err(e);
-        }
+        self.ensure_connected(app_id_to_use)?;
 
         let activity_builder = {
             if details.is_none() && state.is_none() {
@@ -108,13 +150,13 @@ impl DiscordClient {
                     let mut timestamps_builder = activity::Timestamps::new();
 
                     if let Some(start) = &start_time {
-                        if let Some(timestamp) = Self::parse_timestamp(start) {
+                        if let Some(timestamp) = Self::parse_timestamp(start, speed) {
                             timestamps_builder = timestamps_builder.start(timestamp);
                         }
                     }
 
                     if let Some(end) = &end_time {
-                        if let Some(timestamp) = Self::parse_timestamp(end) {
+                        if let Some(timestamp) = Self::parse_timestamp(end, speed) {
                             timestamps_builder = timestamps_builder.end(timestamp);
                         }
                     }
@@ -1,4 +1,3 @@
-use serde::Deserialize;
 #[cfg(desktop)]
 use tauri::{
     menu::{Menu, MenuItem},
@@ -8,24 +7,45 @@ use tauri::{
 #[cfg(desktop)]
 use tauri_plugin_window_state::{AppHandleExt, StateFlags, WindowExt};
 
+use std::collections::BTreeMap;
 use std::sync::{Arc, Mutex};
-use tauri::Manager;
+use tauri::{Emitter, Manager};
+
+pub mod backend;
+use backend::{Backend, SharedBackend};
 
 pub mod mpv;
-use mpv::MpvPlayer;
+use mpv::chapters::Chapter;
+use mpv::crossfade::CrossfadeSettings;
+use mpv::equalizer::{EqualizerSettings, PresetStore};
+use mpv::logs::{LogEntry, LogLevel};
+use mpv::loudness::NormalizationSettings;
+use mpv::options::MpvOptions;
+use mpv::repeat::RepeatMode;
+use mpv::replaygain::ReplayGainSettings;
+use mpv::sleep_timer::SleepTimerSettings;
+use mpv::speed::PitchCorrection;
+use mpv::volume::VolumeSettings;
+use mpv::{events::PlayerEvent, AudioDevice, MpvPlayer, PlaylistEntry};
 
 pub mod discord_rpc;
-use discord_rpc::DiscordClient;
+use discord_rpc::{DiscordClient, Presence};
+
+// The last status the backend emitted, readable without waiting on the backend lock
+#[derive(Default)]
+struct StatusCache(Mutex<Option<mpv::MpvStatus>>);
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let mpv_player = Arc::new(Mutex::new(MpvPlayer::new()));
+    let mpv_player: SharedBackend =
+        Arc::new(Mutex::new(Box::new(MpvPlayer::new()) as Box<dyn Backend>));
     let discord_client = Arc::new(Mutex::new(DiscordClient::new()));
 
     let builder = tauri::Builder::default()
         .plugin(tauri_plugin_http::init())
         .manage(mpv_player.clone())
         .manage(discord_client.clone())
+        .manage(StatusCache::default())
         .invoke_handler(tauri::generate_handler![
             mpv_init,
             mpv_get_status,
@@ -36,13 +56,51 @@ pub fn run() {
             mpv_seek,
             mpv_seek_precise,
             mpv_set_volume,
+            mpv_set_mute,
+            mpv_set_volume_settings,
             mpv_load_playlist,
             mpv_load_playlist_optimized,
+            mpv_sync_playlist,
+            mpv_get_playlist,
             mpv_set_playlist_position,
             mpv_playlist_next,
             mpv_playlist_prev,
             mpv_playlist_play_index,
             mpv_playlist_jump_to_index,
+            mpv_playlist_insert,
+            mpv_playlist_move,
+            mpv_playlist_remove,
+            mpv_playlist_clear_after_current,
+            mpv_get_chapters,
+            mpv_chapter_next,
+            mpv_chapter_prev,
+            mpv_set_chapter,
+            mpv_set_repeat,
+            mpv_set_ab_loop,
+            mpv_set_sleep_timer,
+            mpv_cancel_sleep_timer,
+            mpv_set_speed,
+            mpv_set_pitch,
+            mpv_set_replaygain,
+            mpv_set_normalization,
+            mpv_get_audio_devices,
+            mpv_set_audio_device,
+            mpv_set_equalizer,
+            mpv_get_equalizer,
+            mpv_list_equalizer_presets,
+            mpv_load_equalizer_preset,
+            mpv_save_equalizer_preset,
+            mpv_delete_equalizer_preset,
+            mpv_get_equalizer_genre_presets,
+            mpv_set_equalizer_genre_presets,
+            mpv_set_crossfade,
+            mpv_set_options,
+            mpv_get_property,
+            mpv_set_property,
+            mpv_observe_property,
+            mpv_unobserve_property,
+            mpv_get_logs,
+            mpv_set_log_level,
             update_rpc
         ]);
 
@@ -64,6 +122,16 @@ pub fn run() {
                 let _ = window.restore_state(StateFlags::all());
             }))
             .setup(|app| {
+                let presets = PresetStore::new(app.path().app_config_dir()?.join("equalizer"));
+                let backend = app.state::<SharedBackend>();
+                if let Err(e) = apply_genre_presets(&presets, &backend) {
+                    backend.lock().unwrap().report(
+                        LogLevel::Error,
+                        &format!("Failed to load genre equalizer presets: {}", e),
+                    );
+                }
+                app.manage(presets);
+
                 if let Some(discord_state) = app.try_state::<Arc<Mutex<DiscordClient>>>() {
                     if let Ok(mut client) = discord_state.lock() {
                         client.initialize();
@@ -86,7 +154,7 @@ pub fn run() {
                             }
                         }
                         "quit" => {
-                            let mpv_state = app.state::<Arc<Mutex<MpvPlayer>>>();
+                            let mpv_state = app.state::<SharedBackend>();
                             if let Ok(mut player) = mpv_state.lock() {
                                 player.shutdown();
                             }
@@ -102,19 +170,19 @@ pub fn run() {
                         }
                         _ => {}
                     })
-                    .on_tray_icon_event(|tray, event| match event {
-                        TrayIconEvent::Click {
+                    .on_tray_icon_event(|tray, event| {
+                        if let TrayIconEvent::Click {
                             button: MouseButton::Left,
                             button_state: MouseButtonState::Up,
                             ..
-                        } => {
+                        } = event
+                        {
                             let app = tray.app_handle();
                             if let Some(window) = app.get_webview_window("main") {
                                 let _ = window.show();
                                 let _ = window.set_focus();
                             }
                         }
-                        _ => {}
                     })
                     .build(app)?;
 
@@ -132,7 +200,7 @@ pub fn run() {
                 }
                 tauri::WindowEvent::Destroyed => {
                     let app_handle = window.app_handle();
-                    if let Some(state) = app_handle.try_state::<Arc<Mutex<MpvPlayer>>>() {
+                    if let Some(state) = app_handle.try_state::<SharedBackend>() {
                         let player_ref = state.clone();
                         let _ = player_ref.lock().map(|mut player| player.shutdown());
                     }
@@ -161,63 +229,87 @@ pub fn run() {
 
 #[tauri::command]
 fn mpv_init(custom_path: Option<String>, app_handle: tauri::AppHandle) -> Result<bool, String> {
-    let state = app_handle.state::<Arc<Mutex<MpvPlayer>>>();
+    let state = app_handle.state::<SharedBackend>();
     let mut player = state.lock().unwrap();
+    let emitter = app_handle.clone();
+    player.set_event_sink(Arc::new(move |event: PlayerEvent| {
+        if let PlayerEvent::Status(status) = &event {
+            *emitter.state::<StatusCache>().0.lock().unwrap() = Some((**status).clone());
+        }
+        let _ = emitter.emit(event.name(), event);
+    }));
     player.init(custom_path)
 }
 
 #[tauri::command]
 fn mpv_get_status(app_handle: tauri::AppHandle) -> Result<mpv::MpvStatus, String> {
-    let state = app_handle.state::<Arc<Mutex<MpvPlayer>>>();
+    let state = app_handle.state::<SharedBackend>();
     let player = state.lock().unwrap();
     Ok(player.get_status())
 }
 
 #[tauri::command]
 fn mpv_load(url: String, app_handle: tauri::AppHandle) -> Result<(), String> {
-    let state = app_handle.state::<Arc<Mutex<MpvPlayer>>>();
+    let state = app_handle.state::<SharedBackend>();
     let mut player = state.lock().unwrap();
     player.load(&url)
 }
 
 #[tauri::command]
 fn mpv_play(app_handle: tauri::AppHandle) -> Result<(), String> {
-    let state = app_handle.state::<Arc<Mutex<MpvPlayer>>>();
+    let state = app_handle.state::<SharedBackend>();
     let mut player = state.lock().unwrap();
     player.play()
 }
 
 #[tauri::command]
 fn mpv_pause(app_handle: tauri::AppHandle) -> Result<(), String> {
-    let state = app_handle.state::<Arc<Mutex<MpvPlayer>>>();
+    let state = app_handle.state::<SharedBackend>();
     let mut player = state.lock().unwrap();
     player.pause()
 }
 
 #[tauri::command]
 fn mpv_stop(app_handle: tauri::AppHandle) -> Result<(), String> {
-    let state = app_handle.state::<Arc<Mutex<MpvPlayer>>>();
+    let state = app_handle.state::<SharedBackend>();
     let mut player = state.lock().unwrap();
     player.stop()
 }
 
 #[tauri::command]
 fn mpv_seek(position: f64, app_handle: tauri::AppHandle) -> Result<(), String> {
-    let state = app_handle.state::<Arc<Mutex<MpvPlayer>>>();
+    let state = app_handle.state::<SharedBackend>();
     let mut player = state.lock().unwrap();
     player.seek(position)
 }
 
 #[tauri::command]
 fn mpv_set_volume(volume: f64, app_handle: tauri::AppHandle) -> Result<(), String> {
-    let state = app_handle.state::<Arc<Mutex<MpvPlayer>>>();
+    let state = app_handle.state::<SharedBackend>();
     let mut player = state.lock().unwrap();
     player.set_volume(volume)
 }
 
+#[tauri::command]
+fn mpv_set_mute(muted: bool, app_handle: tauri::AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.set_mute(muted)
+}
+
+#[tauri::command]
+fn mpv_set_volume_settings(
+    settings: VolumeSettings,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.set_volume_settings(settings)
+}
+
 #[tauri::command]
 fn mpv_load_playlist(urls: Vec<String>, app_handle: tauri::AppHandle) -> Result<(), String> {
-    let state = app_handle.state::<Arc<Mutex<MpvPlayer>>>();
+    let state = app_handle.state::<SharedBackend>();
     let mut player = state.lock().unwrap();
     player.load_playlist(urls)
 }
@@ -227,72 +319,387 @@ fn mpv_load_playlist_optimized(
     urls: Vec<String>,
     app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
-    let state = app_handle.state::<Arc<Mutex<MpvPlayer>>>();
+    let state = app_handle.state::<SharedBackend>();
     let mut player = state.lock().unwrap();
     player.load_playlist_optimized(urls)
 }
 
 #[tauri::command]
 fn mpv_set_playlist_position(index: usize, app_handle: tauri::AppHandle) -> Result<(), String> {
-    let state = app_handle.state::<Arc<Mutex<MpvPlayer>>>();
+    let state = app_handle.state::<SharedBackend>();
     let mut player = state.lock().unwrap();
     player.set_playlist_position(index)
 }
 
 #[tauri::command]
 fn mpv_playlist_next(app_handle: tauri::AppHandle) -> Result<(), String> {
-    let state = app_handle.state::<Arc<Mutex<MpvPlayer>>>();
+    let state = app_handle.state::<SharedBackend>();
     let mut player = state.lock().unwrap();
     player.playlist_next()
 }
 
 #[tauri::command]
 fn mpv_playlist_prev(app_handle: tauri::AppHandle) -> Result<(), String> {
-    let state = app_handle.state::<Arc<Mutex<MpvPlayer>>>();
+    let state = app_handle.state::<SharedBackend>();
     let mut player = state.lock().unwrap();
     player.playlist_prev()
 }
 
 #[tauri::command]
 fn mpv_playlist_play_index(index: usize, app_handle: tauri::AppHandle) -> Result<(), String> {
-    let state = app_handle.state::<Arc<Mutex<MpvPlayer>>>();
+    let state = app_handle.state::<SharedBackend>();
     let mut player = state.lock().unwrap();
     player.playlist_play_index(index)
 }
 
 #[tauri::command]
 fn mpv_playlist_jump_to_index(index: usize, app_handle: tauri::AppHandle) -> Result<(), String> {
-    let state = app_handle.state::<Arc<Mutex<MpvPlayer>>>();
+    let state = app_handle.state::<SharedBackend>();
     let mut player = state.lock().unwrap();
     player.playlist_jump_to_index(index)
 }
 
 #[tauri::command]
-fn mpv_seek_precise(position: f64, app_handle: tauri::AppHandle) -> Result<(), String> {
-    let state = app_handle.state::<Arc<Mutex<MpvPlayer>>>();
+fn mpv_sync_playlist(
+    urls: Vec<String>,
+    current_index: usize,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
     let mut player = state.lock().unwrap();
-    player.seek(position)
+    player.sync_playlist(urls, current_index)
+}
+
+#[tauri::command]
+fn mpv_get_playlist(app_handle: tauri::AppHandle) -> Result<Vec<PlaylistEntry>, String> {
+    let state = app_handle.state::<SharedBackend>();
+    let player = state.lock().unwrap();
+    player.playlist_entries()
 }
 
-#[derive(Deserialize, Debug)]
-struct DiscordRPC {
-    app_id: Option<String>,
-    details: Option<String>,
-    state: Option<String>,
-    large_image: Option<String>,
-    small_image: Option<String>,
-    start_time: Option<String>,
-    end_time: Option<String>,
+#[tauri::command]
+fn mpv_playlist_insert(
+    url: String,
+    index: usize,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.playlist_insert(&url, index)
+}
+
+#[tauri::command]
+fn mpv_playlist_move(from: usize, to: usize, app_handle: tauri::AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.playlist_move(from, to)
+}
+
+#[tauri::command]
+fn mpv_playlist_remove(index: usize, app_handle: tauri::AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.playlist_remove(index)
+}
+
+#[tauri::command]
+fn mpv_playlist_clear_after_current(app_handle: tauri::AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.playlist_clear_after_current()
+}
+
+#[tauri::command]
+fn mpv_get_chapters(app_handle: tauri::AppHandle) -> Result<Vec<Chapter>, String> {
+    let state = app_handle.state::<SharedBackend>();
+    let player = state.lock().unwrap();
+    player.chapters()
+}
+
+#[tauri::command]
+fn mpv_chapter_next(app_handle: tauri::AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.chapter_next()
 }
 
 #[tauri::command]
-async fn update_rpc(rpc: DiscordRPC, app_handle: tauri::AppHandle) -> Result<(), String> {
-    let should_die = rpc.details.is_none()
-        && rpc.state.is_none()
-        && rpc.large_image.is_none()
-        && rpc.small_image.is_none()
-        && rpc.start_time.is_none()
-        && rpc.end_time.is_none();
+fn mpv_chapter_prev(app_handle: tauri::AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.chapter_prev()
+}
+
+#[tauri::command]
+fn mpv_set_chapter(index: usize, app_handle: tauri::AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.set_chapter(index)
+}
+
+#[tauri::command]
+fn mpv_set_repeat(mode: RepeatMode, app_handle: tauri::AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.set_repeat(mode)
+}
+
+#[tauri::command]
+fn mpv_set_ab_loop(
+    a: Option<f64>,
+    b: Option<f64>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.set_ab_loop(a, b)
+}
+
+#[tauri::command]
+fn mpv_set_sleep_timer(
+    settings: SleepTimerSettings,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.set_sleep_timer(settings)
+}
+
+#[tauri::command]
+fn mpv_cancel_sleep_timer(app_handle: tauri::AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.cancel_sleep_timer()
+}
+
+#[tauri::command]
+fn mpv_set_speed(
+    speed: f64,
+    pitch_correction: Option<PitchCorrection>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.set_speed(speed, pitch_correction.unwrap_or_default())
+}
+
+#[tauri::command]
+fn mpv_set_pitch(semitones: f64, app_handle: tauri::AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.set_pitch(semitones)
+}
+
+#[tauri::command]
+fn mpv_set_replaygain(
+    settings: ReplayGainSettings,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.set_replaygain(settings)
+}
+
+#[tauri::command]
+fn mpv_set_normalization(
+    settings: NormalizationSettings,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.set_normalization(settings)
+}
+
+#[tauri::command]
+fn mpv_get_audio_devices(app_handle: tauri::AppHandle) -> Result<Vec<AudioDevice>, String> {
+    let state = app_handle.state::<SharedBackend>();
+    let player = state.lock().unwrap();
+    player.audio_devices()
+}
+
+#[tauri::command]
+fn mpv_set_audio_device(name: String, app_handle: tauri::AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.set_audio_device(&name)
+}
+
+#[tauri::command]
+fn mpv_set_equalizer(
+    settings: EqualizerSettings,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.set_equalizer(settings)
+}
+
+#[tauri::command]
+fn mpv_get_equalizer(app_handle: tauri::AppHandle) -> Result<EqualizerSettings, String> {
+    let state = app_handle.state::<SharedBackend>();
+    let player = state.lock().unwrap();
+    Ok(player.equalizer())
+}
+
+#[tauri::command]
+fn mpv_list_equalizer_presets(app_handle: tauri::AppHandle) -> Result<Vec<String>, String> {
+    preset_store(&app_handle)?.list()
+}
+
+#[tauri::command]
+fn mpv_load_equalizer_preset(
+    name: String,
+    app_handle: tauri::AppHandle,
+) -> Result<EqualizerSettings, String> {
+    let settings = preset_store(&app_handle)?.load(&name)?;
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.set_equalizer(settings.clone())?;
+    Ok(settings)
+}
+
+#[tauri::command]
+fn mpv_save_equalizer_preset(
+    name: String,
+    settings: EqualizerSettings,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let presets = preset_store(&app_handle)?;
+    presets.save(&name, &settings)?;
+    apply_genre_presets(&presets, &app_handle.state::<SharedBackend>())
+}
+
+#[tauri::command]
+fn mpv_delete_equalizer_preset(name: String, app_handle: tauri::AppHandle) -> Result<(), String> {
+    let presets = preset_store(&app_handle)?;
+    presets.delete(&name)?;
+    apply_genre_presets(&presets, &app_handle.state::<SharedBackend>())
+}
+
+#[tauri::command]
+fn mpv_get_equalizer_genre_presets(
+    app_handle: tauri::AppHandle,
+) -> Result<BTreeMap<String, String>, String> {
+    preset_store(&app_handle)?.genre_presets()
+}
+
+#[tauri::command]
+fn mpv_set_equalizer_genre_presets(
+    presets: BTreeMap<String, String>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let store = preset_store(&app_handle)?;
+    for (genre, preset) in &presets {
+        store
+            .load(preset)
+            .map_err(|e| format!("Genre '{}': {}", genre, e))?;
+    }
+    store.set_genre_presets(&presets)?;
+    apply_genre_presets(&store, &app_handle.state::<SharedBackend>())
+}
+
+fn preset_store(app_handle: &tauri::AppHandle) -> Result<tauri::State<'_, PresetStore>, String> {
+    app_handle
+        .try_state::<PresetStore>()
+        .ok_or_else(|| "Equalizer presets are not available".to_string())
+}
+
+// Genre presets are resolved up front, so the backend never touches the disk
+fn apply_genre_presets(presets: &PresetStore, backend: &SharedBackend) -> Result<(), String> {
+    let (resolved, skipped) = presets.resolve_genre_presets()?;
+    let mut backend = backend.lock().unwrap();
+    for message in skipped {
+        backend.report(LogLevel::Warn, &message);
+    }
+    backend.set_genre_equalizers(resolved)
+}
+
+#[tauri::command]
+fn mpv_set_crossfade(
+    settings: CrossfadeSettings,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.set_crossfade(settings)
+}
+
+#[tauri::command]
+fn mpv_set_options(options: MpvOptions, app_handle: tauri::AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.set_mpv_options(options)
+}
+
+#[tauri::command]
+fn mpv_get_property(
+    name: String,
+    app_handle: tauri::AppHandle,
+) -> Result<serde_json::Value, String> {
+    let state = app_handle.state::<SharedBackend>();
+    let player = state.lock().unwrap();
+    player.get_property(&name)
+}
+
+#[tauri::command]
+fn mpv_set_property(
+    name: String,
+    value: serde_json::Value,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.set_property(&name, value)
+}
+
+#[tauri::command]
+fn mpv_observe_property(name: String, app_handle: tauri::AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.observe_property(&name)
+}
+
+#[tauri::command]
+fn mpv_unobserve_property(name: String, app_handle: tauri::AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.unobserve_property(&name)
+}
+
+#[tauri::command]
+fn mpv_get_logs(app_handle: tauri::AppHandle) -> Vec<LogEntry> {
+    let state = app_handle.state::<SharedBackend>();
+    let player = state.lock().unwrap();
+    player.logs()
+}
+
+#[tauri::command]
+fn mpv_set_log_level(level: LogLevel, app_handle: tauri::AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.set_log_level(level)
+}
+
+#[tauri::command]
+fn mpv_seek_precise(position: f64, app_handle: tauri::AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<SharedBackend>();
+    let mut player = state.lock().unwrap();
+    player.seek(position)
+}
+
+#[tauri::command]
+async fn update_rpc(mut rpc: Presence, app_handle: tauri::AppHandle) -> Result<(), String> {
+    let should_die = rpc.is_empty();
+
+    if rpc.speed.is_none() {
+        if let Some(cache) = app_handle.try_state::<StatusCache>() {
+            let status = cache.0.lock().unwrap();
+            rpc.speed = status
+                .as_ref()
+                .filter(|status| status.initialized)
+                .map(|status| status.speed);
+        }
+    }
 
     if let Some(discord_state) = app_handle.try_state::<Arc<Mutex<DiscordClient>>>() {
         if let Ok(mut client) = discord_state.lock() {
@@ -302,15 +709,7 @@ async fn update_rpc(rpc: DiscordRPC, app_handle: tauri::AppHandle) -> Result<(),
             } else {
                 client.initialize();
 
-                return client.update_presence(
-                    rpc.app_id,
-                    rpc.details,
-                    rpc.state,
-                    rpc.large_image,
-                    rpc.small_image,
-                    rpc.start_time,
-                    rpc.end_time,
-                );
+                return client.update_presence(rpc);
             }
         }
     }
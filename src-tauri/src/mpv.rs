@@ -1,36 +1,506 @@
+pub mod cache;
+pub mod chapters;
+pub mod crossfade;
+mod crossfader;
+pub mod equalizer;
+pub mod events;
+mod filters;
+mod ipc;
+pub mod logs;
+pub mod loudness;
+pub mod options;
+pub mod playlist_sync;
+pub mod properties;
+pub mod repeat;
+pub mod replaygain;
+mod session;
+pub mod sleep_timer;
+mod sleep_watcher;
+pub mod speed;
+pub mod stream_info;
+mod supervisor;
+pub mod volume;
+
+use crate::backend::{AudioEffects, MpvExtensions, PlaybackBackend};
+use cache::CacheStatus;
+use chapters::Chapter;
+use crossfade::CrossfadeSettings;
+use crossfader::Crossfader;
+use equalizer::{EqualizerSettings, EqualizerState};
+use events::{
+    AudioDevicesChange, BufferingChange, EqualizerChange, EventSink, PlaybackEnded, PlaybackError,
+    PlayerEvent, PlayerEvents, PlaylistChange, PropertyChange, TrackChange,
+};
+use ipc::{EventHandler, MpvIpc, MpvResponse};
+use logs::{LogBuffer, LogEntry, LogHandler, LogLevel};
+use loudness::{NormalizationMode, NormalizationSettings};
+use options::MpvOptions;
+use playlist_sync::PlaylistEdit;
+use properties::PropertyAllowlist;
+use repeat::RepeatMode;
+use replaygain::{ReplayGainMode, ReplayGainSettings, ReplayGainTags};
 use serde::{Deserialize, Serialize};
-use std::io::{BufRead, BufReader, Write};
-use std::process::{Child, Command, Stdio};
+use session::{LaunchConfig, MpvSession, DEFAULT_STARTUP_TIMEOUT};
+use sleep_timer::{SleepTimer, SleepTimerSettings, SleepTimerStatus};
+use sleep_watcher::SleepWatcher;
+use speed::{PitchCorrection, SpeedSettings};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use stream_info::StreamInfo;
+use supervisor::Supervisor;
 use uuid::Uuid;
-
-#[cfg(target_os = "windows")]
-use named_pipe::PipeClient;
-#[cfg(unix)]
-use std::os::unix::net::UnixStream;
-#[cfg(target_os = "windows")]
-use std::os::windows::process::CommandExt;
-#[cfg(unix)]
-use std::path::Path;
-#[cfg(target_os = "windows")]
-const CREATE_NO_WINDOW: u32 = 0x08000000;
+use volume::VolumeSettings;
+
+const STATUS_PROPERTIES: [&str; 9] = [
+    "time-pos",
+    "path",
+    "duration",
+    "media-title",
+    "pause",
+    "chapter",
+    "chapter-list/count",
+    "playlist-pos",
+    "playlist-count",
+];
+// Only fetched when catching up with a session, they change along with the file
+const STREAM_PROPERTIES: [&str; 8] = [
+    "audio-params",
+    "audio-codec-name",
+    "audio-bitrate",
+    "file-format",
+    "metadata",
+    "cache-buffering-state",
+    "demuxer-cache-duration",
+    "demuxer-cache-state",
+];
+const STATUS_FETCH_TIMEOUT: Duration = Duration::from_millis(500);
+
+const OBSERVED_PROPERTIES: [&str; 32] = [
+    "time-pos",
+    "duration",
+    "pause",
+    "eof-reached",
+    "volume",
+    "mute",
+    "playlist-pos",
+    "playlist-count",
+    "media-title",
+    "playback-time",
+    "chapter",
+    "chapter-list/count",
+    "chapter-list",
+    "path",
+    "audio-device",
+    "audio-device-list",
+    "current-tracks/audio",
+    "metadata/by-key/genre",
+    "speed",
+    "loop-file",
+    "loop-playlist",
+    "ab-loop-a",
+    "ab-loop-b",
+    "audio-params",
+    "audio-codec-name",
+    "audio-bitrate",
+    "file-format",
+    "metadata",
+    "paused-for-cache",
+    "cache-buffering-state",
+    "demuxer-cache-duration",
+    "demuxer-cache-state",
+];
+
+// Observations made for the webview are numbered apart from the player's own
+const USER_OBSERVE_ID_BASE: i64 = 1000;
 
 pub struct MpvPlayer {
-    process: Option<Child>,
+    shared: SharedState,
+    supervisor: Option<Supervisor>,
+    crossfader: Option<Crossfader>,
+    sleep_watcher: Option<SleepWatcher>,
+    options: MpvOptions,
+    property_allowlist: PropertyAllowlist,
+    next_observe_id: i64,
+    startup_timeout: Duration,
+}
+
+#[derive(Clone)]
+struct SharedState {
+    session: Arc<Mutex<Option<MpvSession>>>,
     status: Arc<Mutex<MpvStatus>>,
-    ipc_thread: Option<thread::JoinHandle<()>>,
-    ipc_path: Arc<Mutex<String>>,
-    request_counter: Arc<Mutex<i32>>,
+    events: PlayerEvents,
+    playlist: Arc<Mutex<Vec<String>>>,
+    audio_device: Arc<Mutex<Option<String>>>,
+    chapters: Arc<Mutex<Vec<Chapter>>>,
+    // Properties the webview observes, by observation id
+    observed: Arc<Mutex<BTreeMap<String, i64>>>,
+    logs: Arc<Mutex<LogBuffer>>,
+    log_level: Arc<Mutex<LogLevel>>,
+    replaygain: Arc<Mutex<ReplayGainSettings>>,
+    replaygain_tags: Arc<Mutex<Option<ReplayGainTags>>>,
+    normalization: Arc<Mutex<NormalizationSettings>>,
+    loudness_filter: Arc<Mutex<Option<String>>>,
+    repeat: Arc<Mutex<RepeatMode>>,
+    equalizer: Arc<Mutex<EqualizerState>>,
+    speed: Arc<Mutex<SpeedSettings>>,
+    pitch_filter: Arc<Mutex<Option<String>>>,
+    volume: Arc<Mutex<VolumeSettings>>,
+    crossfade: Arc<Mutex<CrossfadeSettings>>,
+    // Set while the crossfader drives the live instance's volume
+    fading: Arc<AtomicBool>,
+    sleep_timer: Arc<Mutex<Option<SleepTimer>>>,
+    // Set while the sleep timer fades the volume out
+    sleep_fading: Arc<AtomicBool>,
+    // Told about every loaded file while the supervisor restores a restarted mpv
+    file_loaded: Arc<Mutex<Option<Sender<()>>>>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+// Filters set up on a standby instance, they count as installed once it goes live
+struct StandbyFilters {
+    equalizer: Option<EqualizerSettings>,
+    pitch: Option<String>,
+    loudness: Option<String>,
+}
+
+impl SharedState {
+    fn apply_replaygain(&self, ipc: &MpvIpc) -> Result<(), String> {
+        let settings = self.replaygain_settings();
+        for (property, value) in settings.properties() {
+            ipc.set_property(property, value)?;
+        }
+        Ok(())
+    }
+
+    fn update_replaygain_gain(&self) {
+        let gain = {
+            let settings = self.replaygain_settings();
+            let tags = self.replaygain_tags.lock().unwrap();
+            settings.effective_gain(tags.as_ref())
+        };
+        self.status.lock().unwrap().replaygain_gain = gain;
+        self.events.status_changed();
+    }
+
+    // Normalization replaces ReplayGain while it's on, the ReplayGain settings come back after
+    fn replaygain_settings(&self) -> ReplayGainSettings {
+        let settings = self.replaygain.lock().unwrap().clone();
+        if self.normalization.lock().unwrap().is_active() {
+            return ReplayGainSettings {
+                mode: ReplayGainMode::Off,
+                ..settings
+            };
+        }
+        settings
+    }
+
+    fn apply_normalization(&self, ipc: &MpvIpc) -> Result<(), String> {
+        let filter = self.normalization.lock().unwrap().filter();
+        let installed = self.loudness_filter.lock().unwrap().clone();
+        if installed == filter {
+            return Ok(());
+        }
+
+        if installed.is_some() {
+            ipc.command(filters::remove(loudness::FILTER_LABEL))?;
+            *self.loudness_filter.lock().unwrap() = None;
+        }
+        if let Some(filter) = &filter {
+            ipc.command(filters::add(filter))?;
+        }
+        *self.loudness_filter.lock().unwrap() = filter;
+        Ok(())
+    }
+
+    fn apply_repeat(&self, ipc: &MpvIpc) -> Result<(), String> {
+        let mode = *self.repeat.lock().unwrap();
+        for (property, value) in mode.properties() {
+            ipc.set_property(property, value)?;
+        }
+        Ok(())
+    }
+
+    // mpv's volume for a slider level under the current curve
+    fn mpv_volume(&self, level: f64) -> f64 {
+        self.volume.lock().unwrap().to_mpv(level)
+    }
+
+    fn apply_volume(&self, ipc: &MpvIpc) -> Result<(), String> {
+        let (level, muted) = {
+            let status = self.status.lock().unwrap();
+            (status.volume, status.muted)
+        };
+        let volume_max = self.volume.lock().unwrap().volume_max();
+        ipc.set_property("volume-max", volume_max)?;
+        ipc.set_property("volume", self.mpv_volume(level))?;
+        ipc.set_property("mute", muted)
+    }
+
+    fn apply_observed(&self, ipc: &MpvIpc) -> Result<(), String> {
+        let observed = self.observed.lock().unwrap().clone();
+        for (name, id) in observed {
+            if OBSERVED_PROPERTIES.contains(&name.as_str()) {
+                continue;
+            }
+            // Re-observing under the same id would report every change twice
+            let _ = ipc.unobserve_property(id);
+            ipc.observe_property(id, &name)?;
+        }
+        Ok(())
+    }
+
+    fn apply_log_level(&self, ipc: &MpvIpc) -> Result<(), String> {
+        MpvSession::request_log_messages(ipc, *self.log_level.lock().unwrap())
+    }
+
+    fn record_log(&self, entry: LogEntry) {
+        self.logs.lock().unwrap().push(entry.clone());
+        self.events.log(entry);
+    }
+
+    fn log(&self, level: LogLevel, text: &str) {
+        self.record_log(LogEntry::player(level, text));
+    }
+
+    fn apply_equalizer(&self, ipc: &MpvIpc) -> Result<(), String> {
+        // Not held across commands, the IPC thread needs it to handle genre changes
+        let (settings, installed) = {
+            let state = self.equalizer.lock().unwrap();
+            (state.active().clone(), state.installed().cloned())
+        };
+        let Some(installed) = installed else {
+            if !settings.enabled {
+                return Ok(());
+            }
+            return self.install_equalizer(ipc, settings, false);
+        };
+
+        if installed.layout() == settings.layout() {
+            let live = settings
+                .gain_commands()
+                .into_iter()
+                .try_for_each(|command| ipc.command(command).map(|_| ()));
+            if live.is_ok() {
+                // The chain is rebuilt from the af option for every file, so that lags behind now
+                self.equalizer
+                    .lock()
+                    .unwrap()
+                    .set_stale(settings != installed);
+                return Ok(());
+            }
+        }
+        self.install_equalizer(ipc, settings, true)
+    }
+
+    fn refresh_equalizer(&self, ipc: &MpvIpc) -> Result<(), String> {
+        let settings = {
+            let state = self.equalizer.lock().unwrap();
+            if !state.is_stale() {
+                return Ok(());
+            }
+            state.active().clone()
+        };
+        self.install_equalizer(ipc, settings, true)
+    }
+
+    fn install_equalizer(
+        &self,
+        ipc: &MpvIpc,
+        settings: EqualizerSettings,
+        replace: bool,
+    ) -> Result<(), String> {
+        if replace {
+            ipc.command(filters::remove(equalizer::FILTER_LABEL))?;
+            self.equalizer.lock().unwrap().set_installed(None);
+        }
+        ipc.command(filters::add(&settings.filter()))?;
+        self.equalizer.lock().unwrap().set_installed(Some(settings));
+        Ok(())
+    }
+
+    fn apply_speed(&self, ipc: &MpvIpc) -> Result<(), String> {
+        let settings = self.speed.lock().unwrap().clone();
+        let filter = settings.filter();
+        let installed = self.pitch_filter.lock().unwrap().clone();
+
+        if installed != filter {
+            if installed.is_some() {
+                ipc.command(filters::remove(speed::FILTER_LABEL))?;
+                *self.pitch_filter.lock().unwrap() = None;
+            }
+            if let Some(filter) = &filter {
+                ipc.command(filters::add(filter))?;
+            }
+            *self.pitch_filter.lock().unwrap() = filter;
+        }
+        for (property, value) in settings.properties() {
+            ipc.set_property(property, value)?;
+        }
+        Ok(())
+    }
+
+    // Mirrors the live instance's audio setup on a standby one
+    fn prepare_standby(&self, ipc: &MpvIpc) -> Result<StandbyFilters, String> {
+        self.apply_replaygain(ipc)?;
+        ipc.command(filters::clear())?;
+
+        let equalizer = {
+            let state = self.equalizer.lock().unwrap();
+            (state.active().enabled || state.installed().is_some()).then(|| state.active().clone())
+        };
+        if let Some(settings) = &equalizer {
+            ipc.command(filters::add(&settings.filter()))?;
+        }
+
+        let speed = self.speed.lock().unwrap().clone();
+        let pitch_filter = speed.filter();
+        if let Some(filter) = &pitch_filter {
+            ipc.command(filters::add(filter))?;
+        }
+        for (property, value) in speed.properties() {
+            ipc.set_property(property, value)?;
+        }
+
+        self.apply_repeat(ipc)?;
+        self.apply_observed(ipc)?;
+
+        // The crossfader owns the standby's volume, only its limit and mute carry over
+        let volume_max = self.volume.lock().unwrap().volume_max();
+        ipc.set_property("volume-max", volume_max)?;
+        let muted = self.status.lock().unwrap().muted;
+        ipc.set_property("mute", muted)?;
+
+        let loudness_filter = self.normalization.lock().unwrap().filter();
+        if let Some(filter) = &loudness_filter {
+            ipc.command(filters::add(filter))?;
+        }
+
+        let device = self.status.lock().unwrap().audio_device.clone();
+        if let Some(device) = device {
+            ipc.set_property("audio-device", device)?;
+        }
+        Ok(StandbyFilters {
+            equalizer,
+            pitch: pitch_filter,
+            loudness: loudness_filter,
+        })
+    }
+
+    fn adopt_standby_filters(&self, filters: &StandbyFilters) {
+        self.equalizer
+            .lock()
+            .unwrap()
+            .set_installed(filters.equalizer.clone());
+        *self.pitch_filter.lock().unwrap() = filters.pitch.clone();
+        *self.loudness_filter.lock().unwrap() = filters.loudness.clone();
+    }
+
+    // Catches up with a session whose events weren't driving the player until now
+    fn refresh_status(&self, ipc: &MpvIpc) {
+        let fetched: Vec<_> = STATUS_PROPERTIES
+            .iter()
+            .chain(STREAM_PROPERTIES.iter())
+            .filter_map(|prop| {
+                ipc.get_property::<serde_json::Value>(prop)
+                    .ok()
+                    .map(|value| (*prop, value))
+            })
+            .collect();
+        let tags = ipc
+            .get_property::<ReplayGainTags>("current-tracks/audio")
+            .ok();
+        let genre = ipc.get_property::<String>("metadata/by-key/genre").ok();
+        let chapters = ipc
+            .get_property::<Vec<Chapter>>("chapter-list")
+            .unwrap_or_default();
+
+        let change = {
+            let mut status = self.status.lock().unwrap();
+            for (prop, value) in &fetched {
+                MpvPlayer::apply_property(&mut status, prop, value);
+            }
+            TrackChange {
+                playlist_pos: status.playlist_pos,
+                playlist_count: status.playlist_count,
+            }
+        };
+        *self.replaygain_tags.lock().unwrap() = tags;
+        self.update_replaygain_gain();
+        self.chapters_changed(chapters);
+        MpvPlayer::genre_changed(self, genre);
+        self.events.emit(PlayerEvent::TrackChanged(change));
+    }
+
+    // The entries stay the same when another mpv instance takes the playlist over
+    fn playlist_changed(&self) {
+        let entries = self.playlist.lock().unwrap().clone();
+        let playlist_pos = self.status.lock().unwrap().playlist_pos;
+        self.events
+            .emit(PlayerEvent::PlaylistChanged(PlaylistChange {
+                entries,
+                playlist_pos,
+            }));
+    }
+
+    fn chapters_changed(&self, chapters: Vec<Chapter>) {
+        {
+            let mut status = self.status.lock().unwrap();
+            status.chapter_title = chapters::title(&chapters, status.chapter);
+        }
+        *self.chapters.lock().unwrap() = chapters;
+        self.events.status_changed();
+    }
+
+    fn equalizer_changed(&self) {
+        let change = {
+            let state = self.equalizer.lock().unwrap();
+            EqualizerChange {
+                settings: state.active().clone(),
+                genre: state.active_genre(),
+            }
+        };
+        self.events.emit(PlayerEvent::EqualizerChanged(change));
+    }
+
+    fn ipc(&self) -> Result<Arc<MpvIpc>, String> {
+        self.session
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|session| Arc::clone(&session.ipc))
+            .ok_or_else(|| "MPV IPC not initialized".to_string())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AudioDevice {
+    pub name: String,
+    pub description: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PlaylistEntry {
+    pub id: Option<i64>,
+    pub filename: String,
+    pub title: Option<String>,
+    #[serde(default)]
+    pub current: bool,
+    #[serde(default)]
+    pub playing: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct MpvStatus {
     pub initialized: bool,
     pub position: f64,
     pub duration: f64,
     pub volume: f64,
+    // Highest volume the slider can reach, above 1 while boost is on
+    pub max_volume: f64,
+    pub muted: bool,
     pub state: String,
     pub playlist_pos: i32,
     pub playlist_count: i32,
@@ -39,6 +509,19 @@ pub struct MpvStatus {
     pub pause: Option<bool>,
     pub chapter: Option<i32>,
     pub chapter_count: Option<i32>,
+    pub chapter_title: Option<String>,
+    pub current_url: Option<String>,
+    pub audio_device: Option<String>,
+    pub replaygain_gain: Option<f64>,
+    pub normalization: NormalizationMode,
+    pub repeat: RepeatMode,
+    pub ab_loop_a: Option<f64>,
+    pub ab_loop_b: Option<f64>,
+    pub sleep_timer: Option<SleepTimerStatus>,
+    pub stream: StreamInfo,
+    pub cache: CacheStatus,
+    pub speed: f64,
+    pub pitch: f64,
 }
 
 impl Default for MpvStatus {
@@ -48,6 +531,8 @@ impl Default for MpvStatus {
             position: 0.0,
             duration: 0.0,
             volume: 1.0,
+            max_volume: 1.0,
+            muted: false,
             state: "idle".to_string(),
             playlist_pos: -1,
             playlist_count: 0,
@@ -56,122 +541,66 @@ impl Default for MpvStatus {
             pause: None,
             chapter: None,
             chapter_count: None,
+            chapter_title: None,
+            current_url: None,
+            audio_device: None,
+            replaygain_gain: None,
+            normalization: NormalizationMode::Off,
+            repeat: RepeatMode::None,
+            ab_loop_a: None,
+            ab_loop_b: None,
+            sleep_timer: None,
+            stream: StreamInfo::default(),
+            cache: CacheStatus::default(),
+            speed: 1.0,
+            pitch: 0.0,
         }
     }
 }
 
-#[derive(Serialize, Deserialize)]
-struct MpvCommand {
-    command: Vec<serde_json::Value>,
-    request_id: i32,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct MpvResponse {
-    request_id: Option<i32>,
-    error: Option<String>,
-    data: Option<serde_json::Value>,
-    #[serde(rename = "event")]
-    event_name: Option<String>,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct MpvEvent {
-    #[serde(rename = "name")]
-    name: String,
-    data: Option<serde_json::Value>,
-}
-
 impl MpvPlayer {
     pub fn new() -> Self {
+        let status = Arc::new(Mutex::new(MpvStatus::default()));
+        let events = PlayerEvents::new(Arc::clone(&status));
+
         Self {
-            process: None,
-            status: Arc::new(Mutex::new(MpvStatus::default())),
-            ipc_thread: None,
-            ipc_path: Arc::new(Mutex::new(String::new())),
-            request_counter: Arc::new(Mutex::new(1)),
+            shared: SharedState {
+                session: Arc::new(Mutex::new(None)),
+                status,
+                events,
+                playlist: Arc::new(Mutex::new(Vec::new())),
+                audio_device: Arc::new(Mutex::new(None)),
+                chapters: Arc::new(Mutex::new(Vec::new())),
+                observed: Arc::new(Mutex::new(BTreeMap::new())),
+                logs: Arc::new(Mutex::new(LogBuffer::default())),
+                log_level: Arc::new(Mutex::new(LogLevel::default())),
+                replaygain: Arc::new(Mutex::new(ReplayGainSettings::default())),
+                replaygain_tags: Arc::new(Mutex::new(None)),
+                normalization: Arc::new(Mutex::new(NormalizationSettings::default())),
+                loudness_filter: Arc::new(Mutex::new(None)),
+                repeat: Arc::new(Mutex::new(RepeatMode::None)),
+                equalizer: Arc::new(Mutex::new(EqualizerState::default())),
+                speed: Arc::new(Mutex::new(SpeedSettings::default())),
+                pitch_filter: Arc::new(Mutex::new(None)),
+                volume: Arc::new(Mutex::new(VolumeSettings::default())),
+                crossfade: Arc::new(Mutex::new(CrossfadeSettings::default())),
+                fading: Arc::new(AtomicBool::new(false)),
+                sleep_timer: Arc::new(Mutex::new(None)),
+                sleep_fading: Arc::new(AtomicBool::new(false)),
+                file_loaded: Arc::new(Mutex::new(None)),
+            },
+            supervisor: None,
+            crossfader: None,
+            sleep_watcher: None,
+            options: MpvOptions::default(),
+            property_allowlist: PropertyAllowlist::default(),
+            next_observe_id: USER_OBSERVE_ID_BASE,
+            startup_timeout: DEFAULT_STARTUP_TIMEOUT,
         }
     }
 
-    pub fn init(&mut self, custom_path: Option<String>) -> Result<bool, String> {
-        if self.process.is_some() {
-            return Ok(true);
-        }
-
-        let mpv_executable = if let Some(path) = custom_path {
-            if path.trim().is_empty() {
-                self.find_mpv_executable()?
-            } else {
-                path
-            }
-        } else {
-            self.find_mpv_executable()?
-        };
-
-        let uuid = Uuid::new_v4().to_string();
-        let socket_path = {
-            #[cfg(target_os = "windows")]
-            {
-                format!("\\\\.\\pipe\\mpvsocket-{}", uuid)
-            }
-            #[cfg(any(target_os = "linux", target_os = "macos"))]
-            {
-                format!("/tmp/mpvsocket-{}", uuid)
-            }
-        };
-
-        {
-            let mut path = self.ipc_path.lock().unwrap();
-            *path = socket_path.clone();
-        }
-        let mut command = Command::new(&mpv_executable);
-        command.args([
-            "--idle=yes",
-            &format!("--input-ipc-server={}", socket_path),
-            "--no-terminal",
-            "--no-config",
-            "--keep-open=yes",
-            "--audio-display=no",
-            "--ytdl=no",
-            "--hr-seek=yes",
-            "--osd-level=0",
-            "--cache=yes",
-            "--cache-secs=10",
-            "--no-audio-display",
-            "--gapless-audio=yes",
-        ]);
-
-        #[cfg(target_os = "windows")]
-        {
-            command.creation_flags(CREATE_NO_WINDOW);
-        }
-
-        let process = command
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|e| format!("Failed to start MPV: {}", e))?;
-
-        thread::sleep(Duration::from_millis(500));
-
-        let status_clone = Arc::clone(&self.status);
-        let ipc_path_clone = Arc::clone(&self.ipc_path);
-        let request_counter_clone = Arc::clone(&self.request_counter);
-
-        let ipc_thread = thread::spawn(move || {
-            Self::ipc_communication_loop(ipc_path_clone, status_clone, request_counter_clone);
-        });
-
-        self.process = Some(process);
-        self.ipc_thread = Some(ipc_thread);
-
-        {
-            let mut status = self.status.lock().unwrap();
-            status.initialized = true;
-        }
-
-        Ok(true)
+    pub fn set_startup_timeout(&mut self, timeout: Duration) {
+        self.startup_timeout = timeout;
     }
 
     fn find_mpv_executable(&self) -> Result<String, String> {
@@ -223,638 +652,645 @@ impl MpvPlayer {
         Err("MPV executable not found. Please install MPV or provide a custom path.".to_string())
     }
 
-    fn ipc_communication_loop(
-        ipc_path: Arc<Mutex<String>>,
-        status: Arc<Mutex<MpvStatus>>,
-        request_counter: Arc<Mutex<i32>>,
-    ) {
-        let path = ipc_path.lock().unwrap().clone();
-        let mut retry_count = 0;
-        let max_retries = 50;
-
-        while retry_count < max_retries {
-            #[cfg(target_os = "windows")]
-            {
-                if let Ok(client) = PipeClient::connect(&path) {
-                    drop(client);
-                    break;
-                }
-            }
-
-            #[cfg(unix)]
-            {
-                if Path::new(&path).exists() {
-                    break;
-                }
+    fn event_handler(shared: &SharedState, live: Arc<AtomicBool>) -> EventHandler {
+        let shared = shared.clone();
+        let mut reported_pos = -1;
+        Box::new(move |response| {
+            // Kept from standby instances too, a crossfade that fails to start shows up there
+            if response.event_name.as_deref() == Some("log-message") {
+                let level = response.level.as_deref().and_then(LogLevel::from_mpv);
+                shared.record_log(LogEntry::new(
+                    level.unwrap_or_default(),
+                    response.prefix.as_deref().unwrap_or("mpv"),
+                    response.text.as_deref().unwrap_or_default(),
+                ));
+            } else if live.load(Ordering::SeqCst) {
+                Self::process_mpv_response(response, &shared, &mut reported_pos);
             }
+        })
+    }
 
-            thread::sleep(Duration::from_millis(100));
-            retry_count += 1;
-        }
+    // Live and standby instances alike report their events and log messages into the shared state
+    fn spawn_session(
+        shared: &SharedState,
+        launch: LaunchConfig,
+        live: bool,
+    ) -> Result<MpvSession, String> {
+        let live = Arc::new(AtomicBool::new(live));
+        let on_event = Self::event_handler(shared, Arc::clone(&live));
+        let log_shared = shared.clone();
+        let on_log: LogHandler = Arc::new(move |entry| log_shared.record_log(entry));
+        let log_level = *shared.log_level.lock().unwrap();
+        MpvSession::spawn(launch, live, on_event, on_log, log_level)
+    }
 
-        if retry_count >= max_retries {
-            eprintln!("Failed to connect to MPV socket after multiple retries");
+    fn process_mpv_response(response: MpvResponse, shared: &SharedState, reported_pos: &mut i32) {
+        let Some(event_name) = &response.event_name else {
             return;
-        }
+        };
 
-        #[cfg(target_os = "windows")]
-        {
-            if let Ok(mut client) = PipeClient::connect(&path) {
-                let props = [
-                    "time-pos",
-                    "duration",
-                    "pause",
-                    "eof-reached",
-                    "volume",
-                    "playlist-pos",
-                    "playlist-count",
-                    "media-title",
-                    "playback-time",
-                    "chapter",
-                    "chapter-list/count",
-                    "metadata",
-                    "filename",
-                ];
-
-                let request_id = {
-                    let mut counter = request_counter.lock().unwrap();
-                    *counter += 1;
-                    *counter
-                };
+        if let (Some(name), "property-change") = (&response.name, event_name.as_str()) {
+            if shared.observed.lock().unwrap().contains_key(name) {
+                shared
+                    .events
+                    .emit(PlayerEvent::PropertyChanged(PropertyChange {
+                        name: name.clone(),
+                        value: response.data.clone().unwrap_or_default(),
+                    }));
+            }
+            // Only observed for the webview, the player doesn't follow it
+            if !OBSERVED_PROPERTIES.contains(&name.as_str()) {
+                return;
+            }
+        }
 
-                let cmd = MpvCommand {
-                    command: vec!["enable_event".into(), "all".into()],
-                    request_id,
+        match event_name.as_str() {
+            "property-change" if response.name.as_deref() == Some("audio-device-list") => {
+                if let Some(devices) = response
+                    .data
+                    .clone()
+                    .and_then(|data| serde_json::from_value::<Vec<AudioDevice>>(data).ok())
+                {
+                    Self::audio_devices_changed(shared, devices);
+                }
+            }
+            "property-change" if response.name.as_deref() == Some("current-tracks/audio") => {
+                let tags = response
+                    .data
+                    .clone()
+                    .and_then(|data| serde_json::from_value::<ReplayGainTags>(data).ok());
+                *shared.replaygain_tags.lock().unwrap() = tags;
+                shared.update_replaygain_gain();
+            }
+            "property-change" if response.name.as_deref() == Some("paused-for-cache") => {
+                let paused = response
+                    .data
+                    .as_ref()
+                    .and_then(|data| data.as_bool())
+                    .unwrap_or(false);
+                let change = {
+                    let mut status = shared.status.lock().unwrap();
+                    status
+                        .cache
+                        .set_paused_for_cache(paused)
+                        .then(|| BufferingChange {
+                            buffering: paused,
+                            stalls: status.cache.stalls,
+                        })
                 };
-
-                if let Ok(cmd_json) = serde_json::to_string(&cmd) {
-                    let _ = writeln!(client, "{}", cmd_json);
+                if let Some(change) = change {
+                    shared.events.status_changed();
+                    shared.events.emit(PlayerEvent::Buffering(change));
                 }
+            }
+            "property-change" if response.name.as_deref() == Some("chapter-list") => {
+                let chapters = response
+                    .data
+                    .clone()
+                    .and_then(|data| serde_json::from_value::<Vec<Chapter>>(data).ok())
+                    .unwrap_or_default();
+                shared.chapters_changed(chapters);
+            }
+            "property-change" if response.name.as_deref() == Some("metadata/by-key/genre") => {
+                let genre = response
+                    .data
+                    .as_ref()
+                    .and_then(|data| data.as_str())
+                    .map(String::from);
+                Self::genre_changed(shared, genre);
+            }
+            // Fades own the volume while they run, the slider keeps the user's setting
+            "property-change"
+                if response.name.as_deref() == Some("volume")
+                    && (shared.fading.load(Ordering::SeqCst)
+                        || shared.sleep_fading.load(Ordering::SeqCst)) => {}
+            "property-change" if response.name.as_deref() == Some("volume") => {
+                if let Some(volume) = response.data.as_ref().and_then(|data| data.as_f64()) {
+                    let level = shared.volume.lock().unwrap().from_mpv(volume);
+                    shared.status.lock().unwrap().volume = level;
+                    shared.events.status_changed();
+                }
+            }
+            "property-change" => {
+                if let Some(name) = &response.name {
+                    let value = response.data.clone().unwrap_or_default();
+                    let (track_change, held_at_end) = {
+                        let mut status_guard = shared.status.lock().unwrap();
+                        let was_ended = status_guard.state == "ended";
+                        Self::apply_property(&mut status_guard, name, &value);
+                        // keep-open holds the last file instead of ending it, so no end-file follows
+                        let held_at_end = name == "eof-reached" && value == true && !was_ended;
+                        if name == "chapter" {
+                            let chapters = shared.chapters.lock().unwrap();
+                            status_guard.chapter_title =
+                                chapters::title(&chapters, status_guard.chapter);
+                        }
 
-                for prop in props {
-                    let request_id = {
-                        let mut counter = request_counter.lock().unwrap();
-                        *counter += 1;
-                        *counter
-                    };
-
-                    let cmd = MpvCommand {
-                        command: vec!["observe_property".into(), request_id.into(), prop.into()],
-                        request_id,
+                        // Local writes can race ahead of mpv, so compare with its own last report
+                        let previous_pos = *reported_pos;
+                        if name == "playlist-pos" {
+                            *reported_pos = status_guard.playlist_pos;
+                        }
+                        let track_change = (name == "playlist-pos"
+                            && status_guard.playlist_pos >= 0
+                            && status_guard.playlist_pos != previous_pos)
+                            .then(|| TrackChange {
+                                playlist_pos: status_guard.playlist_pos,
+                                playlist_count: status_guard.playlist_count,
+                            });
+                        (track_change, held_at_end)
                     };
 
-                    if let Ok(cmd_json) = serde_json::to_string(&cmd) {
-                        let _ = writeln!(client, "{}", cmd_json);
+                    shared.events.status_changed();
+                    if let Some(change) = track_change {
+                        shared.events.emit(PlayerEvent::TrackChanged(change));
+                    }
+                    if held_at_end {
+                        shared.events.emit(PlayerEvent::Ended(PlaybackEnded {
+                            reason: "eof".to_string(),
+                            playlist_entry_id: None,
+                        }));
                     }
                 }
             }
-        }
-
-        #[cfg(unix)]
-        {
-            if let Ok(mut stream) = UnixStream::connect(&path) {
-                let props = [
-                    "time-pos",
-                    "duration",
-                    "pause",
-                    "eof-reached",
-                    "volume",
-                    "playlist-pos",
-                    "playlist-count",
-                    "media-title",
-                    "playback-time",
-                    "chapter",
-                    "chapter-list/count",
-                ];
-
-                let request_id = {
-                    let mut counter = request_counter.lock().unwrap();
-                    *counter += 1;
-                    *counter
-                };
-
-                let cmd = MpvCommand {
-                    command: vec!["enable_event".into(), "all".into()],
-                    request_id,
-                };
-
-                if let Ok(cmd_json) = serde_json::to_string(&cmd) {
-                    let _ = writeln!(stream, "{}", cmd_json);
-                }
-
-                for prop in props {
-                    let request_id = {
-                        let mut counter = request_counter.lock().unwrap();
-                        *counter += 1;
-                        *counter
-                    };
-
-                    let cmd = MpvCommand {
-                        command: vec!["observe_property".into(), request_id.into(), prop.into()],
-                        request_id,
-                    };
-
-                    if let Ok(cmd_json) = serde_json::to_string(&cmd) {
-                        let _ = writeln!(stream, "{}", cmd_json);
+            "start-file" if shared.equalizer.lock().unwrap().is_stale() => {
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    if let Err(e) = shared.ipc().and_then(|ipc| shared.refresh_equalizer(&ipc)) {
+                        shared.log(
+                            LogLevel::Error,
+                            &format!("Failed to refresh equalizer filter: {}", e),
+                        );
                     }
+                });
+            }
+            "file-loaded" => {
+                if let Some(loaded) = shared.file_loaded.lock().unwrap().as_ref() {
+                    let _ = loaded.send(());
                 }
             }
-        }
+            "playback-restart" => {
+                shared.status.lock().unwrap().state = "playing".to_string();
+                shared.events.status_changed();
+            }
+            "seek" => {
+                if let Some(data) = &response.data {
+                    let position = if let Some(target) = data.get("target").and_then(|v| v.as_f64())
+                    {
+                        Some(target)
+                    } else if let Some(pos) = data.get("position").and_then(|v| v.as_f64()) {
+                        Some(pos)
+                    } else {
+                        data.as_f64()
+                    };
 
-        loop {
-            #[cfg(target_os = "windows")]
-            {
-                if let Ok(client) = PipeClient::connect(&path) {
-                    let reader = BufReader::new(client);
-                    for line in reader.lines() {
-                        if let Ok(line) = line {
-                            if let Ok(response) = serde_json::from_str::<MpvResponse>(&line) {
-                                Self::process_mpv_response(response, &status);
-                            }
-                        }
+                    if let Some(pos) = position {
+                        let mut status_guard = shared.status.lock().unwrap();
+                        status_guard.position = pos;
+                        status_guard.playback_time = Some(pos);
+                        drop(status_guard);
+                        shared.events.status_changed();
                     }
-                } else {
-                    Self::poll_properties(&path, &request_counter, &status);
                 }
             }
-
-            #[cfg(unix)]
-            {
-                if let Ok(stream) = UnixStream::connect(&path) {
-                    let reader = BufReader::new(stream);
-                    for line in reader.lines() {
-                        if let Ok(line) = line {
-                            if let Ok(response) = serde_json::from_str::<MpvResponse>(&line) {
-                                Self::process_mpv_response(response, &status);
-                            }
+            "end-file" => {
+                if let Some(reason) = &response.reason {
+                    let was_ended = {
+                        let mut status_guard = shared.status.lock().unwrap();
+                        let was_ended = status_guard.state == "ended";
+                        match reason.as_str() {
+                            "eof" => status_guard.state = "ended".to_string(),
+                            "stop" => status_guard.state = "stopped".to_string(),
+                            "quit" => status_guard.state = "idle".to_string(),
+                            "error" => status_guard.state = "error".to_string(),
+                            _ => {}
                         }
+                        was_ended
+                    };
+                    shared.events.status_changed();
+
+                    match reason.as_str() {
+                        // Already reported when mpv reached the end of the held file
+                        "eof" if was_ended => {}
+                        "eof" => shared.events.emit(PlayerEvent::Ended(PlaybackEnded {
+                            reason: reason.clone(),
+                            playlist_entry_id: response.playlist_entry_id,
+                        })),
+                        "error" => shared.events.emit(PlayerEvent::Error(PlaybackError {
+                            message: response
+                                .file_error
+                                .clone()
+                                .unwrap_or_else(|| "Playback failed".to_string()),
+                        })),
+                        _ => {}
                     }
-                } else {
-                    Self::poll_properties(&path, &request_counter, &status);
                 }
             }
-
-            thread::sleep(Duration::from_millis(500));
+            _ => {}
         }
     }
 
-    fn poll_properties(
-        path: &str,
-        request_counter: &Arc<Mutex<i32>>,
-        status: &Arc<Mutex<MpvStatus>>,
-    ) {
-        if let Some(position) = Self::get_property_value::<f64>(path, request_counter, "time-pos") {
-            let mut status_guard = status.lock().unwrap();
-            status_guard.position = position;
-        }
-
-        if let Some(duration) = Self::get_property_value::<f64>(path, request_counter, "duration") {
-            let mut status_guard = status.lock().unwrap();
-            status_guard.duration = duration;
-        }
+    fn audio_devices_changed(shared: &SharedState, devices: Vec<AudioDevice>) {
+        let preferred = shared.audio_device.lock().unwrap().clone();
+        let active = shared.status.lock().unwrap().audio_device.clone();
 
-        if let Some(paused) = Self::get_property_value::<bool>(path, request_counter, "pause") {
-            let mut status_guard = status.lock().unwrap();
-            status_guard.state = if paused {
-                "paused".to_string()
-            } else {
-                "playing".to_string()
-            };
+        let wanted = match preferred {
+            Some(name) if devices.iter().any(|device| device.name == name) => name,
+            _ => "auto".to_string(),
+        };
+        if active.is_some() && active.as_deref() != Some(wanted.as_str()) {
+            // Commands can't be awaited from the IPC thread that delivers their replies
+            let shared = shared.clone();
+            thread::spawn(move || {
+                if let Err(e) = shared
+                    .ipc()
+                    .and_then(|ipc| ipc.set_property("audio-device", wanted.as_str()))
+                {
+                    shared.log(
+                        LogLevel::Error,
+                        &format!("Failed to switch audio device: {}", e),
+                    );
+                }
+            });
         }
 
-        if let Some(volume) = Self::get_property_value::<f64>(path, request_counter, "volume") {
-            let mut status_guard = status.lock().unwrap();
-            status_guard.volume = volume / 100.0;
-        }
+        shared
+            .events
+            .emit(PlayerEvent::AudioDevicesChanged(AudioDevicesChange {
+                devices,
+            }));
     }
 
-    fn get_property_value<T: serde::de::DeserializeOwned>(
-        path: &str,
-        request_counter: &Arc<Mutex<i32>>,
-        property: &str,
-    ) -> Option<T> {
-        let request_id = {
-            let mut counter = request_counter.lock().unwrap();
-            *counter += 1;
-            *counter
-        };
-
-        let cmd = MpvCommand {
-            command: vec!["get_property".into(), property.into()],
-            request_id,
+    fn genre_changed(shared: &SharedState, genre: Option<String>) {
+        let changed = {
+            let mut state = shared.equalizer.lock().unwrap();
+            let previous = state.active().clone();
+            state.set_genre(genre);
+            *state.active() != previous
         };
+        if !changed {
+            return;
+        }
 
-        if let Ok(cmd_json) = serde_json::to_string(&cmd) {
-            #[cfg(target_os = "windows")]
-            {
-                if let Ok(mut client) = PipeClient::connect(path) {
-                    if writeln!(client, "{}", cmd_json).is_ok() {
-                        let reader = BufReader::new(client);
-                        for line in reader.lines().take(1) {
-                            if let Ok(line) = line {
-                                if let Ok(response) = serde_json::from_str::<MpvResponse>(&line) {
-                                    if response.request_id == Some(request_id)
-                                        && response.error.is_none()
-                                    {
-                                        if let Some(data) = response.data {
-                                            return serde_json::from_value(data).ok();
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+        let shared = shared.clone();
+        thread::spawn(move || {
+            if let Err(e) = shared.ipc().and_then(|ipc| shared.apply_equalizer(&ipc)) {
+                shared.log(
+                    LogLevel::Error,
+                    &format!("Failed to apply genre equalizer preset: {}", e),
+                );
             }
+            shared.equalizer_changed();
+        });
+    }
 
-            #[cfg(unix)]
-            {
-                if let Ok(mut stream) = UnixStream::connect(path) {
-                    if writeln!(stream, "{}", cmd_json).is_ok() {
-                        let reader = BufReader::new(stream);
-                        for line in reader.lines().take(1) {
-                            if let Ok(line) = line {
-                                if let Ok(response) = serde_json::from_str::<MpvResponse>(&line) {
-                                    if response.request_id == Some(request_id)
-                                        && response.error.is_none()
-                                    {
-                                        if let Some(data) = response.data {
-                                            return serde_json::from_value(data).ok();
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
+    fn apply_property(status: &mut MpvStatus, name: &str, value: &serde_json::Value) {
+        match name {
+            "time-pos" => {
+                if let Some(pos) = value.as_f64() {
+                    status.position = pos;
+                    status.playback_time = Some(pos);
                 }
             }
-        }
-
-        None
-    }
-
-    fn process_mpv_response(response: MpvResponse, status: &Arc<Mutex<MpvStatus>>) {
-        if let Some(event_name) = &response.event_name {
-            match event_name.as_str() {
-                "property-change" => {
-                    if let Some(data) = &response.data {
-                        if let Ok(property_data) =
-                            serde_json::from_value::<serde_json::Value>(data.clone())
-                        {
-                            let property_name = property_data.get("name").and_then(|v| v.as_str());
-                            let property_value = property_data.get("data");
-
-                            if let (Some(name), Some(value)) = (property_name, property_value) {
-                                let mut status_guard = status.lock().unwrap();
-
-                                match name {
-                                    "time-pos" => {
-                                        if let Some(pos) = value.as_f64() {
-                                            status_guard.position = pos;
-                                            status_guard.playback_time = Some(pos);
-                                        }
-                                    }
-                                    "duration" => {
-                                        if let Some(dur) = value.as_f64() {
-                                            status_guard.duration = dur;
-                                        }
-                                    }
-                                    "pause" => {
-                                        if let Some(paused) = value.as_bool() {
-                                            status_guard.pause = Some(paused);
-                                            status_guard.state = if paused {
-                                                "paused".to_string()
-                                            } else {
-                                                "playing".to_string()
-                                            };
-                                        }
-                                    }
-                                    "eof-reached" => {
-                                        if let Some(eof) = value.as_bool() {
-                                            if eof {
-                                                status_guard.state = "ended".to_string();
-                                            }
-                                        }
-                                    }
-                                    "volume" => {
-                                        if let Some(vol) = value.as_f64() {
-                                            status_guard.volume = vol / 100.0;
-                                        }
-                                    }
-                                    "playlist-pos" => {
-                                        if let Some(pos) = value.as_i64() {
-                                            status_guard.playlist_pos = pos as i32;
-                                        }
-                                    }
-                                    "playlist-count" => {
-                                        if let Some(count) = value.as_i64() {
-                                            status_guard.playlist_count = count as i32;
-                                        }
-                                    }
-                                    "media-title" => {
-                                        if let Some(title) = value.as_str() {
-                                            status_guard.media_title = Some(title.to_string());
-                                        }
-                                    }
-                                    "playback-time" => {
-                                        if let Some(time) = value.as_f64() {
-                                            status_guard.playback_time = Some(time);
-                                        }
-                                    }
-                                    "chapter" => {
-                                        if let Some(chapter) = value.as_i64() {
-                                            status_guard.chapter = Some(chapter as i32);
-                                        }
-                                    }
-                                    "chapter-list/count" => {
-                                        if let Some(count) = value.as_i64() {
-                                            status_guard.chapter_count = Some(count as i32);
-                                        }
-                                    }
-                                    _ => {
-                                        println!("Unhandled property: {}", name);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                "playback-restart" => {
-                    let mut status_guard = status.lock().unwrap();
-                    status_guard.state = "playing".to_string();
+            "duration" => {
+                if let Some(dur) = value.as_f64() {
+                    status.duration = dur;
                 }
-                "seek" => {
-                    if let Some(data) = &response.data {
-                        let position = if let Some(target) =
-                            data.get("target").and_then(|v| v.as_f64())
-                        {
-                            Some(target)
-                        } else if let Some(pos) = data.get("position").and_then(|v| v.as_f64()) {
-                            Some(pos)
-                        } else if let Some(pos) = data.as_f64() {
-                            Some(pos)
-                        } else {
-                            None
-                        };
-
-                        if let Some(pos) = position {
-                            let mut status_guard = status.lock().unwrap();
-                            status_guard.position = pos;
-                            status_guard.playback_time = Some(pos);
-                        }
-                    }
-                }
-                "end-file" => {
-                    if let Some(data) = &response.data {
-                        if let Some(reason) = data.get("reason").and_then(|v| v.as_str()) {
-                            let mut status_guard = status.lock().unwrap();
-                            match reason {
-                                "eof" => status_guard.state = "ended".to_string(),
-                                "stop" => status_guard.state = "stopped".to_string(),
-                                "quit" => status_guard.state = "idle".to_string(),
-                                "error" => status_guard.state = "error".to_string(),
-                                _ => {}
-                            }
-                        }
-                    }
-                }
-                _ => {}
             }
-        } else if let Some(_request_id) = &response.request_id {
-            if let Some(data) = &response.data {
-                if let Some(position) = data.as_f64() {
-                    let mut status_guard = status.lock().unwrap();
-
-                    if position > 10.0 && (position - position.round()).abs() < 0.01 {
-                        status_guard.duration = position;
-                    } else {
-                        status_guard.position = position;
-                    }
-                } else if let Some(paused) = data.as_bool() {
-                    let mut status_guard = status.lock().unwrap();
-                    status_guard.state = if paused {
+            "pause" => {
+                if let Some(paused) = value.as_bool() {
+                    status.pause = Some(paused);
+                    status.state = if paused {
                         "paused".to_string()
                     } else {
                         "playing".to_string()
                     };
                 }
             }
+            "eof-reached" if value.as_bool() == Some(true) => {
+                status.state = "ended".to_string();
+            }
+            "mute" => {
+                if let Some(muted) = value.as_bool() {
+                    status.muted = muted;
+                }
+            }
+            "playlist-pos" => {
+                if let Some(pos) = value.as_i64() {
+                    status.playlist_pos = pos as i32;
+                }
+            }
+            "playlist-count" => {
+                if let Some(count) = value.as_i64() {
+                    status.playlist_count = count as i32;
+                }
+            }
+            "path" => {
+                status.current_url = value.as_str().map(String::from);
+                status
+                    .stream
+                    .update_transcoded(status.current_url.as_deref());
+            }
+            "audio-device" => {
+                status.audio_device = value.as_str().map(String::from);
+            }
+            "media-title" => {
+                if let Some(title) = value.as_str() {
+                    status.media_title = Some(title.to_string());
+                }
+            }
+            "playback-time" => {
+                if let Some(time) = value.as_f64() {
+                    status.playback_time = Some(time);
+                }
+            }
+            "chapter" => {
+                if let Some(chapter) = value.as_i64() {
+                    status.chapter = Some(chapter as i32);
+                }
+            }
+            "chapter-list/count" => {
+                if let Some(count) = value.as_i64() {
+                    status.chapter_count = Some(count as i32);
+                }
+            }
+            "speed" => {
+                if let Some(speed) = value.as_f64() {
+                    status.speed = speed;
+                }
+            }
+            // Only one of the two loops is ever on, whichever was switched on last
+            "loop-file" => {
+                if repeat::is_looping(value) {
+                    status.repeat = RepeatMode::One;
+                } else if status.repeat == RepeatMode::One {
+                    status.repeat = RepeatMode::None;
+                }
+            }
+            "loop-playlist" => {
+                if repeat::is_looping(value) {
+                    status.repeat = RepeatMode::All;
+                } else if status.repeat == RepeatMode::All {
+                    status.repeat = RepeatMode::None;
+                }
+            }
+            "ab-loop-a" => status.ab_loop_a = value.as_f64(),
+            "ab-loop-b" => status.ab_loop_b = value.as_f64(),
+            "cache-buffering-state" => {
+                status.cache.buffering_state = value.as_u64().map(|percent| percent as u32);
+            }
+            "demuxer-cache-duration" => status.cache.duration = value.as_f64(),
+            "demuxer-cache-state" => status.cache.apply_demuxer_state(value),
+            "audio-params" => status.stream.apply_audio_params(value),
+            "audio-codec-name" => status.stream.set_codec(value.as_str().map(String::from)),
+            "audio-bitrate" => {
+                status.stream.bitrate = value.as_f64().map(|bitrate| bitrate.round() as u32);
+            }
+            "file-format" => status.stream.container = value.as_str().map(String::from),
+            "metadata" => status.stream.apply_metadata(value),
+            _ => {}
+        }
+    }
+
+    fn ipc(&self) -> Result<Arc<MpvIpc>, String> {
+        self.shared.ipc()
+    }
+
+    fn set_playlist(&self, urls: &[String]) {
+        *self.shared.playlist.lock().unwrap() = urls.to_vec();
+        self.shared
+            .events
+            .emit(PlayerEvent::PlaylistChanged(PlaylistChange {
+                entries: urls.to_vec(),
+                playlist_pos: if urls.is_empty() { -1 } else { 0 },
+            }));
+    }
+
+    fn edit_playlist(&self, edit: impl FnOnce(&mut Vec<String>, i32) -> i32) {
+        let change = {
+            let mut playlist = self.shared.playlist.lock().unwrap();
+            let mut status = self.shared.status.lock().unwrap();
+            status.playlist_pos = edit(&mut playlist, status.playlist_pos);
+            status.playlist_count = playlist.len() as i32;
+            PlaylistChange {
+                entries: playlist.clone(),
+                playlist_pos: status.playlist_pos,
+            }
+        };
+
+        self.shared.events.status_changed();
+        self.shared
+            .events
+            .emit(PlayerEvent::PlaylistChanged(change));
+    }
+
+    fn update_speed(&self, settings: SpeedSettings) -> Result<(), String> {
+        settings.validate()?;
+        *self.shared.speed.lock().unwrap() = settings.clone();
+        if let Ok(ipc) = self.ipc() {
+            self.shared.apply_speed(&ipc)?;
+        }
+
+        {
+            let mut status = self.shared.status.lock().unwrap();
+            status.speed = settings.speed;
+            status.pitch = settings.pitch;
         }
+        self.shared.events.status_changed();
+        Ok(())
+    }
+
+    fn playlist_len(&self) -> usize {
+        self.shared.playlist.lock().unwrap().len()
     }
 
     fn send_command(&self, command: Vec<serde_json::Value>) -> Result<(), String> {
-        let path = self.ipc_path.lock().unwrap().clone();
+        self.ipc()?.command(command).map(|_| ())
+    }
+}
+
+impl PlaybackBackend for MpvPlayer {
+    fn init(&mut self, custom_path: Option<String>) -> Result<bool, String> {
+        if self.shared.session.lock().unwrap().is_some() {
+            return Ok(true);
+        }
+
+        let mpv_executable = if let Some(path) = custom_path {
+            if path.trim().is_empty() {
+                self.find_mpv_executable()?
+            } else {
+                path
+            }
+        } else {
+            self.find_mpv_executable()?
+        };
 
-        if path.is_empty() {
-            return Err("MPV IPC path not initialized".to_string());
+        let uuid = Uuid::new_v4().to_string();
+        let socket_path = {
+            #[cfg(target_os = "windows")]
+            {
+                format!("\\\\.\\pipe\\mpvsocket-{}", uuid)
+            }
+            #[cfg(any(target_os = "linux", target_os = "macos"))]
+            {
+                format!("/tmp/mpvsocket-{}", uuid)
+            }
+        };
+
+        let mut args: Vec<String> = [
+            "--idle=yes",
+            &format!("--input-ipc-server={}", socket_path),
+            "--no-terminal",
+            "--keep-open=yes",
+            "--audio-display=no",
+            "--ytdl=no",
+            "--hr-seek=yes",
+            "--osd-level=0",
+            "--cache=yes",
+            "--cache-secs=10",
+            "--no-audio-display",
+            "--gapless-audio=yes",
+            "--reset-on-next-file=ab-loop-a,ab-loop-b",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        if !self.options.load_config {
+            args.push("--no-config".to_string());
         }
+        args.extend(self.options.args());
 
-        let request_id = {
-            let mut counter = self.request_counter.lock().unwrap();
-            *counter += 1;
-            *counter
-        };
-
-        let cmd = MpvCommand {
-            command,
-            request_id,
+        let launch = LaunchConfig {
+            executable: mpv_executable,
+            args,
+            socket_path,
+            startup_timeout: self.startup_timeout,
         };
+        let session = Self::spawn_session(&self.shared, launch.clone(), true)?;
+        if let Err(e) = self.shared.apply_volume(&session.ipc) {
+            self.shared.log(
+                LogLevel::Error,
+                &format!("Failed to apply volume settings: {}", e),
+            );
+        }
+        if let Err(e) = self.shared.apply_replaygain(&session.ipc) {
+            self.shared.log(
+                LogLevel::Error,
+                &format!("Failed to apply ReplayGain settings: {}", e),
+            );
+        }
+        self.shared.equalizer.lock().unwrap().set_installed(None);
+        if let Err(e) = self.shared.apply_equalizer(&session.ipc) {
+            self.shared.log(
+                LogLevel::Error,
+                &format!("Failed to apply equalizer settings: {}", e),
+            );
+        }
+        *self.shared.pitch_filter.lock().unwrap() = None;
+        if let Err(e) = self.shared.apply_speed(&session.ipc) {
+            self.shared.log(
+                LogLevel::Error,
+                &format!("Failed to apply playback speed: {}", e),
+            );
+        }
+        *self.shared.loudness_filter.lock().unwrap() = None;
+        if let Err(e) = self.shared.apply_normalization(&session.ipc) {
+            self.shared.log(
+                LogLevel::Error,
+                &format!("Failed to apply loudness normalization: {}", e),
+            );
+        }
+        if let Err(e) = self.shared.apply_repeat(&session.ipc) {
+            self.shared.log(
+                LogLevel::Error,
+                &format!("Failed to apply repeat mode: {}", e),
+            );
+        }
+        if let Err(e) = self.shared.apply_observed(&session.ipc) {
+            self.shared.log(
+                LogLevel::Error,
+                &format!("Failed to observe properties for the webview: {}", e),
+            );
+        }
+        *self.shared.session.lock().unwrap() = Some(session);
 
-        let cmd_json = serde_json::to_string(&cmd)
-            .map_err(|e| format!("Failed to serialize command: {}", e))?;
+        self.supervisor = Some(Supervisor::start(self.shared.clone()));
+        self.crossfader = Some(Crossfader::start(self.shared.clone(), launch));
+        self.sleep_watcher = Some(SleepWatcher::start(self.shared.clone()));
 
-        #[cfg(target_os = "windows")]
         {
-            let mut client = PipeClient::connect(&path)
-                .map_err(|e| format!("Failed to connect to MPV pipe: {}", e))?;
-
-            writeln!(client, "{}", cmd_json)
-                .map_err(|e| format!("Failed to send command to MPV: {}", e))?;
+            let mut status = self.shared.status.lock().unwrap();
+            status.initialized = true;
+            status.normalization = self.shared.normalization.lock().unwrap().mode;
+            status.max_volume = self.shared.volume.lock().unwrap().max_level();
         }
+        self.shared.events.status_changed();
 
-        #[cfg(unix)]
-        {
-            let mut stream = UnixStream::connect(&path)
-                .map_err(|e| format!("Failed to connect to MPV socket: {}", e))?;
-
-            writeln!(stream, "{}", cmd_json)
-                .map_err(|e| format!("Failed to send command to MPV: {}", e))?;
-        }
+        Ok(true)
+    }
 
-        Ok(())
+    fn set_event_sink(&mut self, sink: EventSink) {
+        self.shared.events.set_sink(sink);
     }
 
-    pub fn get_status(&self) -> MpvStatus {
-        let status = self.status.lock().unwrap().clone();
+    fn get_status(&self) -> MpvStatus {
+        let status = self.shared.status.lock().unwrap().clone();
         if !status.initialized {
             return status;
         }
 
-        let path = self.ipc_path.lock().unwrap().clone();
-        if path.is_empty() {
-            return status;
-        }
-
-        use std::cell::RefCell;
-        thread_local! {
-            static LAST_POLL: RefCell<std::time::Instant> = RefCell::new(std::time::Instant::now());
-            static CACHED_STATUS: RefCell<Option<MpvStatus>> = RefCell::new(None);
-        }
-
-        let now = std::time::Instant::now();
-        let should_update = LAST_POLL.with(|last| {
-            let last_poll = *last.borrow();
-            let should_poll = now.duration_since(last_poll).as_millis() >= 100;
-            if should_poll {
-                *last.borrow_mut() = now;
-            }
-            should_poll
-        });
-
-        let cached = CACHED_STATUS.with(|cache| cache.borrow().clone());
-        if !should_update && cached.is_some() {
-            return cached.unwrap();
-        }
-
-        let mut updated_status = status.clone();
-        let properties_to_fetch = [
-            ("playback-time", 1001),
-            ("duration", 1002),
-            ("media-title", 1003),
-            ("pause", 1004),
-            ("chapter", 1005),
-            ("chapter-list/count", 1006),
-            ("playlist-pos", 1007),
-            ("playlist-count", 1008),
-        ];
-
-        #[cfg(target_os = "windows")]
-        {
-            if let Ok(mut client) = PipeClient::connect(&path) {
-                let mut batch_cmd = String::new();
-                for (prop, id) in &properties_to_fetch {
-                    batch_cmd.push_str(&format!(
-                        "{{\"command\": [\"get_property\", \"{}\"], \"request_id\": {}}}\n",
-                        prop, id
-                    ));
-                }
+        let ipc = match self.ipc() {
+            Ok(ipc) => ipc,
+            Err(_) => return status,
+        };
 
-                if writeln!(client, "{}", batch_cmd.trim()).is_ok() {
-                    let reader = BufReader::new(client);
-                    for line in reader.lines().take(properties_to_fetch.len()) {
-                        if let Ok(line) = line {
-                            if let Ok(json) = serde_json::from_str::<serde_json::Value>(&line) {
-                                if let Some(request_id) =
-                                    json.get("request_id").and_then(|id| id.as_i64())
-                                {
-                                    match request_id {
-                                        1001 => {
-                                            if let Some(data) = json.get("data") {
-                                                if let Some(time) = data.as_f64() {
-                                                    updated_status.position = time;
-                                                    updated_status.playback_time = Some(time);
-                                                }
-                                            }
-                                        }
-                                        1002 => {
-                                            if let Some(data) = json.get("data") {
-                                                if let Some(duration) = data.as_f64() {
-                                                    updated_status.duration = duration;
-                                                }
-                                            }
-                                        }
-                                        1003 => {
-                                            if let Some(data) = json.get("data") {
-                                                if let Some(title) = data.as_str() {
-                                                    updated_status.media_title =
-                                                        Some(title.to_string());
-                                                }
-                                            }
-                                        }
-                                        1004 => {
-                                            if let Some(data) = json.get("data") {
-                                                if let Some(paused) = data.as_bool() {
-                                                    updated_status.pause = Some(paused);
-                                                    updated_status.state = if paused {
-                                                        "paused".to_string()
-                                                    } else {
-                                                        "playing".to_string()
-                                                    };
-                                                }
-                                            }
-                                        }
-                                        1005 => {
-                                            if let Some(data) = json.get("data") {
-                                                if let Some(chapter) = data.as_i64() {
-                                                    updated_status.chapter = Some(chapter as i32);
-                                                }
-                                            }
-                                        }
-                                        1006 => {
-                                            if let Some(data) = json.get("data") {
-                                                if let Some(count) = data.as_i64() {
-                                                    updated_status.chapter_count =
-                                                        Some(count as i32);
-                                                }
-                                            }
-                                        }
-                                        1007 => {
-                                            if let Some(data) = json.get("data") {
-                                                if let Some(pos) = data.as_i64() {
-                                                    updated_status.playlist_pos = pos.max(0) as i32;
-                                                }
-                                            }
-                                        }
-                                        1008 => {
-                                            if let Some(data) = json.get("data") {
-                                                if let Some(count) = data.as_i64() {
-                                                    updated_status.playlist_count = count as i32;
-                                                }
-                                            }
-                                        }
-                                        _ => {}
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+        let deadline = Instant::now() + STATUS_FETCH_TIMEOUT;
+        let pending: Vec<_> = STATUS_PROPERTIES
+            .iter()
+            .filter_map(|prop| {
+                ipc.request(vec!["get_property".into(), (*prop).into()])
+                    .ok()
+                    .map(|response| (*prop, response))
+            })
+            .collect();
+
+        let mut fetched = Vec::with_capacity(pending.len());
+        for (prop, response) in pending {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if let Ok(Ok(Some(value))) = response
+                .recv_timeout(remaining)
+                .map(|response| response.into_result())
+            {
+                fetched.push((prop, value));
             }
         }
-        {
-            let mut current_status = self.status.lock().unwrap();
-            current_status.position = updated_status.position;
-            current_status.duration = updated_status.duration;
-            current_status.playback_time = updated_status.playback_time;
-            current_status.media_title = updated_status.media_title;
-            current_status.pause = updated_status.pause;
-            current_status.chapter = updated_status.chapter;
-            current_status.chapter_count = updated_status.chapter_count;
-            current_status.state = updated_status.state;
-            if updated_status.playlist_pos >= 0 {
-                current_status.playlist_pos = updated_status.playlist_pos;
-            } else if current_status.playlist_count > 0 {
-                current_status.playlist_pos = 0;
-            }
-
-            current_status.playlist_count = updated_status.playlist_count;
 
-            updated_status = current_status.clone();
+        let mut current_status = self.shared.status.lock().unwrap();
+        let previous = current_status.clone();
+        for (prop, value) in &fetched {
+            Self::apply_property(&mut current_status, prop, value);
         }
 
-        CACHED_STATUS.with(|cache| {
-            *cache.borrow_mut() = Some(updated_status.clone());
-        });
+        if !matches!(previous.state.as_str(), "playing" | "paused") {
+            current_status.state = previous.state.clone();
+        }
+        if current_status.playlist_pos < 0 && current_status.playlist_count > 0 {
+            current_status.playlist_pos = 0;
+        }
 
-        updated_status
+        if *current_status != previous {
+            self.shared.events.status_changed();
+        }
+        current_status.clone()
     }
 
-    pub fn load(&mut self, url: &str) -> Result<(), String> {
+    fn load(&mut self, url: &str) -> Result<(), String> {
         let command = vec!["loadfile".into(), url.into(), "replace".into()];
         self.send_command(command)?;
+        self.set_playlist(&[url.to_string()]);
 
         {
-            let mut status = self.status.lock().unwrap();
+            let mut status = self.shared.status.lock().unwrap();
             status.state = "idle".to_string();
             status.position = 0.0;
         }
@@ -862,7 +1298,7 @@ impl MpvPlayer {
         Ok(())
     }
 
-    pub fn load_playlist(&mut self, urls: Vec<String>) -> Result<(), String> {
+    fn load_playlist(&mut self, urls: Vec<String>) -> Result<(), String> {
         let command = vec!["playlist-clear".into()];
         self.send_command(command)?;
 
@@ -870,171 +1306,165 @@ impl MpvPlayer {
             let command = vec!["loadfile".into(), url.as_str().into(), "append".into()];
             self.send_command(command)?;
         }
+        self.set_playlist(&urls);
         {
-            let mut status = self.status.lock().unwrap();
+            let mut status = self.shared.status.lock().unwrap();
             status.state = "idle".to_string();
             status.position = 0.0;
         }
 
-        let request_id = {
-            let mut counter = self.request_counter.lock().unwrap();
-            *counter += 1;
-            *counter
-        };
-
-        let cmd = MpvCommand {
-            command: vec![
-                "observe_property".into(),
-                request_id.into(),
-                "playlist-pos".into(),
-            ],
-            request_id,
-        };
+        Ok(())
+    }
 
-        let path = self.ipc_path.lock().unwrap().clone();
+    fn load_playlist_optimized(&mut self, urls: Vec<String>) -> Result<(), String> {
+        let empty = urls.is_empty();
+        self.sync_playlist(urls, 0)?;
+        if empty {
+            return Ok(());
+        }
+        // The sync keeps a first track that is already playing, a fresh load starts it over
+        self.send_command(vec!["playlist-play-index".into(), 0.into()])
+    }
 
-        #[cfg(target_os = "windows")]
-        {
-            if let Ok(mut client) = PipeClient::connect(&path) {
-                if let Ok(cmd_json) = serde_json::to_string(&cmd) {
-                    let _ = writeln!(client, "{}", cmd_json);
-                }
-            }
+    fn sync_playlist(&mut self, urls: Vec<String>, current_index: usize) -> Result<(), String> {
+        if urls.is_empty() {
+            return self.stop();
+        }
+        if current_index >= urls.len() {
+            return Err(index_out_of_range(current_index, urls.len()));
         }
 
-        #[cfg(unix)]
-        {
-            if let Ok(mut stream) = UnixStream::connect(&path) {
-                if let Ok(cmd_json) = serde_json::to_string(&cmd) {
-                    let _ = writeln!(stream, "{}", cmd_json);
+        let ipc = self.ipc()?;
+        let entries = ipc.get_property::<Vec<PlaylistEntry>>("playlist")?;
+        let current: Vec<String> = entries.iter().map(|e| e.filename.clone()).collect();
+        let current_pos = entries.iter().position(|e| e.current);
+
+        for edit in playlist_sync::diff(&current, current_pos, &urls, current_index) {
+            let command = match edit {
+                PlaylistEdit::Remove(index) => {
+                    vec!["playlist-remove".into(), (index as i64).into()]
                 }
-            }
+                PlaylistEdit::Move { from, before } => vec![
+                    "playlist-move".into(),
+                    (from as i64).into(),
+                    (before as i64).into(),
+                ],
+                PlaylistEdit::Insert { url, index } => vec![
+                    "loadfile".into(),
+                    url.into(),
+                    "insert-at".into(),
+                    (index as i64).into(),
+                ],
+                PlaylistEdit::Select(index) => vec![
+                    "set_property".into(),
+                    "playlist-pos".into(),
+                    (index as i64).into(),
+                ],
+            };
+            ipc.command(command)?;
         }
 
+        self.edit_playlist(|playlist, _| {
+            *playlist = urls;
+            current_index as i32
+        });
         Ok(())
     }
 
-    pub fn load_playlist_optimized(&mut self, urls: Vec<String>) -> Result<(), String> {
-        self.stop()?;
-
-        self.send_command(vec!["playlist-clear".into()])?;
-
-        thread::sleep(Duration::from_millis(50));
-
-        let status_before = self.get_status();
-        if status_before.playlist_count > 0 {
-            println!("Warning: Playlist wasn't cleared properly, forcing a restart");
-
-            self.send_command(vec!["quit".into()])?;
-            thread::sleep(Duration::from_millis(100));
+    fn playlist_entries(&self) -> Result<Vec<PlaylistEntry>, String> {
+        self.ipc()?.get_property("playlist")
+    }
 
-            self.init(None)?;
-            thread::sleep(Duration::from_millis(100));
+    fn set_repeat(&mut self, mode: RepeatMode) -> Result<(), String> {
+        *self.shared.repeat.lock().unwrap() = mode;
+        if let Ok(ipc) = self.ipc() {
+            self.shared.apply_repeat(&ipc)?;
         }
 
-        println!("Loading playlist with {} tracks", urls.len());
-
-        if let Some(first_url) = urls.get(0) {
-            let command = vec![
-                "loadfile".into(),
-                first_url.as_str().into(),
-                "replace".into(),
-            ];
-            self.send_command(command)?;
-
-            thread::sleep(Duration::from_millis(50));
-
-            self.send_command(vec!["set_property".into(), "playlist-pos".into(), 0.into()])?;
-        }
+        self.shared.status.lock().unwrap().repeat = mode;
+        self.shared.events.status_changed();
+        Ok(())
+    }
 
-        for (i, url) in urls.iter().skip(1).enumerate() {
-            let command = vec!["loadfile".into(), url.as_str().into(), "append".into()];
-            self.send_command(command)?;
+    fn set_ab_loop(&mut self, a: Option<f64>, b: Option<f64>) -> Result<(), String> {
+        repeat::validate_ab_loop(a, b)?;
+        let ipc = self.ipc()?;
+        ipc.set_property("ab-loop-a", repeat::ab_loop_point(a))?;
+        ipc.set_property("ab-loop-b", repeat::ab_loop_point(b))?;
 
-            if i % 10 == 0 {
-                thread::sleep(Duration::from_millis(10));
-            }
+        {
+            let mut status = self.shared.status.lock().unwrap();
+            status.ab_loop_a = a;
+            status.ab_loop_b = b;
         }
+        self.shared.events.status_changed();
+        Ok(())
+    }
 
-        thread::sleep(Duration::from_millis(150));
-        let status_after = self.get_status();
-        println!("Playlist loaded with {} items", status_after.playlist_count);
+    fn set_sleep_timer(&mut self, settings: SleepTimerSettings) -> Result<(), String> {
+        settings.validate()?;
+        self.cancel_sleep_timer()?;
 
-        if status_after.playlist_count != urls.len() as i32 {
-            println!(
-                "Warning: Playlist count mismatch - expected {}, got {}",
-                urls.len(),
-                status_after.playlist_count
-            );
+        let timer = SleepTimer::new(settings);
+        {
+            let mut status = self.shared.status.lock().unwrap();
+            status.sleep_timer = Some(timer.status(&status));
         }
+        *self.shared.sleep_timer.lock().unwrap() = Some(timer);
+        self.shared.events.status_changed();
+        Ok(())
+    }
 
-        let request_id = {
-            let mut counter = self.request_counter.lock().unwrap();
-            *counter += 1;
-            *counter
-        };
-
-        let cmd = MpvCommand {
-            command: vec![
-                "observe_property".into(),
-                request_id.into(),
-                "playlist-pos".into(),
-            ],
-            request_id,
+    fn cancel_sleep_timer(&mut self) -> Result<(), String> {
+        let Some(timer) = self.shared.sleep_timer.lock().unwrap().take() else {
+            return Ok(());
         };
 
-        let path = self.ipc_path.lock().unwrap().clone();
-
-        #[cfg(target_os = "windows")]
-        {
-            if let Ok(mut client) = PipeClient::connect(&path) {
-                if let Ok(cmd_json) = serde_json::to_string(&cmd) {
-                    let _ = writeln!(client, "{}", cmd_json);
-                }
-            }
+        let volume = self.shared.status.lock().unwrap().volume;
+        if let Ok(ipc) = self.ipc() {
+            SleepWatcher::release(&self.shared, &ipc, &timer, volume);
         }
+        self.shared.status.lock().unwrap().sleep_timer = None;
+        self.shared.events.status_changed();
+        Ok(())
+    }
 
-        #[cfg(unix)]
-        {
-            if let Ok(mut stream) = UnixStream::connect(&path) {
-                if let Ok(cmd_json) = serde_json::to_string(&cmd) {
-                    let _ = writeln!(stream, "{}", cmd_json);
-                }
-            }
-        }
+    fn audio_devices(&self) -> Result<Vec<AudioDevice>, String> {
+        self.ipc()?.get_property("audio-device-list")
+    }
 
-        {
-            let mut status = self.status.lock().unwrap();
-            status.state = "idle".to_string();
-            status.position = 0.0;
-            status.playlist_count = urls.len() as i32;
-            status.playlist_pos = 0;
+    fn set_audio_device(&mut self, name: &str) -> Result<(), String> {
+        let devices = self.audio_devices()?;
+        if !devices.iter().any(|device| device.name == name) {
+            return Err(format!("Unknown audio device '{}'", name));
         }
 
+        self.ipc()?.set_property("audio-device", name)?;
+        *self.shared.audio_device.lock().unwrap() = (name != "auto").then(|| name.to_string());
         Ok(())
     }
 
-    pub fn playlist_next(&mut self) -> Result<(), String> {
+    fn playlist_next(&mut self) -> Result<(), String> {
         let command = vec!["playlist-next".into(), "weak".into()];
         self.send_command(command)
     }
 
-    pub fn playlist_prev(&mut self) -> Result<(), String> {
+    fn playlist_prev(&mut self) -> Result<(), String> {
         let command = vec!["playlist-prev".into(), "weak".into()];
         self.send_command(command)
     }
 
-    pub fn playlist_play_index(&mut self, index: usize) -> Result<(), String> {
+    fn playlist_play_index(&mut self, index: usize) -> Result<(), String> {
         let command = vec!["set_property".into(), "playlist-pos".into(), index.into()];
         self.send_command(command)
     }
 
-    pub fn set_playlist_position(&mut self, index: usize) -> Result<(), String> {
+    fn set_playlist_position(&mut self, index: usize) -> Result<(), String> {
         let command = vec!["playlist-play-index".into(), index.into()];
         self.send_command(command)
     }
 
-    pub fn playlist_jump_to_index(&mut self, index: usize) -> Result<(), String> {
+    fn playlist_jump_to_index(&mut self, index: usize) -> Result<(), String> {
         let status = self.get_status();
 
         if status.playlist_count <= 0 {
@@ -1056,22 +1486,121 @@ impl MpvPlayer {
 
         let result = self.send_command(command);
 
-        thread::sleep(Duration::from_millis(20));
-
         {
-            let mut status = self.status.lock().unwrap();
+            let mut status = self.shared.status.lock().unwrap();
             status.playlist_pos = index as i32;
         }
 
         result
     }
 
-    pub fn play(&mut self) -> Result<(), String> {
-        let command = vec!["set_property".into(), "pause".into(), false.into()];
+    fn playlist_insert(&mut self, url: &str, index: usize) -> Result<(), String> {
+        let count = self.playlist_len();
+        if index > count {
+            return Err(index_out_of_range(index, count));
+        }
+
+        let command = vec![
+            "loadfile".into(),
+            url.into(),
+            "insert-at".into(),
+            (index as i64).into(),
+        ];
+        self.send_command(command)?;
+
+        self.edit_playlist(|playlist, pos| {
+            playlist.insert(index, url.to_string());
+            if pos >= 0 && index as i32 <= pos {
+                pos + 1
+            } else {
+                pos
+            }
+        });
+        Ok(())
+    }
+
+    fn playlist_move(&mut self, from: usize, to: usize) -> Result<(), String> {
+        let count = self.playlist_len();
+        if from >= count {
+            return Err(index_out_of_range(from, count));
+        }
+        if to >= count {
+            return Err(index_out_of_range(to, count));
+        }
+        if from == to {
+            return Ok(());
+        }
+
+        // mpv moves the entry in front of the target index, so moving down needs one past it
+        let target = if to > from { to + 1 } else { to };
+        let command = vec![
+            "playlist-move".into(),
+            (from as i64).into(),
+            (target as i64).into(),
+        ];
+        self.send_command(command)?;
+
+        self.edit_playlist(|playlist, pos| {
+            let url = playlist.remove(from);
+            playlist.insert(to, url);
+
+            let (from, to) = (from as i32, to as i32);
+            if pos == from {
+                to
+            } else if from < pos && to >= pos {
+                pos - 1
+            } else if from > pos && to <= pos {
+                pos + 1
+            } else {
+                pos
+            }
+        });
+        Ok(())
+    }
+
+    fn playlist_remove(&mut self, index: usize) -> Result<(), String> {
+        let count = self.playlist_len();
+        if index >= count {
+            return Err(index_out_of_range(index, count));
+        }
+
+        let command = vec!["playlist-remove".into(), (index as i64).into()];
         self.send_command(command)?;
 
+        self.edit_playlist(|playlist, pos| {
+            playlist.remove(index);
+            if (index as i32) < pos {
+                pos - 1
+            } else if pos >= playlist.len() as i32 {
+                -1
+            } else {
+                pos
+            }
+        });
+        Ok(())
+    }
+
+    fn playlist_clear_after_current(&mut self) -> Result<(), String> {
+        let count = self.playlist_len();
+        let keep = (self.shared.status.lock().unwrap().playlist_pos + 1).max(0) as usize;
+
+        for index in (keep..count).rev() {
+            let command = vec!["playlist-remove".into(), (index as i64).into()];
+            self.send_command(command)?;
+        }
+
+        self.edit_playlist(|playlist, pos| {
+            playlist.truncate(keep);
+            pos
+        });
+        Ok(())
+    }
+
+    fn play(&mut self) -> Result<(), String> {
+        self.ipc()?.set_property("pause", false)?;
+
         {
-            let mut status = self.status.lock().unwrap();
+            let mut status = self.shared.status.lock().unwrap();
             status.state = "playing".to_string();
             status.pause = Some(false);
         }
@@ -1079,12 +1608,11 @@ impl MpvPlayer {
         Ok(())
     }
 
-    pub fn pause(&mut self) -> Result<(), String> {
-        let command = vec!["set_property".into(), "pause".into(), true.into()];
-        self.send_command(command)?;
+    fn pause(&mut self) -> Result<(), String> {
+        self.ipc()?.set_property("pause", true)?;
 
         {
-            let mut status = self.status.lock().unwrap();
+            let mut status = self.shared.status.lock().unwrap();
             status.state = "paused".to_string();
             status.pause = Some(true);
         }
@@ -1092,12 +1620,13 @@ impl MpvPlayer {
         Ok(())
     }
 
-    pub fn stop(&mut self) -> Result<(), String> {
+    fn stop(&mut self) -> Result<(), String> {
         let command = vec!["stop".into()];
         self.send_command(command)?;
+        self.set_playlist(&[]);
 
         {
-            let mut status = self.status.lock().unwrap();
+            let mut status = self.shared.status.lock().unwrap();
             status.state = "idle".to_string();
             status.position = 0.0;
         }
@@ -1105,52 +1634,280 @@ impl MpvPlayer {
         Ok(())
     }
 
-    pub fn seek(&mut self, position: f64) -> Result<(), String> {
+    fn seek(&mut self, position: f64) -> Result<(), String> {
         let command = vec!["seek".into(), position.into(), "absolute+exact".into()];
         self.send_command(command)?;
 
         {
-            let mut status = self.status.lock().unwrap();
+            let mut status = self.shared.status.lock().unwrap();
             status.position = position;
         }
 
         Ok(())
     }
 
-    pub fn set_volume(&mut self, volume: f64) -> Result<(), String> {
-        let mpv_volume = volume * 100.0;
-        let command = vec!["set_property".into(), "volume".into(), mpv_volume.into()];
-        self.send_command(command)?;
+    fn chapters(&self) -> Result<Vec<Chapter>, String> {
+        self.ipc()?.get_property("chapter-list")
+    }
+
+    fn chapter_next(&mut self) -> Result<(), String> {
+        // Past the last chapter mpv moves on to the next file
+        let command = vec!["add".into(), "chapter".into(), 1.into()];
+        self.send_command(command)
+    }
+
+    fn chapter_prev(&mut self) -> Result<(), String> {
+        let command = vec!["add".into(), "chapter".into(), (-1).into()];
+        self.send_command(command)
+    }
+
+    fn set_chapter(&mut self, index: usize) -> Result<(), String> {
+        let count = self.shared.chapters.lock().unwrap().len();
+        chapters::validate_index(index, count)?;
+        self.ipc()?.set_property("chapter", index)
+    }
+
+    fn set_volume(&mut self, volume: f64) -> Result<(), String> {
+        self.shared.volume.lock().unwrap().validate_level(volume)?;
+        let mpv_volume = self.shared.mpv_volume(volume);
+        self.ipc()?.set_property("volume", mpv_volume)?;
 
         {
-            let mut status = self.status.lock().unwrap();
+            let mut status = self.shared.status.lock().unwrap();
             status.volume = volume;
         }
 
         Ok(())
     }
 
-    pub fn shutdown(&mut self) {
-        let _ = self.send_command(vec!["quit".into()]);
-        thread::sleep(Duration::from_millis(100));
+    fn set_mute(&mut self, muted: bool) -> Result<(), String> {
+        self.ipc()?.set_property("mute", muted)?;
 
-        if let Some(mut process) = self.process.take() {
-            let _ = process.kill();
-            let _ = process.wait();
-        }
+        self.shared.status.lock().unwrap().muted = muted;
+        self.shared.events.status_changed();
+        Ok(())
+    }
+
+    fn set_volume_settings(&mut self, settings: VolumeSettings) -> Result<(), String> {
+        settings.validate()?;
+        let max_volume = settings.max_level();
+        *self.shared.volume.lock().unwrap() = settings;
 
-        #[cfg(unix)]
         {
-            let path = self.ipc_path.lock().unwrap().clone();
-            if !path.is_empty() {
-                let _ = std::fs::remove_file(&path);
+            // Turning boost off pulls a boosted level back down to full volume
+            let mut status = self.shared.status.lock().unwrap();
+            status.volume = status.volume.min(max_volume);
+            status.max_volume = max_volume;
+        }
+        if let Ok(ipc) = self.ipc() {
+            self.shared.apply_volume(&ipc)?;
+        }
+        self.shared.events.status_changed();
+        Ok(())
+    }
+
+    fn shutdown(&mut self) {
+        if let Some(supervisor) = self.supervisor.take() {
+            supervisor.stop();
+        }
+        if let Some(crossfader) = self.crossfader.take() {
+            crossfader.stop();
+        }
+        if let Some(sleep_watcher) = self.sleep_watcher.take() {
+            sleep_watcher.stop();
+        }
+        *self.shared.sleep_timer.lock().unwrap() = None;
+        self.shared.sleep_fading.store(false, Ordering::SeqCst);
+
+        let session = self.shared.session.lock().unwrap().take();
+        if let Some(session) = session {
+            session.terminate();
+        }
+
+        self.set_playlist(&[]);
+        *self.shared.status.lock().unwrap() = MpvStatus::default();
+        self.shared.events.status_changed();
+    }
+}
+
+impl AudioEffects for MpvPlayer {
+    fn set_speed(&mut self, speed: f64, pitch_correction: PitchCorrection) -> Result<(), String> {
+        let settings = SpeedSettings {
+            speed,
+            pitch_correction,
+            ..self.shared.speed.lock().unwrap().clone()
+        };
+        self.update_speed(settings)
+    }
+
+    fn set_pitch(&mut self, semitones: f64) -> Result<(), String> {
+        let settings = SpeedSettings {
+            pitch: semitones,
+            ..self.shared.speed.lock().unwrap().clone()
+        };
+        self.update_speed(settings)
+    }
+
+    fn set_replaygain(&mut self, settings: ReplayGainSettings) -> Result<(), String> {
+        *self.shared.replaygain.lock().unwrap() = settings;
+        if let Ok(ipc) = self.ipc() {
+            self.shared.apply_replaygain(&ipc)?;
+        }
+        self.shared.update_replaygain_gain();
+        Ok(())
+    }
+
+    fn set_normalization(&mut self, settings: NormalizationSettings) -> Result<(), String> {
+        settings.validate()?;
+        *self.shared.normalization.lock().unwrap() = settings.clone();
+        if let Ok(ipc) = self.ipc() {
+            self.shared.apply_normalization(&ipc)?;
+            self.shared.apply_replaygain(&ipc)?;
+        }
+
+        self.shared.status.lock().unwrap().normalization = settings.mode;
+        self.shared.update_replaygain_gain();
+        Ok(())
+    }
+
+    fn set_equalizer(&mut self, settings: EqualizerSettings) -> Result<(), String> {
+        settings.validate()?;
+        self.shared.equalizer.lock().unwrap().set_manual(settings);
+        if let Ok(ipc) = self.ipc() {
+            self.shared.apply_equalizer(&ipc)?;
+        }
+        self.shared.equalizer_changed();
+        Ok(())
+    }
+
+    fn equalizer(&self) -> EqualizerSettings {
+        self.shared.equalizer.lock().unwrap().active().clone()
+    }
+
+    fn set_genre_equalizers(
+        &mut self,
+        presets: HashMap<String, EqualizerSettings>,
+    ) -> Result<(), String> {
+        for settings in presets.values() {
+            settings.validate()?;
+        }
+
+        let changed = {
+            let mut state = self.shared.equalizer.lock().unwrap();
+            let previous = state.active().clone();
+            state.set_genre_presets(presets);
+            *state.active() != previous
+        };
+        if changed {
+            if let Ok(ipc) = self.ipc() {
+                self.shared.apply_equalizer(&ipc)?;
+            }
+            self.shared.equalizer_changed();
+        }
+        Ok(())
+    }
+
+    fn set_crossfade(&mut self, settings: CrossfadeSettings) -> Result<(), String> {
+        settings.validate()?;
+        *self.shared.crossfade.lock().unwrap() = settings;
+        Ok(())
+    }
+}
+
+impl MpvExtensions for MpvPlayer {
+    fn get_property(&self, name: &str) -> Result<serde_json::Value, String> {
+        self.property_allowlist.check_readable(name)?;
+        self.ipc()?.get_property(name)
+    }
+
+    fn set_property(&mut self, name: &str, value: serde_json::Value) -> Result<(), String> {
+        self.property_allowlist.check_writable(name)?;
+        self.ipc()?.set_property(name, value)
+    }
+
+    fn observe_property(&mut self, name: &str) -> Result<(), String> {
+        self.property_allowlist.check_readable(name)?;
+        if self.shared.observed.lock().unwrap().contains_key(name) {
+            return Ok(());
+        }
+
+        let id = self.next_observe_id;
+        self.next_observe_id += 1;
+        if !OBSERVED_PROPERTIES.contains(&name) {
+            if let Ok(ipc) = self.ipc() {
+                ipc.observe_property(id, name)?;
+            }
+        }
+        self.shared
+            .observed
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), id);
+        Ok(())
+    }
+
+    fn unobserve_property(&mut self, name: &str) -> Result<(), String> {
+        let Some(id) = self.shared.observed.lock().unwrap().remove(name) else {
+            return Ok(());
+        };
+        if !OBSERVED_PROPERTIES.contains(&name) {
+            if let Ok(ipc) = self.ipc() {
+                ipc.unobserve_property(id)?;
             }
         }
+        Ok(())
+    }
+
+    fn set_property_allowlist(&mut self, allowlist: PropertyAllowlist) -> Result<(), String> {
+        allowlist.validate()?;
+        self.property_allowlist = allowlist;
+
+        let revoked: Vec<String> = self
+            .shared
+            .observed
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|name| self.property_allowlist.check_readable(name).is_err())
+            .cloned()
+            .collect();
+        for name in revoked {
+            self.unobserve_property(&name)?;
+        }
+        Ok(())
+    }
+
+    fn logs(&self) -> Vec<LogEntry> {
+        self.shared.logs.lock().unwrap().entries()
+    }
+
+    fn set_log_level(&mut self, level: LogLevel) -> Result<(), String> {
+        *self.shared.log_level.lock().unwrap() = level;
+        if let Ok(ipc) = self.ipc() {
+            self.shared.apply_log_level(&ipc)?;
+        }
+        Ok(())
+    }
 
-        self.ipc_thread = None;
+    fn report(&mut self, level: LogLevel, text: &str) {
+        self.shared.log(level, text);
+    }
+
+    // Launch options, a running mpv keeps its own until it is started again
+    fn set_mpv_options(&mut self, options: MpvOptions) -> Result<(), String> {
+        options.validate()?;
+        self.options = options;
+        Ok(())
+    }
+}
+
+fn index_out_of_range(index: usize, count: usize) -> String {
+    format!("Index {} out of range, playlist has {} items", index, count)
+}
 
-        let mut status = self.status.lock().unwrap();
-        *status = MpvStatus::default();
+impl Default for MpvPlayer {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
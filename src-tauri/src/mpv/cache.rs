@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CacheRange {
+    pub start: f64,
+    pub end: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct CacheStatus {
+    // Playback is held until enough of the stream is buffered again
+    pub paused_for_cache: bool,
+    // Percentage of the buffering target reached while paused for cache
+    pub buffering_state: Option<u32>,
+    // Seconds buffered ahead of the playback position
+    pub duration: Option<f64>,
+    // Buffered parts of the file, for drawing on the seekbar
+    pub ranges: Vec<CacheRange>,
+    // Times playback stalled since mpv started
+    pub stalls: u32,
+}
+
+impl CacheStatus {
+    // Returns whether playback started or stopped waiting on the network
+    pub fn set_paused_for_cache(&mut self, paused: bool) -> bool {
+        if paused == self.paused_for_cache {
+            return false;
+        }
+        if paused {
+            self.stalls += 1;
+        }
+        self.paused_for_cache = paused;
+        true
+    }
+
+    pub fn apply_demuxer_state(&mut self, state: &serde_json::Value) {
+        self.ranges = state
+            .get("seekable-ranges")
+            .cloned()
+            .and_then(|ranges| serde_json::from_value(ranges).ok())
+            .unwrap_or_default();
+    }
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Chapter {
+    #[serde(default)]
+    pub title: Option<String>,
+    // Start of the chapter in seconds
+    pub time: f64,
+}
+
+pub fn title(chapters: &[Chapter], chapter: Option<i32>) -> Option<String> {
+    let index = usize::try_from(chapter?).ok()?;
+    chapters.get(index)?.title.clone()
+}
+
+pub fn validate_index(index: usize, count: usize) -> Result<(), String> {
+    if count == 0 {
+        return Err("The current file has no chapters".to_string());
+    }
+    if index >= count {
+        return Err(format!(
+            "Chapter {} out of range, file has {} chapters",
+            index, count
+        ));
+    }
+    Ok(())
+}
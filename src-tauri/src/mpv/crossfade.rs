@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use std::f64::consts::FRAC_PI_2;
+
+const MIN_DURATION: f64 = 0.5;
+const MAX_DURATION: f64 = 12.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FadeCurve {
+    Linear,
+    // Keeps the combined loudness steady, which suits unrelated tracks best
+    #[default]
+    EqualPower,
+    SCurve,
+}
+
+impl FadeCurve {
+    // Amplitude gains of the outgoing and incoming track at `progress` through the fade
+    pub fn gains(&self, progress: f64) -> (f64, f64) {
+        let t = progress.clamp(0.0, 1.0);
+        match self {
+            FadeCurve::Linear => (1.0 - t, t),
+            FadeCurve::EqualPower => ((t * FRAC_PI_2).cos(), (t * FRAC_PI_2).sin()),
+            FadeCurve::SCurve => {
+                let s = t * t * (3.0 - 2.0 * t);
+                (1.0 - s, s)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CrossfadeSettings {
+    pub enabled: bool,
+    pub duration: f64,
+    #[serde(default)]
+    pub curve: FadeCurve,
+    // Albums are usually mastered to flow into the next track, so leave those gapless
+    #[serde(default = "default_skip_same_album")]
+    pub skip_same_album: bool,
+}
+
+fn default_skip_same_album() -> bool {
+    true
+}
+
+impl Default for CrossfadeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            duration: 5.0,
+            curve: FadeCurve::default(),
+            skip_same_album: true,
+        }
+    }
+}
+
+impl CrossfadeSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(MIN_DURATION..=MAX_DURATION).contains(&self.duration) {
+            return Err(format!(
+                "Crossfade duration {} s is out of range ({} to {} s)",
+                self.duration, MIN_DURATION, MAX_DURATION
+            ));
+        }
+        Ok(())
+    }
+}
+
+// mpv's volume scale is cubic, so an amplitude gain maps to its cube root
+pub fn faded_volume(volume: f64, gain: f64) -> f64 {
+    volume * gain.max(0.0).cbrt()
+}
@@ -0,0 +1,434 @@
+use super::crossfade::{faded_volume, CrossfadeSettings};
+use super::ipc::MpvIpc;
+use super::logs::LogLevel;
+use super::repeat::RepeatMode;
+use super::session::{LaunchConfig, MpvSession};
+use super::{MpvPlayer, MpvStatus, SharedState, StandbyFilters};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const TICK_INTERVAL: Duration = Duration::from_millis(50);
+// How long before the fade the standby instance starts buffering the next track
+const PRELOAD_SECS: f64 = 10.0;
+// Hand over just before the outgoing track ends, so it never reaches its end
+const HANDOVER_MARGIN: f64 = 0.25;
+// A seek back out of the fade leaves more than this much of the track to play
+const SEEK_TOLERANCE: f64 = 1.0;
+
+struct Prepared {
+    pos: i32,
+    playlist: Vec<String>,
+    filters: StandbyFilters,
+}
+
+enum Phase {
+    Idle,
+    Preparing(Prepared),
+    Fading {
+        prepared: Prepared,
+        started: Instant,
+        length: f64,
+    },
+    // Plays gapless into the next track, after a fallback or an aborted fade
+    Skipped {
+        pos: i32,
+    },
+}
+
+// Fades between the live mpv instance and a standby one that has the next track loaded,
+// then swaps them so the standby becomes the live instance
+pub struct Crossfader {
+    stop: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Crossfader {
+    pub fn start(shared: SharedState, launch: LaunchConfig) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_clone = Arc::clone(&stop);
+
+        let standby_socket = format!("{}-standby", launch.socket_path);
+        let launches = [launch.with_socket_path(standby_socket), launch];
+        let thread = thread::spawn(move || {
+            Self::run(shared, launches, stop_clone);
+        });
+
+        Self {
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    pub fn stop(mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+
+    fn run(shared: SharedState, launches: [LaunchConfig; 2], stop: Arc<AtomicBool>) {
+        let mut standby: Option<MpvSession> = None;
+        let mut phase = Phase::Idle;
+
+        while !stop.load(Ordering::SeqCst) {
+            thread::sleep(TICK_INTERVAL);
+            phase = Self::tick(&shared, &launches, &mut standby, phase);
+        }
+
+        if let Phase::Fading { .. } = phase {
+            Self::abort(&shared, &mut standby);
+        }
+        if let Some(session) = standby {
+            session.terminate();
+        }
+    }
+
+    fn tick(
+        shared: &SharedState,
+        launches: &[LaunchConfig; 2],
+        standby: &mut Option<MpvSession>,
+        phase: Phase,
+    ) -> Phase {
+        let settings = shared.crossfade.lock().unwrap().clone();
+        let status = shared.status.lock().unwrap().clone();
+        let playlist = shared.playlist.lock().unwrap().clone();
+        let pos = status.playlist_pos;
+        // In wall-clock time, which is what the fade runs on
+        let remaining = (status.duration - status.position) / status.speed.max(0.01);
+
+        match phase {
+            Phase::Idle => {
+                // Nothing to fade into any time soon, so don't keep a second mpv running
+                if !settings.enabled
+                    || matches!(status.state.as_str(), "idle" | "stopped" | "ended")
+                {
+                    if let Some(session) = standby.take() {
+                        session.terminate();
+                    }
+                    return Phase::Idle;
+                }
+
+                let next = (pos + 1) as usize;
+                if status.state != "playing"
+                    || Self::looping(&status)
+                    || Self::sleeping(&status, remaining)
+                    || pos < 0
+                    || next >= playlist.len()
+                    || status.duration <= 0.0
+                    || remaining > settings.duration + PRELOAD_SECS
+                {
+                    return Phase::Idle;
+                }
+
+                match Self::prepare(shared, launches, standby, &playlist, next) {
+                    Ok(filters) => Phase::Preparing(Prepared {
+                        pos,
+                        playlist,
+                        filters,
+                    }),
+                    Err(e) => {
+                        shared.log(
+                            LogLevel::Error,
+                            &format!("Failed to prepare crossfade: {}", e),
+                        );
+                        Self::release(standby);
+                        Phase::Skipped { pos }
+                    }
+                }
+            }
+            Phase::Preparing(prepared) => {
+                if !settings.enabled
+                    || pos != prepared.pos
+                    || playlist != prepared.playlist
+                    || Self::looping(&status)
+                    || Self::sleeping(&status, remaining)
+                {
+                    Self::release(standby);
+                    return Phase::Idle;
+                }
+                if remaining > settings.duration || status.pause == Some(true) {
+                    return Phase::Preparing(prepared);
+                }
+
+                let next = &prepared.playlist[(pos + 1) as usize];
+                match Self::begin(shared, standby.as_ref(), &settings, next, remaining) {
+                    Ok(Some(length)) => Phase::Fading {
+                        prepared,
+                        started: Instant::now(),
+                        length,
+                    },
+                    Ok(None) => {
+                        Self::release(standby);
+                        Phase::Skipped { pos }
+                    }
+                    Err(e) => {
+                        shared.log(
+                            LogLevel::Error,
+                            &format!("Failed to start crossfade: {}", e),
+                        );
+                        Self::abort(shared, standby);
+                        Phase::Skipped { pos }
+                    }
+                }
+            }
+            Phase::Fading {
+                prepared,
+                started,
+                length,
+            } => {
+                if !settings.enabled
+                    || pos != prepared.pos
+                    || playlist != prepared.playlist
+                    || status.pause == Some(true)
+                    || remaining > length + SEEK_TOLERANCE
+                    || Self::sleeping(&status, remaining)
+                {
+                    Self::abort(shared, standby);
+                    return Phase::Skipped { pos };
+                }
+
+                let progress = started.elapsed().as_secs_f64() / length;
+                let result = if progress >= 1.0 {
+                    Self::hand_over(shared, standby, &prepared)
+                } else {
+                    Self::step(shared, standby.as_ref(), &settings, progress, &status)
+                };
+
+                match result {
+                    Ok(()) if progress >= 1.0 => Phase::Idle,
+                    Ok(()) => Phase::Fading {
+                        prepared,
+                        started,
+                        length,
+                    },
+                    Err(e) => {
+                        shared.log(
+                            LogLevel::Warn,
+                            &format!("Crossfade failed, continuing gapless: {}", e),
+                        );
+                        Self::abort(shared, standby);
+                        Phase::Skipped { pos }
+                    }
+                }
+            }
+            Phase::Skipped { pos: skipped } if skipped == pos => Phase::Skipped { pos },
+            Phase::Skipped { .. } => Phase::Idle,
+        }
+    }
+
+    // Loops stay inside the current track, there's nothing to fade into
+    fn looping(status: &MpvStatus) -> bool {
+        status.repeat == RepeatMode::One || status.ab_loop_b.is_some()
+    }
+
+    // The sleep timer stops playback before the next track gets going, or is fading it out
+    fn sleeping(status: &MpvStatus, remaining: f64) -> bool {
+        status.sleep_timer.as_ref().is_some_and(|timer| {
+            timer.fading
+                || timer.remaining_tracks == Some(1)
+                || timer.remaining_secs.is_some_and(|secs| secs <= remaining)
+        })
+    }
+
+    fn prepare(
+        shared: &SharedState,
+        launches: &[LaunchConfig; 2],
+        standby: &mut Option<MpvSession>,
+        playlist: &[String],
+        next: usize,
+    ) -> Result<StandbyFilters, String> {
+        if standby
+            .as_mut()
+            .is_some_and(|session| session.exit_code().is_some())
+        {
+            if let Some(session) = standby.take() {
+                session.kill();
+            }
+        }
+
+        let session = match standby {
+            Some(session) => session,
+            None => {
+                // The instances swap roles, so use whichever socket the live one isn't on
+                let live_socket = shared
+                    .session
+                    .lock()
+                    .unwrap()
+                    .as_ref()
+                    .map(|session| session.launch.socket_path.clone());
+                let launch = launches
+                    .iter()
+                    .find(|launch| Some(&launch.socket_path) != live_socket.as_ref())
+                    .cloned()
+                    .ok_or("No socket available for the standby MPV instance")?;
+
+                standby.insert(MpvPlayer::spawn_session(shared, launch, false)?)
+            }
+        };
+
+        let ipc = &session.ipc;
+        ipc.set_property("pause", true)?;
+        ipc.set_property("volume", 0.0)?;
+        ipc.set_property("keep-open", "yes")?;
+        let filters = shared.prepare_standby(ipc)?;
+
+        // Only the next track is preloaded, the rest of the playlist follows at the handover
+        ipc.command(vec![
+            "loadfile".into(),
+            playlist[next].as_str().into(),
+            "replace".into(),
+        ])?;
+        Ok(filters)
+    }
+
+    fn begin(
+        shared: &SharedState,
+        standby: Option<&MpvSession>,
+        settings: &CrossfadeSettings,
+        next: &str,
+        remaining: f64,
+    ) -> Result<Option<f64>, String> {
+        let Some(session) = standby else {
+            return Ok(None);
+        };
+        let length = settings.duration.min(remaining - HANDOVER_MARGIN);
+        if length <= 0.0 {
+            return Ok(None);
+        }
+
+        let primary = shared.ipc()?;
+        if !Self::buffered(&session.ipc, next, length) {
+            return Ok(None);
+        }
+        if settings.skip_same_album && Self::same_album(&primary, &session.ipc) {
+            return Ok(None);
+        }
+
+        shared.fading.store(true, Ordering::SeqCst);
+        // Pauses on the last frame instead of moving on, the standby plays the next track
+        primary.set_property("keep-open", "always")?;
+        session.ipc.set_property("pause", false)?;
+        Ok(Some(length))
+    }
+
+    fn buffered(ipc: &MpvIpc, url: &str, needed: f64) -> bool {
+        let loaded = ipc
+            .get_property::<String>("path")
+            .is_ok_and(|path| path == url);
+        loaded
+            && (ipc
+                .get_property::<bool>("demuxer-cache-idle")
+                .unwrap_or(false)
+                || ipc
+                    .get_property::<f64>("demuxer-cache-duration")
+                    .is_ok_and(|cached| cached >= needed))
+    }
+
+    fn same_album(primary: &MpvIpc, standby: &MpvIpc) -> bool {
+        let album = |ipc: &MpvIpc| {
+            ipc.get_property::<String>("metadata/by-key/album")
+                .ok()
+                .filter(|album| !album.is_empty())
+        };
+        matches!((album(primary), album(standby)), (Some(a), Some(b)) if a == b)
+    }
+
+    fn step(
+        shared: &SharedState,
+        standby: Option<&MpvSession>,
+        settings: &CrossfadeSettings,
+        progress: f64,
+        status: &MpvStatus,
+    ) -> Result<(), String> {
+        let session = standby.ok_or("Standby MPV instance went away")?;
+        let (outgoing, incoming) = settings.curve.gains(progress);
+        let volume = shared.mpv_volume(status.volume);
+        shared
+            .ipc()?
+            .set_property("volume", faded_volume(volume, outgoing))?;
+        session
+            .ipc
+            .set_property("volume", faded_volume(volume, incoming))
+    }
+
+    fn hand_over(
+        shared: &SharedState,
+        standby: &mut Option<MpvSession>,
+        prepared: &Prepared,
+    ) -> Result<(), String> {
+        let incoming = standby.take().ok_or("Standby MPV instance went away")?;
+        let ipc = Arc::clone(&incoming.ipc);
+        // Before it goes live, so filling in the entries ahead of it doesn't read as track changes
+        Self::fill_playlist(&ipc, &prepared.playlist, (prepared.pos + 1) as usize)?;
+        shared.apply_volume(&ipc)?;
+
+        let outgoing = {
+            let mut session = shared.session.lock().unwrap();
+            let outgoing = session.take();
+            if let Some(outgoing) = &outgoing {
+                outgoing.live.store(false, Ordering::SeqCst);
+            }
+            incoming.live.store(true, Ordering::SeqCst);
+            *session = Some(incoming);
+            outgoing
+        };
+        shared.fading.store(false, Ordering::SeqCst);
+
+        if let Some(outgoing) = outgoing {
+            let _ = outgoing.ipc.command(vec!["stop".into()]);
+            *standby = Some(outgoing);
+        }
+
+        shared.adopt_standby_filters(&prepared.filters);
+
+        // Settings may have changed on the outgoing instance during the fade
+        shared.apply_replaygain(&ipc)?;
+        shared.apply_equalizer(&ipc)?;
+        shared.apply_speed(&ipc)?;
+        shared.apply_normalization(&ipc)?;
+        shared.apply_repeat(&ipc)?;
+        shared.apply_observed(&ipc)?;
+        shared.apply_log_level(&ipc)?;
+        shared.refresh_status(&ipc);
+        shared.playlist_changed();
+        Ok(())
+    }
+
+    // Puts the rest of the playlist around the one track the standby instance has loaded
+    fn fill_playlist(ipc: &MpvIpc, playlist: &[String], current: usize) -> Result<(), String> {
+        let commands = playlist
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != current)
+            .map(|(i, url)| {
+                let mut command = vec!["loadfile".into(), url.as_str().into()];
+                if i < current {
+                    command.extend(["insert-at".into(), (i as i64).into()]);
+                } else {
+                    command.push("append".into());
+                }
+                command
+            })
+            .collect();
+        ipc.command_batch(commands)
+    }
+
+    fn abort(shared: &SharedState, standby: &mut Option<MpvSession>) {
+        if shared.fading.swap(false, Ordering::SeqCst) {
+            if let Ok(primary) = shared.ipc() {
+                let volume = shared.status.lock().unwrap().volume;
+                let _ = primary.set_property("volume", shared.mpv_volume(volume));
+                let _ = primary.set_property("keep-open", "yes");
+            }
+        }
+        Self::release(standby);
+    }
+
+    fn release(standby: &mut Option<MpvSession>) {
+        if let Some(session) = standby {
+            let _ = session.ipc.command(vec!["stop".into()]);
+        }
+    }
+}
@@ -0,0 +1,382 @@
+use super::filters;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+
+pub const GRAPHIC_FREQUENCIES: [f64; 10] = [
+    31.0, 62.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0, 16000.0,
+];
+// Roughly one octave wide, matching the spacing of the graphic bands
+const GRAPHIC_Q: f64 = 1.41;
+const MAX_BANDS: usize = 20;
+const MAX_GAIN: f64 = 24.0;
+pub(super) const FILTER_LABEL: &str = "eq";
+
+const BUILTIN_PRESETS: [(&str, [f64; 10]); 7] = [
+    ("Flat", [0.0; 10]),
+    (
+        "Bass Boost",
+        [6.0, 5.0, 4.0, 2.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    ),
+    (
+        "Treble Boost",
+        [0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 2.0, 4.0, 5.0, 6.0],
+    ),
+    (
+        "Vocal",
+        [-2.0, -2.0, -1.0, 1.0, 3.0, 3.0, 2.0, 1.0, 0.0, -1.0],
+    ),
+    ("Rock", [4.0, 3.0, 2.0, 0.0, -1.0, -1.0, 1.0, 2.0, 3.0, 4.0]),
+    (
+        "Electronic",
+        [5.0, 4.0, 1.0, 0.0, -2.0, 1.0, 0.0, 1.0, 4.0, 5.0],
+    ),
+    (
+        "Classical",
+        [3.0, 2.0, 1.0, 0.0, 0.0, 0.0, -1.0, -1.0, 1.0, 2.0],
+    ),
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EqualizerBand {
+    pub frequency: f64,
+    pub gain: f64,
+    #[serde(default = "default_q")]
+    pub q: f64,
+}
+
+fn default_q() -> f64 {
+    GRAPHIC_Q
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EqualizerSettings {
+    pub enabled: bool,
+    #[serde(default)]
+    pub preamp: f64,
+    // The graphic equalizer uses GRAPHIC_FREQUENCIES, parametric setups pick their own bands
+    pub bands: Vec<EqualizerBand>,
+}
+
+impl Default for EqualizerSettings {
+    fn default() -> Self {
+        Self::graphic([0.0; 10])
+    }
+}
+
+impl EqualizerSettings {
+    pub fn graphic(gains: [f64; 10]) -> Self {
+        Self {
+            enabled: false,
+            preamp: 0.0,
+            bands: GRAPHIC_FREQUENCIES
+                .iter()
+                .zip(gains)
+                .map(|(frequency, gain)| EqualizerBand {
+                    frequency: *frequency,
+                    gain,
+                    q: GRAPHIC_Q,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.bands.is_empty() || self.bands.len() > MAX_BANDS {
+            return Err(format!(
+                "Equalizer needs between 1 and {} bands, got {}",
+                MAX_BANDS,
+                self.bands.len()
+            ));
+        }
+        if !(-MAX_GAIN..=MAX_GAIN).contains(&self.preamp) {
+            return Err(format!("Preamp {} dB is out of range", self.preamp));
+        }
+
+        for band in &self.bands {
+            if !(20.0..=20000.0).contains(&band.frequency) {
+                return Err(format!(
+                    "Band frequency {} Hz is out of range",
+                    band.frequency
+                ));
+            }
+            if !(-MAX_GAIN..=MAX_GAIN).contains(&band.gain) {
+                return Err(format!(
+                    "Gain {} dB for the {} Hz band is out of range",
+                    band.gain, band.frequency
+                ));
+            }
+            if !(0.1..=10.0).contains(&band.q) {
+                return Err(format!(
+                    "Q {} for the {} Hz band is out of range",
+                    band.q, band.frequency
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // Changing anything but the gains needs the filter chain to be rebuilt
+    pub fn layout(&self) -> Vec<(f64, f64)> {
+        self.bands
+            .iter()
+            .map(|band| (band.frequency, band.q))
+            .collect()
+    }
+
+    pub fn filter(&self) -> String {
+        let (preamp, gains) = self.applied_gains();
+        let mut filters = vec![format!("volume={}dB", preamp)];
+        for (band, gain) in self.bands.iter().zip(gains) {
+            filters.push(format!(
+                "equalizer=f={}:t=q:w={}:g={}",
+                band.frequency, band.q, gain
+            ));
+        }
+        filters::labelled(FILTER_LABEL, &format!("lavfi=[{}]", filters.join(",")))
+    }
+
+    // Updates the gains of an installed chain in place, so there is no audio reinit
+    pub fn gain_commands(&self) -> Vec<Vec<serde_json::Value>> {
+        let (preamp, gains) = self.applied_gains();
+        let mut commands = vec![af_command(
+            "volume",
+            format!("{}dB", preamp),
+            "Parsed_volume_0".to_string(),
+        )];
+        for (i, gain) in gains.into_iter().enumerate() {
+            // lavfi names parsed filters by their position in the whole graph
+            commands.push(af_command(
+                "g",
+                gain.to_string(),
+                format!("Parsed_equalizer_{}", i + 1),
+            ));
+        }
+        commands
+    }
+
+    // Bypass keeps the chain in place with neutral gains
+    fn applied_gains(&self) -> (f64, Vec<f64>) {
+        if self.enabled {
+            (
+                self.preamp,
+                self.bands.iter().map(|band| band.gain).collect(),
+            )
+        } else {
+            (0.0, vec![0.0; self.bands.len()])
+        }
+    }
+}
+
+fn af_command(command: &str, argument: String, target: String) -> Vec<serde_json::Value> {
+    vec![
+        "af-command".into(),
+        FILTER_LABEL.into(),
+        command.into(),
+        argument.into(),
+        target.into(),
+    ]
+}
+
+#[derive(Default)]
+pub struct EqualizerState {
+    manual: EqualizerSettings,
+    genre_presets: HashMap<String, EqualizerSettings>,
+    genre: Option<String>,
+    manual_override: bool,
+    // What the af option was last built from, live gain updates don't touch it
+    installed: Option<EqualizerSettings>,
+    stale: bool,
+}
+
+impl EqualizerState {
+    pub fn active(&self) -> &EqualizerSettings {
+        self.genre_preset()
+            .map(|(_, settings)| settings)
+            .unwrap_or(&self.manual)
+    }
+
+    // The genre whose preset is currently applied, if any
+    pub fn active_genre(&self) -> Option<String> {
+        self.genre_preset().map(|(genre, _)| genre.to_string())
+    }
+
+    fn genre_preset(&self) -> Option<(&str, &EqualizerSettings)> {
+        if self.manual_override {
+            return None;
+        }
+        let genre = self.genre.as_deref()?;
+
+        // Multi-valued tags like "Rock; Alternative" use the first genre that has a preset
+        std::iter::once(genre)
+            .chain(genre.split([';', '/', ',']))
+            .map(|name| name.trim().to_lowercase())
+            .find_map(|name| self.genre_presets.get_key_value(&name))
+            .map(|(name, settings)| (name.as_str(), settings))
+    }
+
+    pub fn set_manual(&mut self, settings: EqualizerSettings) {
+        self.manual = settings;
+        self.manual_override = true;
+    }
+
+    pub fn set_genre_presets(&mut self, presets: HashMap<String, EqualizerSettings>) {
+        self.genre_presets = presets
+            .into_iter()
+            .map(|(genre, settings)| (genre.trim().to_lowercase(), settings))
+            .collect();
+    }
+
+    // A manual choice sticks until a track with a different genre comes along
+    pub fn set_genre(&mut self, genre: Option<String>) {
+        if self.genre != genre {
+            self.genre = genre;
+            self.manual_override = false;
+        }
+    }
+
+    pub fn installed(&self) -> Option<&EqualizerSettings> {
+        self.installed.as_ref()
+    }
+
+    pub fn set_installed(&mut self, settings: Option<EqualizerSettings>) {
+        self.installed = settings;
+        self.stale = false;
+    }
+
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    pub fn set_stale(&mut self, stale: bool) {
+        self.stale = stale;
+    }
+}
+
+pub struct PresetStore {
+    dir: PathBuf,
+}
+
+impl PresetStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn list(&self) -> Result<Vec<String>, String> {
+        let mut names: Vec<String> = BUILTIN_PRESETS
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect();
+
+        let entries = match fs::read_dir(self.presets_dir()) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(names),
+            Err(e) => return Err(format!("Failed to read equalizer presets: {}", e)),
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                if !names.iter().any(|existing| existing == name) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        Ok(names)
+    }
+
+    // Saved presets shadow built-in ones with the same name
+    pub fn load(&self, name: &str) -> Result<EqualizerSettings, String> {
+        let path = self.preset_path(name)?;
+        match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|e| format!("Equalizer preset '{}' is corrupt: {}", name, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BUILTIN_PRESETS
+                .iter()
+                .find(|(builtin, _)| *builtin == name.trim())
+                .map(|(_, gains)| EqualizerSettings {
+                    enabled: true,
+                    ..EqualizerSettings::graphic(*gains)
+                })
+                .ok_or_else(|| format!("Unknown equalizer preset '{}'", name)),
+            Err(e) => Err(format!("Failed to read equalizer preset '{}': {}", name, e)),
+        }
+    }
+
+    pub fn save(&self, name: &str, settings: &EqualizerSettings) -> Result<(), String> {
+        settings.validate()?;
+        let path = self.preset_path(name)?;
+        let contents = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+
+        fs::create_dir_all(self.presets_dir())
+            .and_then(|_| fs::write(&path, contents))
+            .map_err(|e| format!("Failed to save equalizer preset '{}': {}", name, e))
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), String> {
+        let path = self.preset_path(name)?;
+        fs::remove_file(&path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => format!("No saved equalizer preset '{}'", name),
+            _ => format!("Failed to delete equalizer preset '{}': {}", name, e),
+        })
+    }
+
+    pub fn genre_presets(&self) -> Result<BTreeMap<String, String>, String> {
+        match fs::read_to_string(self.genres_path()) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|e| format!("Genre preset mapping is corrupt: {}", e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(format!("Failed to read genre preset mapping: {}", e)),
+        }
+    }
+
+    pub fn set_genre_presets(&self, presets: &BTreeMap<String, String>) -> Result<(), String> {
+        let contents = serde_json::to_string_pretty(presets).map_err(|e| e.to_string())?;
+        fs::create_dir_all(&self.dir)
+            .and_then(|_| fs::write(self.genres_path(), contents))
+            .map_err(|e| format!("Failed to save genre preset mapping: {}", e))
+    }
+
+    // Resolves the genre mapping into settings, skipping presets that no longer exist and
+    // returning why each was skipped
+    pub fn resolve_genre_presets(
+        &self,
+    ) -> Result<(HashMap<String, EqualizerSettings>, Vec<String>), String> {
+        let mut resolved = HashMap::new();
+        let mut skipped = Vec::new();
+        for (genre, preset) in self.genre_presets()? {
+            match self.load(&preset) {
+                Ok(settings) => {
+                    resolved.insert(genre, settings);
+                }
+                Err(e) => skipped.push(format!(
+                    "Ignoring equalizer preset for genre '{}': {}",
+                    genre, e
+                )),
+            }
+        }
+        Ok((resolved, skipped))
+    }
+
+    fn presets_dir(&self) -> PathBuf {
+        self.dir.join("presets")
+    }
+
+    fn genres_path(&self) -> PathBuf {
+        self.dir.join("genres.json")
+    }
+
+    fn preset_path(&self, name: &str) -> Result<PathBuf, String> {
+        let name = name.trim();
+        if name.is_empty()
+            || name.starts_with('.')
+            || name.contains(['/', '\\', ':', '*', '?', '"', '<', '>', '|'])
+        {
+            return Err(format!("Invalid equalizer preset name '{}'", name));
+        }
+        Ok(self.presets_dir().join(format!("{}.json", name)))
+    }
+}
@@ -0,0 +1,202 @@
+use super::equalizer::EqualizerSettings;
+use super::logs::LogEntry;
+use super::{AudioDevice, MpvStatus};
+use serde::Serialize;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const STATUS_EVENT: &str = "player://status";
+pub const TRACK_CHANGED_EVENT: &str = "player://track-changed";
+pub const ENDED_EVENT: &str = "player://ended";
+pub const ERROR_EVENT: &str = "player://error";
+pub const BACKEND_CRASHED_EVENT: &str = "player://backend-crashed";
+pub const PLAYLIST_CHANGED_EVENT: &str = "player://playlist-changed";
+pub const AUDIO_DEVICES_CHANGED_EVENT: &str = "player://audio-devices-changed";
+pub const EQUALIZER_CHANGED_EVENT: &str = "player://equalizer-changed";
+pub const BUFFERING_EVENT: &str = "player://buffering";
+pub const PROPERTY_CHANGED_EVENT: &str = "player://property-changed";
+pub const LOG_EVENT: &str = "player://log";
+
+const STATUS_COALESCE_INTERVAL: Duration = Duration::from_millis(100);
+// A chatty log level can produce thousands of lines a second
+const LOG_BATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct TrackChange {
+    pub playlist_pos: i32,
+    pub playlist_count: i32,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct PlaybackEnded {
+    pub reason: String,
+    pub playlist_entry_id: Option<i64>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct PlaybackError {
+    pub message: String,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct BackendCrash {
+    pub exit_code: Option<i32>,
+    pub restart_attempt: usize,
+    pub will_restart: bool,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct PlaylistChange {
+    pub entries: Vec<String>,
+    pub playlist_pos: i32,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct AudioDevicesChange {
+    pub devices: Vec<AudioDevice>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct EqualizerChange {
+    pub settings: EqualizerSettings,
+    // Set when the settings come from the current track's genre preset
+    pub genre: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct BufferingChange {
+    pub buffering: bool,
+    pub stalls: u32,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct PropertyChange {
+    pub name: String,
+    pub value: serde_json::Value,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum PlayerEvent {
+    Status(Box<MpvStatus>),
+    TrackChanged(TrackChange),
+    Ended(PlaybackEnded),
+    Error(PlaybackError),
+    BackendCrashed(BackendCrash),
+    PlaylistChanged(PlaylistChange),
+    AudioDevicesChanged(AudioDevicesChange),
+    EqualizerChanged(EqualizerChange),
+    Buffering(BufferingChange),
+    PropertyChanged(PropertyChange),
+    Log(Vec<LogEntry>),
+}
+
+impl PlayerEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PlayerEvent::Status(_) => STATUS_EVENT,
+            PlayerEvent::TrackChanged(_) => TRACK_CHANGED_EVENT,
+            PlayerEvent::Ended(_) => ENDED_EVENT,
+            PlayerEvent::Error(_) => ERROR_EVENT,
+            PlayerEvent::BackendCrashed(_) => BACKEND_CRASHED_EVENT,
+            PlayerEvent::PlaylistChanged(_) => PLAYLIST_CHANGED_EVENT,
+            PlayerEvent::AudioDevicesChanged(_) => AUDIO_DEVICES_CHANGED_EVENT,
+            PlayerEvent::EqualizerChanged(_) => EQUALIZER_CHANGED_EVENT,
+            PlayerEvent::Buffering(_) => BUFFERING_EVENT,
+            PlayerEvent::PropertyChanged(_) => PROPERTY_CHANGED_EVENT,
+            PlayerEvent::Log(_) => LOG_EVENT,
+        }
+    }
+}
+
+pub type EventSink = Arc<dyn Fn(PlayerEvent) + Send + Sync>;
+
+#[derive(Clone)]
+pub struct PlayerEvents {
+    sink: Arc<Mutex<Option<EventSink>>>,
+    status_changed: Sender<()>,
+    logged: Sender<LogEntry>,
+}
+
+impl PlayerEvents {
+    pub fn new(status: Arc<Mutex<MpvStatus>>) -> Self {
+        let sink: Arc<Mutex<Option<EventSink>>> = Arc::new(Mutex::new(None));
+        let (status_changed, status_rx) = mpsc::channel::<()>();
+
+        let sink_clone = Arc::clone(&sink);
+        thread::spawn(move || {
+            let mut last_emit: Option<Instant> = None;
+            let mut last_status: Option<MpvStatus> = None;
+
+            while status_rx.recv().is_ok() {
+                if let Some(last) = last_emit {
+                    let elapsed = last.elapsed();
+                    if elapsed < STATUS_COALESCE_INTERVAL {
+                        thread::sleep(STATUS_COALESCE_INTERVAL - elapsed);
+                    }
+                }
+                while status_rx.try_recv().is_ok() {}
+
+                let snapshot = status.lock().unwrap().clone();
+                if last_status.as_ref() == Some(&snapshot) {
+                    continue;
+                }
+
+                if let Some(sink) = sink_clone.lock().unwrap().clone() {
+                    sink(PlayerEvent::Status(Box::new(snapshot.clone())));
+                }
+                last_emit = Some(Instant::now());
+                last_status = Some(snapshot);
+            }
+        });
+
+        let (logged, log_rx) = mpsc::channel::<LogEntry>();
+        let sink_clone = Arc::clone(&sink);
+        thread::spawn(move || {
+            let mut last_emit: Option<Instant> = None;
+
+            while let Ok(entry) = log_rx.recv() {
+                if let Some(last) = last_emit {
+                    let elapsed = last.elapsed();
+                    if elapsed < LOG_BATCH_INTERVAL {
+                        thread::sleep(LOG_BATCH_INTERVAL - elapsed);
+                    }
+                }
+                let mut entries = vec![entry];
+                entries.extend(log_rx.try_iter());
+
+                if let Some(sink) = sink_clone.lock().unwrap().clone() {
+                    sink(PlayerEvent::Log(entries));
+                }
+                last_emit = Some(Instant::now());
+            }
+        });
+
+        Self {
+            sink,
+            status_changed,
+            logged,
+        }
+    }
+
+    pub fn set_sink(&self, sink: EventSink) {
+        *self.sink.lock().unwrap() = Some(sink);
+    }
+
+    pub fn emit(&self, event: PlayerEvent) {
+        let sink = self.sink.lock().unwrap().clone();
+        if let Some(sink) = sink {
+            sink(event);
+        }
+    }
+
+    pub fn status_changed(&self) {
+        let _ = self.status_changed.send(());
+    }
+
+    pub fn log(&self, entry: LogEntry) {
+        let _ = self.logged.send(entry);
+    }
+}
@@ -0,0 +1,16 @@
+// Effects each own one labelled entry in mpv's af chain, so they can be swapped without touching the others
+pub fn labelled(label: &str, graph: &str) -> String {
+    format!("@{}:{}", label, graph)
+}
+
+pub fn add(filter: &str) -> Vec<serde_json::Value> {
+    vec!["af".into(), "add".into(), filter.into()]
+}
+
+pub fn remove(label: &str) -> Vec<serde_json::Value> {
+    vec!["af".into(), "remove".into(), format!("@{}", label).into()]
+}
+
+pub fn clear() -> Vec<serde_json::Value> {
+    vec!["af".into(), "clr".into(), "".into()]
+}
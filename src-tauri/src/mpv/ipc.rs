@@ -0,0 +1,357 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(target_os = "windows")]
+use named_pipe::PipeClient;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+
+const CONNECT_RETRY_INTERVAL: Duration = Duration::from_millis(20);
+const READ_POLL_INTERVAL: Duration = Duration::from_millis(10);
+const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Deserialize)]
+struct MpvCommand {
+    command: Vec<serde_json::Value>,
+    request_id: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct MpvResponse {
+    pub request_id: Option<i64>,
+    pub error: Option<String>,
+    pub data: Option<serde_json::Value>,
+    #[serde(rename = "event")]
+    pub event_name: Option<String>,
+    pub id: Option<i64>,
+    pub name: Option<String>,
+    pub reason: Option<String>,
+    pub file_error: Option<String>,
+    pub playlist_entry_id: Option<i64>,
+    // Set on log-message events
+    pub prefix: Option<String>,
+    pub level: Option<String>,
+    pub text: Option<String>,
+}
+
+impl MpvResponse {
+    pub fn into_result(self) -> Result<Option<serde_json::Value>, String> {
+        match self.error.as_deref() {
+            None | Some("success") => Ok(self.data),
+            Some(error) => Err(error.to_string()),
+        }
+    }
+}
+
+pub type EventHandler = Box<dyn FnMut(MpvResponse) + Send>;
+
+struct Outgoing {
+    request_id: i64,
+    line: String,
+    reply: Sender<MpvResponse>,
+}
+
+trait IpcStream: Send {
+    fn read_chunk(&mut self, buf: &mut Vec<u8>) -> std::io::Result<usize>;
+    fn write_line(&mut self, line: &str) -> std::io::Result<()>;
+}
+
+#[cfg(unix)]
+struct UnixIpcStream {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+#[cfg(unix)]
+impl UnixIpcStream {
+    fn connect(path: &str) -> std::io::Result<Self> {
+        let stream = UnixStream::connect(path)?;
+        stream.set_read_timeout(Some(READ_POLL_INTERVAL))?;
+        let writer = stream.try_clone()?;
+        Ok(Self {
+            reader: BufReader::new(stream),
+            writer,
+        })
+    }
+}
+
+#[cfg(unix)]
+impl IpcStream for UnixIpcStream {
+    fn read_chunk(&mut self, buf: &mut Vec<u8>) -> std::io::Result<usize> {
+        self.reader.read_until(b'\n', buf)
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        writeln!(self.writer, "{}", line)
+    }
+}
+
+#[cfg(target_os = "windows")]
+struct PipeIpcStream {
+    client: BufReader<PipeClient>,
+}
+
+#[cfg(target_os = "windows")]
+impl PipeIpcStream {
+    fn connect(path: &str) -> std::io::Result<Self> {
+        let mut client = PipeClient::connect(path)?;
+        client.set_read_timeout(Some(READ_POLL_INTERVAL));
+        Ok(Self {
+            client: BufReader::new(client),
+        })
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl IpcStream for PipeIpcStream {
+    fn read_chunk(&mut self, buf: &mut Vec<u8>) -> std::io::Result<usize> {
+        self.client.read_until(b'\n', buf)
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        writeln!(self.client.get_mut(), "{}", line)
+    }
+}
+
+fn open_stream(path: &str) -> std::io::Result<Box<dyn IpcStream>> {
+    #[cfg(target_os = "windows")]
+    {
+        PipeIpcStream::connect(path).map(|s| Box::new(s) as Box<dyn IpcStream>)
+    }
+
+    #[cfg(unix)]
+    {
+        UnixIpcStream::connect(path).map(|s| Box::new(s) as Box<dyn IpcStream>)
+    }
+}
+
+pub struct MpvIpc {
+    outgoing: Sender<Outgoing>,
+    next_request_id: AtomicI64,
+    timeout: Duration,
+    io_thread: Option<thread::JoinHandle<()>>,
+}
+
+impl MpvIpc {
+    pub fn connect(
+        path: &str,
+        deadline: Instant,
+        mut check_alive: impl FnMut() -> Result<(), String>,
+        on_event: EventHandler,
+    ) -> Result<Self, String> {
+        let stream = loop {
+            let error = match open_stream(path) {
+                Ok(stream) => break stream,
+                Err(e) => e,
+            };
+
+            check_alive()?;
+            if Instant::now() >= deadline {
+                return Err(format!(
+                    "Timed out connecting to MPV IPC at {}: {}",
+                    path, error
+                ));
+            }
+            thread::sleep(CONNECT_RETRY_INTERVAL);
+        };
+
+        let (outgoing, outgoing_rx) = mpsc::channel();
+        let io_thread = thread::spawn(move || {
+            Self::io_loop(stream, outgoing_rx, on_event);
+        });
+
+        Ok(Self {
+            outgoing,
+            next_request_id: AtomicI64::new(1),
+            timeout: DEFAULT_COMMAND_TIMEOUT,
+            io_thread: Some(io_thread),
+        })
+    }
+
+    fn io_loop(
+        mut stream: Box<dyn IpcStream>,
+        outgoing: Receiver<Outgoing>,
+        mut on_event: EventHandler,
+    ) {
+        let mut pending: HashMap<i64, Sender<MpvResponse>> = HashMap::new();
+        let mut buf = Vec::new();
+
+        loop {
+            loop {
+                match outgoing.try_recv() {
+                    Ok(message) => {
+                        if let Err(e) = stream.write_line(&message.line) {
+                            let _ = message.reply.send(MpvResponse {
+                                request_id: Some(message.request_id),
+                                error: Some(format!("Failed to send command to MPV: {}", e)),
+                                ..Default::default()
+                            });
+                            return;
+                        }
+                        pending.insert(message.request_id, message.reply);
+                    }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => return,
+                }
+            }
+
+            match stream.read_chunk(&mut buf) {
+                Ok(0) => return,
+                Ok(_) if buf.ends_with(b"\n") => {
+                    let line = String::from_utf8_lossy(&buf).into_owned();
+                    buf.clear();
+
+                    let response = match serde_json::from_str::<MpvResponse>(line.trim()) {
+                        Ok(response) => response,
+                        Err(_) => continue,
+                    };
+
+                    if response.event_name.is_some() {
+                        on_event(response);
+                    } else if let Some(request_id) = response.request_id {
+                        if let Some(reply) = pending.remove(&request_id) {
+                            let _ = reply.send(response);
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted
+                    ) => {}
+                Err(_) => return,
+            }
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.io_thread
+            .as_ref()
+            .is_none_or(|io_thread| io_thread.is_finished())
+    }
+
+    pub fn request(
+        &self,
+        command: Vec<serde_json::Value>,
+    ) -> Result<Receiver<MpvResponse>, String> {
+        let request_id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+        let line = serde_json::to_string(&MpvCommand {
+            command,
+            request_id,
+        })
+        .map_err(|e| format!("Failed to serialize command: {}", e))?;
+
+        let (reply, response) = mpsc::channel();
+        self.outgoing
+            .send(Outgoing {
+                request_id,
+                line,
+                reply,
+            })
+            .map_err(|_| "MPV IPC connection closed".to_string())?;
+
+        Ok(response)
+    }
+
+    pub fn command(
+        &self,
+        command: Vec<serde_json::Value>,
+    ) -> Result<Option<serde_json::Value>, String> {
+        self.command_with_timeout(command, self.timeout)
+    }
+
+    pub fn command_with_timeout(
+        &self,
+        command: Vec<serde_json::Value>,
+        timeout: Duration,
+    ) -> Result<Option<serde_json::Value>, String> {
+        let name = command
+            .first()
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let response = self.request(command)?;
+
+        match response.recv_timeout(timeout) {
+            Ok(response) => response
+                .into_result()
+                .map_err(|e| format!("MPV command '{}' failed: {}", name, e)),
+            Err(RecvTimeoutError::Timeout) => Err(format!("MPV command '{}' timed out", name)),
+            Err(RecvTimeoutError::Disconnected) => Err("MPV IPC connection closed".to_string()),
+        }
+    }
+
+    // Sends every command before waiting on any reply, so a long batch costs one round trip
+    pub fn command_batch(&self, commands: Vec<Vec<serde_json::Value>>) -> Result<(), String> {
+        let pending = commands
+            .into_iter()
+            .map(|command| self.request(command))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let deadline = Instant::now() + self.timeout;
+        for response in pending {
+            match response.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(response) => {
+                    response
+                        .into_result()
+                        .map_err(|e| format!("MPV command batch failed: {}", e))?;
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err("MPV command batch timed out".to_string())
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err("MPV IPC connection closed".to_string())
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_property<T: serde::de::DeserializeOwned>(
+        &self,
+        property: &str,
+    ) -> Result<T, String> {
+        let data = self
+            .command(vec!["get_property".into(), property.into()])?
+            .unwrap_or(serde_json::Value::Null);
+
+        serde_json::from_value(data)
+            .map_err(|e| format!("Unexpected value for MPV property '{}': {}", property, e))
+    }
+
+    pub fn set_property(
+        &self,
+        property: &str,
+        value: impl Into<serde_json::Value>,
+    ) -> Result<(), String> {
+        self.command(vec!["set_property".into(), property.into(), value.into()])
+            .map(|_| ())
+    }
+
+    pub fn observe_property(&self, id: i64, property: &str) -> Result<(), String> {
+        self.command(vec!["observe_property".into(), id.into(), property.into()])
+            .map(|_| ())
+    }
+
+    pub fn unobserve_property(&self, id: i64) -> Result<(), String> {
+        self.command(vec!["unobserve_property".into(), id.into()])
+            .map(|_| ())
+    }
+}
+
+impl Drop for MpvIpc {
+    fn drop(&mut self) {
+        let (closed, _) = mpsc::channel();
+        self.outgoing = closed;
+
+        if let Some(io_thread) = self.io_thread.take() {
+            let _ = io_thread.join();
+        }
+    }
+}
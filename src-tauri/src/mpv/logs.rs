@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Enough for a few tracks at the info level, older lines are dropped first
+pub const LOG_CAPACITY: usize = 2000;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    // Turns mpv's log messages off, process output is still kept
+    No,
+    Fatal,
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Status,
+    #[serde(rename = "v")]
+    Verbose,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    // Name used by mpv's request_log_messages and log-message events
+    pub fn as_mpv(&self) -> &'static str {
+        match self {
+            LogLevel::No => "no",
+            LogLevel::Fatal => "fatal",
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Status => "status",
+            LogLevel::Verbose => "v",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+
+    pub fn from_mpv(level: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(level.to_string())).ok()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LogEntry {
+    // Milliseconds since the Unix epoch
+    pub timestamp: u64,
+    pub level: LogLevel,
+    // mpv module that logged the line, "stdout"/"stderr" for what the process printed,
+    // or "player" for the backend's own diagnostics
+    pub prefix: String,
+    pub text: String,
+}
+
+impl LogEntry {
+    pub fn new(level: LogLevel, prefix: &str, text: &str) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+        Self {
+            timestamp,
+            level,
+            prefix: prefix.to_string(),
+            // mpv ends every message with a newline
+            text: text.trim_end_matches(['\n', '\r']).to_string(),
+        }
+    }
+
+    pub fn player(level: LogLevel, text: &str) -> Self {
+        Self::new(level, "player", text)
+    }
+}
+
+pub(super) type LogHandler = Arc<dyn Fn(LogEntry) + Send + Sync>;
+
+#[derive(Default)]
+pub struct LogBuffer {
+    entries: VecDeque<LogEntry>,
+}
+
+impl LogBuffer {
+    pub fn push(&mut self, entry: LogEntry) {
+        if self.entries.len() == LOG_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    pub fn entries(&self) -> Vec<LogEntry> {
+        self.entries.iter().cloned().collect()
+    }
+}
@@ -0,0 +1,65 @@
+use super::filters;
+use serde::{Deserialize, Serialize};
+
+const MIN_TARGET: f64 = -70.0;
+const MAX_TARGET: f64 = -5.0;
+pub(super) const FILTER_LABEL: &str = "loudness";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NormalizationMode {
+    #[default]
+    Off,
+    // EBU R128, measures as it plays so the first seconds of a track can pump
+    Loudnorm,
+    Dynaudnorm,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct NormalizationSettings {
+    pub mode: NormalizationMode,
+    pub target_lufs: f64,
+}
+
+impl Default for NormalizationSettings {
+    fn default() -> Self {
+        Self {
+            mode: NormalizationMode::Off,
+            target_lufs: -16.0,
+        }
+    }
+}
+
+impl NormalizationSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(MIN_TARGET..=MAX_TARGET).contains(&self.target_lufs) {
+            return Err(format!(
+                "Target loudness {} LUFS is out of range ({} to {} LUFS)",
+                self.target_lufs, MIN_TARGET, MAX_TARGET
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.mode != NormalizationMode::Off
+    }
+
+    pub fn filter(&self) -> Option<String> {
+        let filter = match self.mode {
+            NormalizationMode::Off => return None,
+            NormalizationMode::Loudnorm => {
+                format!("loudnorm=I={}:TP=-1.5:LRA=11", self.target_lufs)
+            }
+            // dynaudnorm targets a peak rather than a loudness, so aim it at the RMS equivalent
+            NormalizationMode::Dynaudnorm => format!(
+                "dynaudnorm=f=500:g=31:r={:.4}",
+                10f64.powf(self.target_lufs / 20.0)
+            ),
+        };
+        Some(filters::labelled(
+            FILTER_LABEL,
+            &format!("lavfi=[{}]", filter),
+        ))
+    }
+}
@@ -0,0 +1,96 @@
+use super::properties::DENIED_PROPERTIES;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Options the player relies on for IPC, or that it sets itself while running
+pub(super) const DENIED_OPTIONS: [&str; 26] = [
+    "input-ipc-server",
+    "input-ipc-client",
+    "idle",
+    "config",
+    "config-dir",
+    "include",
+    "use-filedir-conf",
+    "terminal",
+    "input-terminal",
+    "player-operation-mode",
+    "o",
+    "playlist",
+    "keep-open",
+    "reset-on-next-file",
+    "loop-file",
+    "loop-playlist",
+    "ab-loop-a",
+    "ab-loop-b",
+    "af",
+    "volume",
+    "volume-max",
+    "mute",
+    "speed",
+    "replaygain",
+    "audio-device",
+    "pause",
+];
+// List options also take these as actions, "scripts-append" adds to "scripts"
+const LIST_SUFFIXES: [&str; 8] = [
+    "-add", "-append", "-pre", "-set", "-remove", "-del", "-clr", "-toggle",
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct MpvOptions {
+    // Option names without the leading dashes, an empty value passes a bare flag
+    #[serde(default)]
+    pub options: BTreeMap<String, String>,
+    // Loads mpv.conf and the rest of the user's mpv config directory
+    #[serde(default)]
+    pub load_config: bool,
+}
+
+impl MpvOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value) in &self.options {
+            let valid_name = !name.is_empty()
+                && !name.starts_with('-')
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+            if !valid_name {
+                return Err(format!("'{}' is not a valid mpv option name", name));
+            }
+
+            let option = name.strip_prefix("no-").unwrap_or(name);
+            let option = LIST_SUFFIXES
+                .iter()
+                .find_map(|suffix| option.strip_suffix(suffix))
+                .unwrap_or(option);
+            if DENIED_OPTIONS.contains(&option) {
+                return Err(format!(
+                    "mpv option '{}' is managed by the player and can't be overridden",
+                    name
+                ));
+            }
+            // Anything that can't be handed to the webview can't come in through settings either
+            if DENIED_PROPERTIES.contains(&option) {
+                return Err(format!("mpv option '{}' is not allowed", name));
+            }
+            if value.contains(['\n', '\r', '\0']) {
+                return Err(format!("Value of mpv option '{}' can't span lines", name));
+            }
+        }
+        Ok(())
+    }
+
+    // Appended after the player's own arguments, so these win where both set an option
+    pub fn args(&self) -> Vec<String> {
+        self.options
+            .iter()
+            .map(|(name, value)| {
+                if value.is_empty() {
+                    format!("--{}", name)
+                } else {
+                    format!("--{}={}", name, value)
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,133 @@
+use serde::Serialize;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub enum PlaylistEdit {
+    Remove(usize),
+    // mpv semantics: the entry ends up in front of whatever is at `before` now
+    Move { from: usize, before: usize },
+    Insert { url: String, index: usize },
+    Select(usize),
+}
+
+pub fn diff(
+    current: &[String],
+    current_pos: Option<usize>,
+    desired: &[String],
+    desired_pos: usize,
+) -> Vec<PlaylistEdit> {
+    let mut edits = Vec::new();
+    let mut matched: Vec<Option<usize>> = vec![None; current.len()];
+    let mut taken = vec![false; desired.len()];
+
+    let current_pos = current_pos.filter(|pos| *pos < current.len());
+    let keep_current = current_pos
+        .filter(|pos| desired.get(desired_pos) == Some(&current[*pos]))
+        .inspect(|pos| {
+            matched[*pos] = Some(desired_pos);
+            taken[desired_pos] = true;
+        })
+        .is_some();
+
+    for (i, url) in current.iter().enumerate() {
+        if matched[i].is_some() {
+            continue;
+        }
+        if let Some(t) = (0..desired.len()).find(|t| !taken[*t] && &desired[*t] == url) {
+            matched[i] = Some(t);
+            taken[t] = true;
+        }
+    }
+
+    // An unmatched playing entry is only removed once another entry is selected
+    let deferred = current_pos.filter(|pos| matched[*pos].is_none());
+
+    let mut slots: Vec<Option<usize>> = Vec::new();
+    for i in (0..current.len()).rev() {
+        if matched[i].is_none() && Some(i) != deferred {
+            edits.push(PlaylistEdit::Remove(i));
+        }
+    }
+    for (i, target) in matched.iter().enumerate() {
+        if target.is_some() || Some(i) == deferred {
+            slots.push(*target);
+        }
+    }
+
+    let mut stable = longest_increasing(&slots);
+
+    for (t, url) in desired.iter().enumerate() {
+        let position = slots.iter().position(|slot| *slot == Some(t));
+        if position.is_some_and(|j| stable[j]) {
+            continue;
+        }
+
+        let before = match t {
+            0 => 0,
+            _ => slots.iter().position(|slot| *slot == Some(t - 1)).unwrap() + 1,
+        };
+
+        match position {
+            Some(j) => {
+                if j != before && j + 1 != before {
+                    edits.push(PlaylistEdit::Move { from: j, before });
+                }
+                let slot = slots.remove(j);
+                let flag = stable.remove(j);
+                let index = if j < before { before - 1 } else { before };
+                slots.insert(index, slot);
+                stable.insert(index, flag);
+            }
+            None => {
+                edits.push(PlaylistEdit::Insert {
+                    url: url.clone(),
+                    index: before,
+                });
+                slots.insert(before, Some(t));
+                stable.insert(before, false);
+            }
+        }
+    }
+
+    if !keep_current {
+        if let Some(index) = slots.iter().position(|slot| *slot == Some(desired_pos)) {
+            edits.push(PlaylistEdit::Select(index));
+        }
+    }
+    if deferred.is_some() {
+        if let Some(index) = slots.iter().position(|slot| slot.is_none()) {
+            edits.push(PlaylistEdit::Remove(index));
+        }
+    }
+
+    edits
+}
+
+fn longest_increasing(slots: &[Option<usize>]) -> Vec<bool> {
+    let targets: Vec<(usize, usize)> = slots
+        .iter()
+        .enumerate()
+        .filter_map(|(i, slot)| slot.map(|t| (i, t)))
+        .collect();
+
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous = vec![None; targets.len()];
+    for k in 0..targets.len() {
+        let at = tails.partition_point(|&tail| targets[tail].1 < targets[k].1);
+        if at > 0 {
+            previous[k] = Some(tails[at - 1]);
+        }
+        if at == tails.len() {
+            tails.push(k);
+        } else {
+            tails[at] = k;
+        }
+    }
+
+    let mut stable = vec![false; slots.len()];
+    let mut next = tails.last().copied();
+    while let Some(k) = next {
+        stable[targets[k].0] = true;
+        next = previous[k];
+    }
+    stable
+}
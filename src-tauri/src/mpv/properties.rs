@@ -0,0 +1,189 @@
+use super::options::DENIED_OPTIONS;
+use serde::{Deserialize, Serialize};
+
+// Never reachable from the webview, they load code or point mpv at other files and sockets
+pub(super) const DENIED_PROPERTIES: [&str; 34] = [
+    "input-ipc-server",
+    "input-ipc-client",
+    "input-conf",
+    "input-commands",
+    "scripts",
+    "script",
+    "load-scripts",
+    "script-opts",
+    "include",
+    "config-dir",
+    "stream-open-filename",
+    "log-file",
+    "ao-pcm-file",
+    "cookies-file",
+    "dump-stats",
+    "record-file",
+    "stream-record",
+    "screenshot-directory",
+    "screenshot-dir",
+    "watch-later-directory",
+    "watch-later-dir",
+    "ytdl-path",
+    "ytdl-raw-options",
+    "load-unsafe-playlists",
+    "audio-file",
+    "audio-files",
+    "external-file",
+    "external-files",
+    "sub-file",
+    "sub-files",
+    "lavfi-complex",
+    // Raw libavformat and libavcodec options, these can open other protocols and files
+    "demuxer-lavf-o",
+    "stream-lavf-o",
+    "ad-lavc-o",
+];
+// Every option is also a property under these prefixes
+const OPTION_PREFIXES: [&str; 2] = ["options/", "file-local-options/"];
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PropertyAllowlist {
+    // Properties the webview can read and observe, "name/*" allows one along with all below it
+    pub readable: Vec<String>,
+    // Properties the webview can also set
+    pub writable: Vec<String>,
+}
+
+impl Default for PropertyAllowlist {
+    fn default() -> Self {
+        let list = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        Self {
+            readable: list(&[
+                "mpv-version",
+                "ffmpeg-version",
+                "metadata/*",
+                "filtered-metadata/*",
+                "current-tracks/*",
+                "track-list",
+                "track-list/*",
+                "chapter-list",
+                "audio-params/*",
+                "audio-out-params/*",
+                "audio-codec",
+                "audio-codec-name",
+                "audio-bitrate",
+                "file-format",
+                "file-size",
+                "time-remaining",
+                "percent-pos",
+                "seekable",
+                "demuxer-cache-state",
+                "current-ao",
+            ]),
+            writable: list(&[
+                "audio-delay",
+                "audio-channels",
+                "audio-samplerate",
+                "audio-exclusive",
+                "audio-normalize-downmix",
+                "audio-buffer",
+                "cache-secs",
+                "demuxer-max-bytes",
+                "demuxer-readahead-secs",
+            ]),
+        }
+    }
+}
+
+impl PropertyAllowlist {
+    pub fn validate(&self) -> Result<(), String> {
+        for pattern in self.readable.iter().chain(&self.writable) {
+            let name = pattern.strip_suffix("/*").unwrap_or(pattern);
+            if !is_valid_name(name) {
+                return Err(format!("'{}' is not a valid mpv property", pattern));
+            }
+            if DENIED_PROPERTIES
+                .iter()
+                .flat_map(|denied| option_forms(denied))
+                .any(|denied| matches(pattern, &denied))
+            {
+                return Err(format!(
+                    "mpv property '{}' can't be exposed to the webview",
+                    pattern
+                ));
+            }
+        }
+
+        for pattern in &self.writable {
+            if pattern.ends_with("/*") {
+                return Err(format!(
+                    "Writable mpv properties have to be listed one by one, not as '{}'",
+                    pattern
+                ));
+            }
+            let name = strip_option_prefix(pattern);
+            if DENIED_OPTIONS.contains(&name) {
+                return Err(format!(
+                    "mpv property '{}' is managed by the player and can't be set directly",
+                    pattern
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn check_readable(&self, name: &str) -> Result<(), String> {
+        let allowed = self
+            .readable
+            .iter()
+            .chain(&self.writable)
+            .any(|pattern| matches(pattern, name));
+        if !is_valid_name(name) || is_denied(name) || !allowed {
+            return Err(format!("mpv property '{}' is not allowed", name));
+        }
+        Ok(())
+    }
+
+    pub fn check_writable(&self, name: &str) -> Result<(), String> {
+        if !is_valid_name(name) || is_denied(name) || !self.writable.iter().any(|w| w == name) {
+            return Err(format!("mpv property '{}' can't be set", name));
+        }
+        Ok(())
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.split('/').all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+}
+
+fn is_denied(name: &str) -> bool {
+    DENIED_PROPERTIES.contains(&strip_option_prefix(name))
+}
+
+fn strip_option_prefix(name: &str) -> &str {
+    OPTION_PREFIXES
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .unwrap_or(name)
+}
+
+fn option_forms(name: &str) -> Vec<String> {
+    std::iter::once(name.to_string())
+        .chain(
+            OPTION_PREFIXES
+                .iter()
+                .map(|prefix| format!("{}{}", prefix, name)),
+        )
+        .collect()
+}
+
+fn matches(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some(parent) => name
+            .strip_prefix(parent)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/')),
+        None => pattern == name,
+    }
+}
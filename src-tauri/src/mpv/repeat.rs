@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RepeatMode {
+    #[default]
+    None,
+    One,
+    All,
+}
+
+impl RepeatMode {
+    pub fn properties(&self) -> Vec<(&'static str, serde_json::Value)> {
+        let looped = |on: bool| if on { "inf" } else { "no" };
+        vec![
+            ("loop-file", looped(*self == RepeatMode::One).into()),
+            ("loop-playlist", looped(*self == RepeatMode::All).into()),
+        ]
+    }
+}
+
+// mpv reports loop counts as "inf", a number of repeats, or false/"no" when off
+pub fn is_looping(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::String(value) => value != "no",
+        serde_json::Value::Number(count) => count.as_i64().unwrap_or_default() > 0,
+        serde_json::Value::Bool(looping) => *looping,
+        _ => false,
+    }
+}
+
+pub fn validate_ab_loop(a: Option<f64>, b: Option<f64>) -> Result<(), String> {
+    if [a, b].iter().flatten().any(|point| *point < 0.0) {
+        return Err("Loop points can't be negative".to_string());
+    }
+    match (a, b) {
+        (Some(a), Some(b)) if b <= a => Err(format!(
+            "Loop end {:.2} s must come after its start {:.2} s",
+            b, a
+        )),
+        _ => Ok(()),
+    }
+}
+
+pub fn ab_loop_point(point: Option<f64>) -> serde_json::Value {
+    point.map_or_else(|| "no".into(), Into::into)
+}
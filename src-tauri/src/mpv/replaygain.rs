@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplayGainMode {
+    #[default]
+    Off,
+    Track,
+    Album,
+    Auto,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReplayGainSettings {
+    pub mode: ReplayGainMode,
+    pub preamp: f64,
+    pub clip_prevention: bool,
+    pub fallback_gain: f64,
+    // Whether the queue around the current track comes from one album, used by auto mode
+    #[serde(default)]
+    pub album_context: bool,
+}
+
+impl Default for ReplayGainSettings {
+    fn default() -> Self {
+        Self {
+            mode: ReplayGainMode::Off,
+            preamp: 0.0,
+            clip_prevention: true,
+            fallback_gain: 0.0,
+            album_context: false,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ReplayGainTags {
+    #[serde(rename = "replaygain-track-gain")]
+    pub track_gain: Option<f64>,
+    #[serde(rename = "replaygain-track-peak")]
+    pub track_peak: Option<f64>,
+    #[serde(rename = "replaygain-album-gain")]
+    pub album_gain: Option<f64>,
+    #[serde(rename = "replaygain-album-peak")]
+    pub album_peak: Option<f64>,
+}
+
+impl ReplayGainSettings {
+    pub fn resolved_mode(&self) -> ReplayGainMode {
+        match self.mode {
+            ReplayGainMode::Auto if self.album_context => ReplayGainMode::Album,
+            ReplayGainMode::Auto => ReplayGainMode::Track,
+            mode => mode,
+        }
+    }
+
+    pub fn properties(&self) -> Vec<(&'static str, serde_json::Value)> {
+        let mode = match self.resolved_mode() {
+            ReplayGainMode::Track => "track",
+            ReplayGainMode::Album => "album",
+            _ => "no",
+        };
+
+        vec![
+            ("replaygain", mode.into()),
+            ("replaygain-preamp", self.preamp.into()),
+            ("replaygain-clip", self.clip_prevention.into()),
+            ("replaygain-fallback", self.fallback_gain.into()),
+        ]
+    }
+
+    // Mirrors mpv's own gain selection so the player can show what is applied
+    pub fn effective_gain(&self, tags: Option<&ReplayGainTags>) -> Option<f64> {
+        let track = tags.and_then(|t| t.track_gain.map(|gain| (gain, t.track_peak)));
+        let album = tags.and_then(|t| t.album_gain.map(|gain| (gain, t.album_peak)));
+
+        let selected = match self.resolved_mode() {
+            ReplayGainMode::Off | ReplayGainMode::Auto => return None,
+            ReplayGainMode::Track => track.or(album),
+            ReplayGainMode::Album => album.or(track),
+        };
+
+        let (gain, peak) = match selected {
+            Some((gain, peak)) => (gain + self.preamp, peak),
+            None => (self.fallback_gain, None),
+        };
+
+        match peak {
+            Some(peak) if self.clip_prevention && peak > 0.0 => {
+                Some(gain.min(-20.0 * peak.log10()))
+            }
+            _ => Some(gain),
+        }
+    }
+}
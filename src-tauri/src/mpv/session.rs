@@ -0,0 +1,213 @@
+use super::ipc::{EventHandler, MpvIpc};
+use super::logs::{LogEntry, LogHandler, LogLevel};
+use super::OBSERVED_PROPERTIES;
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+pub const DEFAULT_STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+const STDERR_LINES: usize = 20;
+
+#[derive(Clone, Debug)]
+pub struct LaunchConfig {
+    pub executable: String,
+    pub args: Vec<String>,
+    pub socket_path: String,
+    pub startup_timeout: Duration,
+}
+
+impl LaunchConfig {
+    pub fn with_socket_path(&self, socket_path: String) -> Self {
+        let args = self
+            .args
+            .iter()
+            .map(|arg| match arg.strip_prefix("--input-ipc-server=") {
+                Some(_) => format!("--input-ipc-server={}", socket_path),
+                None => arg.clone(),
+            })
+            .collect();
+
+        Self {
+            args,
+            socket_path,
+            ..self.clone()
+        }
+    }
+}
+
+pub struct MpvSession {
+    pub process: Child,
+    pub ipc: Arc<MpvIpc>,
+    pub launch: LaunchConfig,
+    // Whether this session's events drive the player, only one session is live at a time
+    pub live: Arc<AtomicBool>,
+}
+
+impl MpvSession {
+    pub fn spawn(
+        launch: LaunchConfig,
+        live: Arc<AtomicBool>,
+        on_event: EventHandler,
+        on_log: LogHandler,
+        log_level: LogLevel,
+    ) -> Result<Self, String> {
+        let mut command = Command::new(&launch.executable);
+        command.args(&launch.args);
+
+        #[cfg(target_os = "windows")]
+        {
+            command.creation_flags(CREATE_NO_WINDOW);
+        }
+
+        let mut process = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to start MPV: {}", e))?;
+
+        // Both pipes are drained for the whole run, a full pipe would block mpv
+        if let Some(pipe) = process.stdout.take() {
+            Self::capture_output(pipe, "stdout", LogLevel::Info, None, Arc::clone(&on_log));
+        }
+        let stderr = Arc::new(Mutex::new(VecDeque::new()));
+        let stderr_thread = process.stderr.take().map(|pipe| {
+            Self::capture_output(
+                pipe,
+                "stderr",
+                LogLevel::Warn,
+                Some(Arc::clone(&stderr)),
+                Arc::clone(&on_log),
+            )
+        });
+
+        let ipc = match Self::handshake(&mut process, &launch, on_event) {
+            Ok(ipc) => ipc,
+            Err(e) => {
+                let _ = process.kill();
+                let _ = process.wait();
+                if let Some(thread) = stderr_thread {
+                    let _ = thread.join();
+                }
+
+                let stderr = stderr.lock().unwrap();
+                if stderr.is_empty() {
+                    return Err(e);
+                }
+                let output = stderr.iter().cloned().collect::<Vec<_>>().join("\n");
+                return Err(format!("{}\nMPV stderr:\n{}", e, output));
+            }
+        };
+
+        let _ = ipc.command(vec!["enable_event".into(), "all".into()]);
+        for (id, prop) in OBSERVED_PROPERTIES.iter().enumerate() {
+            if let Err(e) = ipc.observe_property(id as i64 + 1, prop) {
+                on_log(LogEntry::player(
+                    LogLevel::Error,
+                    &format!("Failed to observe MPV property {}: {}", prop, e),
+                ));
+            }
+        }
+        if let Err(e) = Self::request_log_messages(&ipc, log_level) {
+            on_log(LogEntry::player(
+                LogLevel::Error,
+                &format!("Failed to request MPV log messages: {}", e),
+            ));
+        }
+
+        Ok(Self {
+            process,
+            ipc: Arc::new(ipc),
+            launch,
+            live,
+        })
+    }
+
+    pub fn request_log_messages(ipc: &MpvIpc, level: LogLevel) -> Result<(), String> {
+        ipc.command(vec!["request_log_messages".into(), level.as_mpv().into()])
+            .map(|_| ())
+    }
+
+    fn handshake(
+        process: &mut Child,
+        launch: &LaunchConfig,
+        on_event: EventHandler,
+    ) -> Result<MpvIpc, String> {
+        let deadline = Instant::now() + launch.startup_timeout;
+
+        let check_alive = || match process.try_wait() {
+            Ok(Some(status)) => Err(format!("MPV exited during startup ({})", status)),
+            Ok(None) => Ok(()),
+            Err(e) => Err(format!("Failed to check MPV process: {}", e)),
+        };
+        let ipc = MpvIpc::connect(&launch.socket_path, deadline, check_alive, on_event)?;
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        ipc.command_with_timeout(vec!["get_property".into(), "mpv-version".into()], remaining)
+            .map_err(|e| format!("MPV did not answer the startup handshake: {}", e))?;
+
+        Ok(ipc)
+    }
+
+    // The last lines are also kept in `tail`, for reporting a failed startup
+    fn capture_output(
+        pipe: impl Read + Send + 'static,
+        prefix: &'static str,
+        level: LogLevel,
+        tail: Option<Arc<Mutex<VecDeque<String>>>>,
+        on_log: LogHandler,
+    ) -> thread::JoinHandle<()> {
+        thread::spawn(move || {
+            for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+                on_log(LogEntry::new(level, prefix, &line));
+                if let Some(tail) = &tail {
+                    let mut lines = tail.lock().unwrap();
+                    if lines.len() == STDERR_LINES {
+                        lines.pop_front();
+                    }
+                    lines.push_back(line);
+                }
+            }
+        })
+    }
+
+    pub fn exit_code(&mut self) -> Option<Option<i32>> {
+        match self.process.try_wait() {
+            Ok(Some(status)) => Some(status.code()),
+            Ok(None) if self.ipc.is_closed() => Some(None),
+            Ok(None) => None,
+            Err(_) => Some(None),
+        }
+    }
+
+    pub fn terminate(self) -> LaunchConfig {
+        if !self.ipc.is_closed() {
+            let _ = self.ipc.command(vec!["quit".into()]);
+            thread::sleep(Duration::from_millis(100));
+        }
+
+        let launch = self.kill();
+
+        #[cfg(unix)]
+        {
+            let _ = std::fs::remove_file(&launch.socket_path);
+        }
+
+        launch
+    }
+
+    pub fn kill(mut self) -> LaunchConfig {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+        self.launch
+    }
+}
@@ -0,0 +1,136 @@
+use super::MpvStatus;
+use serde::{Deserialize, Serialize};
+
+const MAX_MINUTES: f64 = 24.0 * 60.0;
+const MAX_FADE_SECS: f64 = 120.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum SleepTimerMode {
+    Minutes { minutes: f64 },
+    EndOfTrack,
+    Tracks { count: u32 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SleepTimerSettings {
+    #[serde(flatten)]
+    pub mode: SleepTimerMode,
+    // Playback fades out over this many seconds before it stops, 0 stops without a fade
+    #[serde(default)]
+    pub fade_secs: f64,
+}
+
+impl SleepTimerSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        match self.mode {
+            SleepTimerMode::Minutes { minutes } if !(minutes > 0.0 && minutes <= MAX_MINUTES) => {
+                return Err(format!(
+                    "Sleep timer of {} minutes is out of range (up to {} minutes)",
+                    minutes, MAX_MINUTES
+                ));
+            }
+            SleepTimerMode::Tracks { count: 0 } => {
+                return Err("Sleep timer needs at least one track".to_string());
+            }
+            _ => {}
+        }
+        if !(0.0..=MAX_FADE_SECS).contains(&self.fade_secs) {
+            return Err(format!(
+                "Fade-out of {} s is out of range (0 to {} s)",
+                self.fade_secs, MAX_FADE_SECS
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SleepTimerStatus {
+    pub mode: SleepTimerMode,
+    // Whole seconds until playback stops, once that's known
+    pub remaining_secs: Option<f64>,
+    pub remaining_tracks: Option<u32>,
+    pub fading: bool,
+}
+
+pub struct SleepTimer {
+    settings: SleepTimerSettings,
+    countdown: Option<f64>,
+    tracks_left: Option<u32>,
+}
+
+impl SleepTimer {
+    pub fn new(settings: SleepTimerSettings) -> Self {
+        let (countdown, tracks_left) = match settings.mode {
+            SleepTimerMode::Minutes { minutes } => (Some(minutes * 60.0), None),
+            SleepTimerMode::EndOfTrack => (None, Some(1)),
+            SleepTimerMode::Tracks { count } => (None, Some(count)),
+        };
+        Self {
+            settings,
+            countdown,
+            tracks_left,
+        }
+    }
+
+    pub fn countdown(&self) -> Option<f64> {
+        self.countdown
+    }
+
+    pub fn elapse(&mut self, seconds: f64) {
+        if let Some(countdown) = &mut self.countdown {
+            *countdown -= seconds;
+        }
+    }
+
+    pub fn track_finished(&mut self) {
+        if let Some(tracks) = &mut self.tracks_left {
+            *tracks = tracks.saturating_sub(1);
+        }
+    }
+
+    pub fn counts_tracks(&self) -> bool {
+        self.tracks_left.is_some()
+    }
+
+    // Playback has to stop at the end of the current track rather than move on
+    pub fn is_last_track(&self) -> bool {
+        self.tracks_left == Some(1)
+    }
+
+    pub fn remaining(&self, status: &MpvStatus) -> Option<f64> {
+        match self.tracks_left {
+            Some(0) => Some(0.0),
+            Some(1) if status.duration > 0.0 => {
+                let left = (status.duration - status.position) / status.speed.max(0.01);
+                Some(left.max(0.0))
+            }
+            Some(_) => None,
+            None => self.countdown.map(|countdown| countdown.max(0.0)),
+        }
+    }
+
+    pub fn expired(&self, status: &MpvStatus) -> bool {
+        self.remaining(status)
+            .is_some_and(|remaining| remaining <= 0.0)
+    }
+
+    // Volume factor for the fade-out, 1 until it starts
+    pub fn fade_gain(&self, status: &MpvStatus) -> f64 {
+        let fade = self.settings.fade_secs;
+        match self.remaining(status) {
+            Some(remaining) if fade > 0.0 && remaining < fade => remaining / fade,
+            _ => 1.0,
+        }
+    }
+
+    pub fn status(&self, status: &MpvStatus) -> SleepTimerStatus {
+        SleepTimerStatus {
+            mode: self.settings.mode,
+            remaining_secs: self.remaining(status).map(f64::ceil),
+            remaining_tracks: self.tracks_left,
+            fading: self.fade_gain(status) < 1.0,
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use super::crossfade::faded_volume;
+use super::ipc::MpvIpc;
+use super::logs::LogLevel;
+use super::sleep_timer::SleepTimer;
+use super::{MpvStatus, SharedState};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const TICK_INTERVAL: Duration = Duration::from_millis(200);
+
+// Counts the sleep timer down and pauses playback once it runs out
+pub struct SleepWatcher {
+    stop: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl SleepWatcher {
+    pub fn start(shared: SharedState) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_clone = Arc::clone(&stop);
+
+        let thread = thread::spawn(move || {
+            Self::run(shared, stop_clone);
+        });
+
+        Self {
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    pub fn stop(mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+
+    fn run(shared: SharedState, stop: Arc<AtomicBool>) {
+        let mut last_tick = Instant::now();
+        let mut last_pos = shared.status.lock().unwrap().playlist_pos;
+        let mut holding = false;
+
+        while !stop.load(Ordering::SeqCst) {
+            thread::sleep(TICK_INTERVAL);
+            let elapsed = last_tick.elapsed().as_secs_f64();
+            last_tick = Instant::now();
+
+            let status = shared.status.lock().unwrap().clone();
+            // Starting from an empty playlist doesn't finish a track
+            let track_changed = last_pos >= 0 && status.playlist_pos != last_pos;
+            last_pos = status.playlist_pos;
+
+            // Held through the tick, so a cancel can't slip in halfway through
+            let mut timer = shared.sleep_timer.lock().unwrap();
+            let Some(active) = timer.as_mut() else {
+                holding = false;
+                continue;
+            };
+            active.elapse(elapsed);
+            if track_changed {
+                active.track_finished();
+            }
+
+            let result = if active.expired(&status) {
+                holding = false;
+                let expired = timer.take();
+                expired.map_or(Ok(()), |expired| Self::finish(&shared, &expired, &status))
+            } else {
+                Self::update(&shared, active, &status, &mut holding)
+            };
+            if let Err(e) = result {
+                shared.log(
+                    LogLevel::Error,
+                    &format!("Sleep timer failed to control playback: {}", e),
+                );
+            }
+        }
+    }
+
+    fn update(
+        shared: &SharedState,
+        timer: &SleepTimer,
+        status: &MpvStatus,
+        holding: &mut bool,
+    ) -> Result<(), String> {
+        let ipc = shared.ipc()?;
+        if timer.is_last_track() && !*holding {
+            // Pauses at the end of the track instead of moving on to the next one
+            ipc.set_property("keep-open", "always")?;
+            *holding = true;
+        }
+
+        let gain = timer.fade_gain(status);
+        if gain < 1.0 {
+            shared.sleep_fading.store(true, Ordering::SeqCst);
+            // Same perceptual curve as a crossfade, a linear fade sounds like it drops off at the end
+            ipc.set_property(
+                "volume",
+                faded_volume(shared.mpv_volume(status.volume), gain),
+            )?;
+        }
+
+        let sleep_timer = Some(timer.status(status));
+        let changed = {
+            let mut current = shared.status.lock().unwrap();
+            let changed = current.sleep_timer != sleep_timer;
+            current.sleep_timer = sleep_timer;
+            changed
+        };
+        if changed {
+            shared.events.status_changed();
+        }
+        Ok(())
+    }
+
+    fn finish(shared: &SharedState, timer: &SleepTimer, status: &MpvStatus) -> Result<(), String> {
+        let ipc = shared.ipc()?;
+        ipc.set_property("pause", true)?;
+        Self::release(shared, &ipc, timer, status.volume);
+
+        {
+            let mut current = shared.status.lock().unwrap();
+            current.pause = Some(true);
+            current.state = "paused".to_string();
+            current.sleep_timer = None;
+        }
+        shared.events.status_changed();
+        Ok(())
+    }
+
+    // Undoes what the timer changed on the live instance, used on expiry and on cancel
+    pub fn release(shared: &SharedState, ipc: &MpvIpc, timer: &SleepTimer, volume: f64) {
+        if shared.sleep_fading.swap(false, Ordering::SeqCst) {
+            let _ = ipc.set_property("volume", shared.mpv_volume(volume));
+        }
+        if timer.counts_tracks() {
+            let _ = ipc.set_property("keep-open", "yes");
+        }
+    }
+}
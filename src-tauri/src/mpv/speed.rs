@@ -0,0 +1,77 @@
+use super::filters;
+use serde::{Deserialize, Serialize};
+
+pub const MIN_SPEED: f64 = 0.25;
+pub const MAX_SPEED: f64 = 4.0;
+const MAX_PITCH: f64 = 12.0;
+pub(super) const FILTER_LABEL: &str = "pitch";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PitchCorrection {
+    Off,
+    // mpv inserts scaletempo2 on its own whenever the speed isn't 1
+    #[default]
+    Scaletempo2,
+    Rubberband,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SpeedSettings {
+    pub speed: f64,
+    pub pitch_correction: PitchCorrection,
+    // Semitones, independent of the speed
+    pub pitch: f64,
+}
+
+impl Default for SpeedSettings {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            pitch_correction: PitchCorrection::default(),
+            pitch: 0.0,
+        }
+    }
+}
+
+impl SpeedSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(MIN_SPEED..=MAX_SPEED).contains(&self.speed) {
+            return Err(format!(
+                "Speed {} is out of range ({}x to {}x)",
+                self.speed, MIN_SPEED, MAX_SPEED
+            ));
+        }
+        if !(-MAX_PITCH..=MAX_PITCH).contains(&self.pitch) {
+            return Err(format!(
+                "Pitch shift of {} semitones is out of range",
+                self.pitch
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn properties(&self) -> Vec<(&'static str, serde_json::Value)> {
+        vec![
+            (
+                "audio-pitch-correction",
+                (self.pitch_correction != PitchCorrection::Off).into(),
+            ),
+            ("speed", self.speed.into()),
+        ]
+    }
+
+    // Rubberband takes over time stretching from scaletempo2 and is the only way to shift pitch.
+    // With correction off the pitch follows the speed, a pitch shift waits until it's back on
+    pub fn filter(&self) -> Option<String> {
+        match self.pitch_correction {
+            PitchCorrection::Off => return None,
+            PitchCorrection::Scaletempo2 if self.pitch == 0.0 => return None,
+            _ => {}
+        }
+        Some(filters::labelled(
+            FILTER_LABEL,
+            &format!("rubberband=pitch-scale={}", 2f64.powf(self.pitch / 12.0)),
+        ))
+    }
+}
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+
+const LOSSLESS_CODECS: [&str; 8] = [
+    "flac",
+    "alac",
+    "ape",
+    "wavpack",
+    "tta",
+    "truehd",
+    "mlp",
+    "wmalossless",
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct StreamInfo {
+    pub codec: Option<String>,
+    pub container: Option<String>,
+    // Bits per second, follows the current frame for VBR streams
+    pub bitrate: Option<u32>,
+    pub sample_rate: Option<u32>,
+    pub bit_depth: Option<u32>,
+    // The decoder's sample format, e.g. "s32" or "floatp", for when it doesn't pin down the depth
+    pub sample_format: Option<String>,
+    pub channels: Option<String>,
+    pub encoder: Option<String>,
+    pub transcoded: bool,
+}
+
+impl StreamInfo {
+    pub fn apply_audio_params(&mut self, params: &serde_json::Value) {
+        self.sample_rate = params
+            .get("samplerate")
+            .and_then(|rate| rate.as_u64())
+            .map(|rate| rate as u32);
+        self.channels = params
+            .get("hr-channels")
+            .or_else(|| params.get("channels"))
+            .and_then(|channels| channels.as_str())
+            .map(String::from);
+        self.sample_format = params
+            .get("format")
+            .and_then(|format| format.as_str())
+            .map(String::from);
+        self.update_bit_depth();
+    }
+
+    pub fn set_codec(&mut self, codec: Option<String>) {
+        self.codec = codec;
+        self.update_bit_depth();
+    }
+
+    pub fn apply_metadata(&mut self, metadata: &serde_json::Value) {
+        self.encoder = metadata.as_object().and_then(|tags| {
+            tags.iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case("encoder"))
+                .and_then(|(_, value)| value.as_str())
+                .map(String::from)
+        });
+    }
+
+    pub fn is_lossless(&self) -> bool {
+        self.codec
+            .as_deref()
+            .is_some_and(|codec| LOSSLESS_CODECS.contains(&codec) || codec.starts_with("pcm_"))
+    }
+
+    // Only the stream URL says for sure, plenty of untouched files were tagged by ffmpeg too
+    pub fn update_transcoded(&mut self, url: Option<&str>) {
+        self.transcoded = url.is_some_and(requests_transcode);
+    }
+
+    // Depth of the source samples, lossy codecs decode to float and have none of their own
+    fn update_bit_depth(&mut self) {
+        // Uncompressed PCM names its sample size, e.g. pcm_s24le
+        if let Some(pcm) = self.codec.as_deref().and_then(|c| c.strip_prefix("pcm_")) {
+            let digits: String = pcm
+                .chars()
+                .skip(1)
+                .take_while(char::is_ascii_digit)
+                .collect();
+            self.bit_depth = digits.parse().ok();
+            return;
+        }
+        let lossless = self.is_lossless();
+        self.bit_depth = match self
+            .sample_format
+            .as_deref()
+            .map(|f| f.trim_end_matches('p'))
+        {
+            Some("u8") => Some(8),
+            Some("s16") => Some(16),
+            // Lossless decoders widen 24-bit samples to s32, and real 32-bit sources are rare
+            Some("s32") if lossless => Some(24),
+            _ => None,
+        };
+    }
+}
+
+// Subsonic stream URLs ask for a transcode with a target format or a bitrate cap
+pub fn requests_transcode(url: &str) -> bool {
+    let Some((_, query)) = url.split_once('?') else {
+        return false;
+    };
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .any(|(key, value)| match key {
+            "format" => value != "raw",
+            "maxBitRate" => value.parse::<u32>().is_ok_and(|rate| rate > 0),
+            _ => false,
+        })
+}
@@ -0,0 +1,306 @@
+use super::events::{BackendCrash, PlaybackError, PlayerEvent};
+use super::ipc::MpvIpc;
+use super::logs::LogLevel;
+use super::repeat;
+use super::{MpvPlayer, MpvStatus, SharedState};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+const MAX_RESTARTS: usize = 3;
+const RESTART_WINDOW: Duration = Duration::from_secs(60);
+const RESTART_BACKOFF: Duration = Duration::from_millis(500);
+const LOAD_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub struct Supervisor {
+    stop: Arc<AtomicBool>,
+    // Set while a crashed mpv is being started again
+    restarting: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Supervisor {
+    pub fn start(shared: SharedState) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let restarting = Arc::new(AtomicBool::new(false));
+        let stop_clone = Arc::clone(&stop);
+        let restarting_clone = Arc::clone(&restarting);
+
+        let thread = thread::spawn(move || {
+            Self::run(shared, stop_clone, restarting_clone);
+        });
+
+        Self {
+            stop,
+            restarting,
+            thread: Some(thread),
+        }
+    }
+
+    pub fn stop(mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // A restart can wait out a whole mpv handshake, it sees the flag and cleans up by itself
+        if self.restarting.load(Ordering::SeqCst) {
+            return;
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+
+    fn run(shared: SharedState, stop: Arc<AtomicBool>, restarting: Arc<AtomicBool>) {
+        let session = Arc::clone(&shared.session);
+        let mut crashes: VecDeque<Instant> = VecDeque::new();
+
+        while !stop.load(Ordering::SeqCst) {
+            thread::sleep(POLL_INTERVAL);
+
+            let (launch, exit_code) = {
+                let mut guard = session.lock().unwrap();
+                let exit_code = match guard.as_mut().and_then(|s| s.exit_code()) {
+                    Some(exit_code) => exit_code,
+                    None => continue,
+                };
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                (guard.take().unwrap().kill(), exit_code)
+            };
+
+            restarting.store(true, Ordering::SeqCst);
+            if stop.load(Ordering::SeqCst) {
+                return;
+            }
+            shared.log(
+                LogLevel::Error,
+                &format!("MPV exited unexpectedly (exit code {:?})", exit_code),
+            );
+
+            let snapshot = shared.status.lock().unwrap().clone();
+            let playlist = shared.playlist.lock().unwrap().clone();
+            let audio_device = shared.audio_device.lock().unwrap().clone();
+            {
+                let mut status = shared.status.lock().unwrap();
+                status.initialized = false;
+                status.state = "error".to_string();
+            }
+            shared.events.status_changed();
+
+            let mut exit_code = exit_code;
+            loop {
+                while crashes
+                    .front()
+                    .is_some_and(|crash| crash.elapsed() > RESTART_WINDOW)
+                {
+                    crashes.pop_front();
+                }
+                crashes.push_back(Instant::now());
+
+                let restart_attempt = crashes.len();
+                let will_restart = restart_attempt <= MAX_RESTARTS;
+                shared
+                    .events
+                    .emit(PlayerEvent::BackendCrashed(BackendCrash {
+                        exit_code,
+                        restart_attempt,
+                        will_restart,
+                    }));
+
+                if !will_restart {
+                    shared.events.emit(PlayerEvent::Error(PlaybackError {
+                        message: format!(
+                            "MPV crashed {} times within {} seconds, not restarting",
+                            restart_attempt,
+                            RESTART_WINDOW.as_secs()
+                        ),
+                    }));
+                    return;
+                }
+
+                let resume_at =
+                    Instant::now() + RESTART_BACKOFF * 2u32.pow(restart_attempt as u32 - 1);
+                loop {
+                    if stop.load(Ordering::SeqCst) {
+                        return;
+                    }
+                    let remaining = resume_at.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        break;
+                    }
+                    thread::sleep(remaining.min(POLL_INTERVAL));
+                }
+
+                match MpvPlayer::spawn_session(&shared, launch.clone(), true) {
+                    Ok(new_session) if stop.load(Ordering::SeqCst) => {
+                        new_session.terminate();
+                        return;
+                    }
+                    Ok(new_session) => {
+                        if let Err(e) = shared.apply_volume(&new_session.ipc) {
+                            shared.log(
+                                LogLevel::Error,
+                                &format!("Failed to restore volume settings: {}", e),
+                            );
+                        }
+                        let (loaded_tx, loaded_rx) = mpsc::channel();
+                        *shared.file_loaded.lock().unwrap() = Some(loaded_tx);
+                        let restored = Self::restore(
+                            &new_session.ipc,
+                            &loaded_rx,
+                            &snapshot,
+                            &playlist,
+                            audio_device.as_deref(),
+                        );
+                        *shared.file_loaded.lock().unwrap() = None;
+                        if let Err(e) = restored {
+                            shared.log(
+                                LogLevel::Error,
+                                &format!("Failed to restore MPV state after restart: {}", e),
+                            );
+                        }
+                        if let Err(e) = shared.apply_replaygain(&new_session.ipc) {
+                            shared.log(
+                                LogLevel::Error,
+                                &format!("Failed to restore ReplayGain settings: {}", e),
+                            );
+                        }
+                        shared.equalizer.lock().unwrap().set_installed(None);
+                        if let Err(e) = shared.apply_equalizer(&new_session.ipc) {
+                            shared.log(
+                                LogLevel::Error,
+                                &format!("Failed to restore equalizer settings: {}", e),
+                            );
+                        }
+                        *shared.pitch_filter.lock().unwrap() = None;
+                        if let Err(e) = shared.apply_speed(&new_session.ipc) {
+                            shared.log(
+                                LogLevel::Error,
+                                &format!("Failed to restore playback speed: {}", e),
+                            );
+                        }
+                        *shared.loudness_filter.lock().unwrap() = None;
+                        if let Err(e) = shared.apply_normalization(&new_session.ipc) {
+                            shared.log(
+                                LogLevel::Error,
+                                &format!("Failed to restore loudness normalization: {}", e),
+                            );
+                        }
+                        if let Err(e) = shared.apply_observed(&new_session.ipc) {
+                            shared.log(
+                                LogLevel::Error,
+                                &format!("Failed to restore observed properties: {}", e),
+                            );
+                        }
+
+                        let mut guard = session.lock().unwrap();
+                        if stop.load(Ordering::SeqCst) {
+                            drop(guard);
+                            new_session.terminate();
+                            return;
+                        }
+                        *guard = Some(new_session);
+                        break;
+                    }
+                    Err(e) => {
+                        shared.log(LogLevel::Error, &format!("Failed to restart MPV: {}", e));
+                        exit_code = None;
+                    }
+                }
+            }
+
+            restarting.store(false, Ordering::SeqCst);
+
+            {
+                let mut status = shared.status.lock().unwrap();
+                status.initialized = true;
+                // Clears the error state the crash left, mpv reports the rest as it catches up
+                status.state = match (playlist.is_empty(), snapshot.pause) {
+                    (true, _) => "idle",
+                    (false, Some(true)) => "paused",
+                    (false, _) => "playing",
+                }
+                .to_string();
+                // Where restore left the reloaded playlist
+                status.playlist_pos = if playlist.is_empty() {
+                    -1
+                } else if (snapshot.playlist_pos as usize) < playlist.len() {
+                    snapshot.playlist_pos.max(0)
+                } else {
+                    0
+                };
+            }
+            shared.events.status_changed();
+            shared.playlist_changed();
+        }
+    }
+
+    fn restore(
+        ipc: &MpvIpc,
+        loaded: &Receiver<()>,
+        snapshot: &MpvStatus,
+        playlist: &[String],
+        audio_device: Option<&str>,
+    ) -> Result<(), String> {
+        if let Some(device) = audio_device {
+            ipc.set_property("audio-device", device)?;
+        }
+        ipc.set_property("pause", snapshot.pause.unwrap_or(false))?;
+        for (property, value) in snapshot.repeat.properties() {
+            ipc.set_property(property, value)?;
+        }
+
+        if playlist.is_empty() {
+            return Ok(());
+        }
+
+        for (i, url) in playlist.iter().enumerate() {
+            let mode = if i == 0 { "replace" } else { "append" };
+            ipc.command(vec!["loadfile".into(), url.as_str().into(), mode.into()])?;
+        }
+
+        let pos = if snapshot.playlist_pos > 0 && (snapshot.playlist_pos as usize) < playlist.len()
+        {
+            ipc.set_property("playlist-pos", snapshot.playlist_pos)?;
+            snapshot.playlist_pos
+        } else {
+            0
+        };
+
+        let ab_loop = snapshot.ab_loop_a.is_some() || snapshot.ab_loop_b.is_some();
+        if snapshot.position <= 0.0 && !ab_loop {
+            return Ok(());
+        }
+        Self::wait_for_file(ipc, loaded, pos)?;
+
+        if snapshot.position > 0.0 {
+            ipc.command(vec![
+                "seek".into(),
+                snapshot.position.into(),
+                "absolute+exact".into(),
+            ])?;
+        }
+        // Set once the file is in, mpv resets the loop points on every new file
+        ipc.set_property("ab-loop-a", repeat::ab_loop_point(snapshot.ab_loop_a))?;
+        ipc.set_property("ab-loop-b", repeat::ab_loop_point(snapshot.ab_loop_b))?;
+
+        Ok(())
+    }
+
+    // The first entry can finish loading before the jump to the snapshot's one takes over
+    fn wait_for_file(ipc: &MpvIpc, loaded: &Receiver<()>, pos: i32) -> Result<(), String> {
+        let deadline = Instant::now() + LOAD_TIMEOUT;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            loaded
+                .recv_timeout(remaining)
+                .map_err(|_| "Timed out waiting for the restored track to load".to_string())?;
+            if ipc.get_property::<i32>("playlist-pos")? == pos {
+                return Ok(());
+            }
+        }
+    }
+}
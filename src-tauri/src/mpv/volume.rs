@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+const MIN_VOLUME_MAX: f64 = 100.0;
+const MAX_VOLUME_MAX: f64 = 200.0;
+// The logarithmic curve spreads the slider over this range below full volume
+const LOG_RANGE_DB: f64 = 60.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum VolumeCurve {
+    // Amplitude follows the slider
+    Linear,
+    // mpv's own volume scale, amplitude is the slider cubed
+    #[default]
+    Cubic,
+    // Even steps in dB, which leaves the most room at low volumes
+    Logarithmic,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct VolumeSettings {
+    #[serde(default)]
+    pub curve: VolumeCurve,
+    #[serde(default)]
+    pub boost: bool,
+    // mpv's volume-max in percent, only used while boost is on
+    #[serde(default = "default_boost_max")]
+    pub boost_max: f64,
+}
+
+fn default_boost_max() -> f64 {
+    150.0
+}
+
+impl Default for VolumeSettings {
+    fn default() -> Self {
+        Self {
+            curve: VolumeCurve::default(),
+            boost: false,
+            boost_max: default_boost_max(),
+        }
+    }
+}
+
+impl VolumeSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(MIN_VOLUME_MAX..=MAX_VOLUME_MAX).contains(&self.boost_max) {
+            return Err(format!(
+                "Volume boost to {}% is out of range ({}% to {}%)",
+                self.boost_max, MIN_VOLUME_MAX, MAX_VOLUME_MAX
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn volume_max(&self) -> f64 {
+        if self.boost {
+            self.boost_max
+        } else {
+            MIN_VOLUME_MAX
+        }
+    }
+
+    // Highest level the slider can reach, 1 is 100% and boost goes beyond it
+    pub fn max_level(&self) -> f64 {
+        self.volume_max() / 100.0
+    }
+
+    pub fn validate_level(&self, level: f64) -> Result<(), String> {
+        if !(0.0..=self.max_level()).contains(&level) {
+            return Err(format!(
+                "Volume {} is out of range (0 to {})",
+                level,
+                self.max_level()
+            ));
+        }
+        Ok(())
+    }
+
+    // Maps a slider level to mpv's volume property, the boost range above 1 stays on mpv's scale
+    pub fn to_mpv(&self, level: f64) -> f64 {
+        if level >= 1.0 {
+            return level * 100.0;
+        }
+        if level <= 0.0 {
+            return 0.0;
+        }
+
+        let amplitude = match self.curve {
+            VolumeCurve::Linear => level,
+            VolumeCurve::Cubic => level.powi(3),
+            VolumeCurve::Logarithmic => 10f64.powf((level - 1.0) * LOG_RANGE_DB / 20.0),
+        };
+        100.0 * amplitude.cbrt()
+    }
+
+    pub fn from_mpv(&self, volume: f64) -> f64 {
+        let level = volume / 100.0;
+        if level >= 1.0 {
+            return level;
+        }
+        if level <= 0.0 {
+            return 0.0;
+        }
+
+        let amplitude = level.powi(3);
+        match self.curve {
+            VolumeCurve::Linear => amplitude,
+            VolumeCurve::Cubic => level,
+            VolumeCurve::Logarithmic => (1.0 + 20.0 * amplitude.log10() / LOG_RANGE_DB).max(0.0),
+        }
+    }
+}
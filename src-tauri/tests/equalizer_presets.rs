@@ -0,0 +1,46 @@
+use navithingy_lib::mpv::equalizer::{EqualizerSettings, PresetStore};
+use std::collections::BTreeMap;
+use std::fs;
+use uuid::Uuid;
+
+#[test]
+fn presets_round_trip_through_disk() {
+    let dir = std::env::temp_dir().join(format!("navithingy-eq-{}", Uuid::new_v4()));
+    let store = PresetStore::new(dir.clone());
+    let custom = EqualizerSettings {
+        enabled: true,
+        preamp: -2.0,
+        ..EqualizerSettings::graphic([1.0, 2.0, 3.0, 4.0, 5.0, 5.0, 4.0, 3.0, 2.0, 1.0])
+    };
+
+    assert!(store.list().unwrap().contains(&"Bass Boost".to_string()));
+    assert!(store.load("Bass Boost").unwrap().enabled);
+    assert!(store.load("Missing").is_err());
+    assert!(store.save("../escape", &custom).is_err());
+
+    store.save("Late Night", &custom).unwrap();
+    store.save("Rock", &custom).unwrap();
+    assert_eq!(store.load("Late Night").unwrap(), custom);
+    assert_eq!(store.load("Rock").unwrap(), custom);
+    let names = store.list().unwrap();
+    assert_eq!(names.iter().filter(|name| *name == "Rock").count(), 1);
+    assert!(names.contains(&"Late Night".to_string()));
+
+    let genres = BTreeMap::from([
+        ("Jazz".to_string(), "Late Night".to_string()),
+        ("Metal".to_string(), "Gone".to_string()),
+    ]);
+    store.set_genre_presets(&genres).unwrap();
+    assert_eq!(store.genre_presets().unwrap(), genres);
+    let (resolved, skipped) = store.resolve_genre_presets().unwrap();
+    assert_eq!(resolved.len(), 1);
+    assert_eq!(skipped.len(), 1);
+    assert!(skipped[0].contains("Metal"), "{}", skipped[0]);
+    assert_eq!(resolved.get("Jazz"), Some(&custom));
+
+    store.delete("Late Night").unwrap();
+    assert!(store.load("Late Night").is_err());
+    assert!(store.delete("Late Night").is_err());
+
+    let _ = fs::remove_dir_all(dir);
+}
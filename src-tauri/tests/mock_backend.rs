@@ -0,0 +1,420 @@
+use navithingy_lib::backend::mock::MockBackend;
+use navithingy_lib::backend::{AudioEffects, MpvExtensions, PlaybackBackend};
+use navithingy_lib::mpv::chapters::Chapter;
+use navithingy_lib::mpv::crossfade::{CrossfadeSettings, FadeCurve};
+use navithingy_lib::mpv::equalizer::EqualizerSettings;
+use navithingy_lib::mpv::events::PlayerEvent;
+use navithingy_lib::mpv::logs::LogLevel;
+use navithingy_lib::mpv::repeat::RepeatMode;
+use navithingy_lib::mpv::sleep_timer::{SleepTimerMode, SleepTimerSettings};
+use navithingy_lib::mpv::speed::PitchCorrection;
+use navithingy_lib::mpv::volume::{VolumeCurve, VolumeSettings};
+use navithingy_lib::mpv::AudioDevice;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+fn urls(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|i| format!("https://navidrome.local/rest/stream?id={}", i))
+        .collect()
+}
+
+fn backend_with_events() -> (MockBackend, Arc<Mutex<Vec<PlayerEvent>>>) {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let mut backend = MockBackend::new();
+    let sink = Arc::clone(&events);
+    backend.set_event_sink(Arc::new(move |event| sink.lock().unwrap().push(event)));
+    backend.init(None).unwrap();
+    (backend, events)
+}
+
+#[test]
+fn playlist_advances_through_tracks_and_ends() {
+    let (mut backend, events) = backend_with_events();
+    backend.set_track_duration(10.0);
+    backend.load_playlist(urls(2)).unwrap();
+
+    backend.advance(4.0);
+    assert_eq!(backend.get_status().position, 4.0);
+
+    backend.advance(8.0);
+    let status = backend.get_status();
+    assert_eq!(status.playlist_pos, 1);
+    assert_eq!(status.position, 2.0);
+
+    backend.advance(20.0);
+    let status = backend.get_status();
+    assert_eq!(status.state, "ended");
+    assert_eq!(status.position, 10.0);
+
+    let events = events.lock().unwrap();
+    let ended = events
+        .iter()
+        .filter(|e| matches!(e, PlayerEvent::Ended(_)))
+        .count();
+    assert_eq!(ended, 2);
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, PlayerEvent::TrackChanged(c) if c.playlist_pos == 1)));
+}
+
+#[test]
+fn repeat_modes_and_ab_loop_keep_playback_going() {
+    let (mut backend, events) = backend_with_events();
+    backend.set_track_duration(10.0);
+    backend.load_playlist(urls(2)).unwrap();
+
+    backend.set_repeat(RepeatMode::One).unwrap();
+    backend.advance(25.0);
+    let status = backend.get_status();
+    assert_eq!((status.playlist_pos, status.position), (0, 5.0));
+    assert!(!events
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|e| matches!(e, PlayerEvent::Ended(_))));
+
+    backend.set_repeat(RepeatMode::All).unwrap();
+    backend.advance(17.0);
+    let status = backend.get_status();
+    assert_eq!((status.playlist_pos, status.position), (0, 2.0));
+    assert_eq!(status.state, "playing");
+
+    backend.set_ab_loop(Some(3.0), Some(6.0)).unwrap();
+    backend.advance(6.0);
+    assert_eq!(backend.get_status().position, 5.0);
+    assert!(backend.set_ab_loop(Some(6.0), Some(3.0)).is_err());
+
+    backend.playlist_next().unwrap();
+    let status = backend.get_status();
+    assert_eq!((status.ab_loop_a, status.ab_loop_b), (None, None));
+}
+
+#[test]
+fn sleep_timer_pauses_after_countdown_or_tracks() {
+    let (mut backend, _) = backend_with_events();
+    backend.set_track_duration(10.0);
+    backend.load_playlist(urls(3)).unwrap();
+
+    backend
+        .set_sleep_timer(SleepTimerSettings {
+            mode: SleepTimerMode::Minutes { minutes: 0.25 },
+            fade_secs: 5.0,
+        })
+        .unwrap();
+    backend.advance(4.0);
+    let timer = backend.get_status().sleep_timer.unwrap();
+    assert_eq!(timer.remaining_secs, Some(11.0));
+    assert!(!timer.fading);
+
+    backend.advance(30.0);
+    let status = backend.get_status();
+    assert_eq!((status.playlist_pos, status.position), (1, 5.0));
+    assert_eq!(status.state, "paused");
+    assert_eq!(status.sleep_timer, None);
+
+    backend.play().unwrap();
+    backend
+        .set_sleep_timer(SleepTimerSettings {
+            mode: SleepTimerMode::Tracks { count: 2 },
+            fade_secs: 0.0,
+        })
+        .unwrap();
+    backend.advance(60.0);
+    let status = backend.get_status();
+    assert_eq!((status.playlist_pos, status.position), (2, 10.0));
+    assert_eq!(status.state, "paused");
+
+    let invalid = SleepTimerSettings {
+        mode: SleepTimerMode::Tracks { count: 0 },
+        fade_secs: 0.0,
+    };
+    assert!(backend.set_sleep_timer(invalid).is_err());
+}
+
+#[test]
+fn chapters_are_tracked_and_navigable() {
+    let mut backend = MockBackend::new();
+    let chapter = |title: &str, time: f64| Chapter {
+        title: Some(title.to_string()),
+        time,
+    };
+    backend.set_chapters(
+        &urls(2)[0],
+        vec![
+            chapter("One", 0.0),
+            chapter("Two", 60.0),
+            chapter("Three", 120.0),
+        ],
+    );
+    assert!(backend.chapter_next().is_err());
+
+    backend.load_playlist(urls(2)).unwrap();
+    backend.play().unwrap();
+    assert_eq!(backend.get_status().chapter_count, Some(3));
+    backend.advance(70.0);
+    assert_eq!(backend.get_status().chapter_title.as_deref(), Some("Two"));
+
+    // Well into a chapter, going back restarts it
+    backend.chapter_prev().unwrap();
+    assert_eq!(backend.get_status().position, 60.0);
+    backend.chapter_prev().unwrap();
+    assert_eq!(backend.get_status().chapter, Some(0));
+
+    backend.set_chapter(2).unwrap();
+    assert_eq!(backend.get_status().chapter_title.as_deref(), Some("Three"));
+    assert!(backend.set_chapter(3).is_err());
+    backend.chapter_next().unwrap();
+    let status = backend.get_status();
+    assert_eq!((status.playlist_pos, status.chapter_count), (1, Some(0)));
+}
+
+#[test]
+fn paused_backend_does_not_progress() {
+    let (mut backend, _) = backend_with_events();
+    backend.load(&urls(1)[0]).unwrap();
+    backend.pause().unwrap();
+    backend.advance(5.0);
+
+    let status = backend.get_status();
+    assert_eq!(status.state, "paused");
+    assert_eq!(status.position, 0.0);
+
+    backend.play().unwrap();
+    backend.advance(5.0);
+    assert_eq!(backend.get_status().position, 5.0);
+}
+
+#[test]
+fn playback_speed_scales_progress() {
+    let (mut backend, _) = backend_with_events();
+    backend.set_track_duration(60.0);
+    backend.load_playlist(urls(1)).unwrap();
+
+    backend.set_speed(1.5, PitchCorrection::Rubberband).unwrap();
+    backend.advance(10.0);
+    let status = backend.get_status();
+    assert_eq!(status.position, 15.0);
+    assert_eq!(status.speed, 1.5);
+
+    assert!(backend.set_speed(0.0, PitchCorrection::Off).is_err());
+    backend.set_pitch(-2.0).unwrap();
+    assert_eq!(backend.get_status().pitch, -2.0);
+    assert_eq!(backend.get_status().speed, 1.5);
+}
+
+#[test]
+fn jump_to_index_validates_range() {
+    let (mut backend, _) = backend_with_events();
+    assert_eq!(
+        backend.playlist_jump_to_index(0),
+        Err("Playlist is empty".to_string())
+    );
+
+    backend.load_playlist(urls(3)).unwrap();
+    assert!(backend.playlist_jump_to_index(3).is_err());
+    backend.playlist_jump_to_index(2).unwrap();
+    assert_eq!(backend.get_status().playlist_pos, 2);
+}
+
+#[test]
+fn injected_failures_surface_as_errors() {
+    let (mut backend, events) = backend_with_events();
+    let playlist = urls(2);
+    backend.set_unplayable(&playlist[1]);
+    backend.load_playlist(playlist).unwrap();
+
+    backend.fail_next_command("property unavailable");
+    assert_eq!(backend.seek(30.0), Err("property unavailable".to_string()));
+    backend.seek(30.0).unwrap();
+
+    backend.playlist_next().unwrap();
+    assert_eq!(backend.get_status().state, "error");
+
+    let events = events.lock().unwrap();
+    let errors: Vec<_> = events
+        .iter()
+        .filter_map(|e| match e {
+            PlayerEvent::Error(error) => Some(error.message.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0], "property unavailable");
+}
+
+#[test]
+fn stop_clears_playlist() {
+    let (mut backend, _) = backend_with_events();
+    backend.load_playlist(urls(3)).unwrap();
+    backend.stop().unwrap();
+
+    let status = backend.get_status();
+    assert_eq!(status.state, "idle");
+    assert_eq!(status.playlist_count, 0);
+    assert!(backend.playlist().is_empty());
+    assert_eq!(
+        backend.commands(),
+        &["init", "load_playlist", "stop"].map(String::from)
+    );
+}
+
+#[test]
+fn playlist_edits_keep_current_track_playing() {
+    let (mut backend, _) = backend_with_events();
+    let urls = urls(4);
+    let order = |indices: &[usize]| indices.iter().map(|&i| urls[i].clone()).collect::<Vec<_>>();
+    backend.load_playlist(urls[..3].to_vec()).unwrap();
+    backend.playlist_jump_to_index(1).unwrap();
+
+    backend.playlist_insert(&urls[3], 0).unwrap();
+    backend.playlist_move(3, 1).unwrap();
+    let status = backend.get_status();
+    assert_eq!(backend.playlist(), order(&[3, 2, 0, 1]));
+    assert_eq!(status.playlist_pos, 3);
+    assert_eq!(status.media_title.as_deref(), Some(urls[1].as_str()));
+
+    backend.playlist_clear_after_current().unwrap();
+    assert_eq!(backend.playlist().len(), 4);
+    backend.playlist_remove(0).unwrap();
+    assert_eq!(backend.get_status().playlist_pos, 2);
+    assert_eq!(backend.get_status().state, "playing");
+}
+
+#[test]
+fn missing_audio_device_falls_back_to_auto() {
+    let (mut backend, _) = backend_with_events();
+    let device = |name: &str| AudioDevice {
+        name: name.to_string(),
+        description: name.to_string(),
+    };
+
+    backend.set_audio_devices(vec![device("auto"), device("alsa/hdmi")]);
+    backend.set_audio_device("alsa/hdmi").unwrap();
+    assert!(backend.set_audio_device("alsa/missing").is_err());
+
+    backend.set_audio_devices(vec![device("auto")]);
+    assert_eq!(backend.get_status().audio_device.as_deref(), Some("auto"));
+
+    backend.set_audio_devices(vec![device("auto"), device("alsa/hdmi")]);
+    assert_eq!(
+        backend.get_status().audio_device.as_deref(),
+        Some("alsa/hdmi")
+    );
+}
+
+#[test]
+fn manual_equalizer_overrides_genre_preset_until_genre_changes() {
+    let (mut backend, events) = backend_with_events();
+    let rock = EqualizerSettings {
+        enabled: true,
+        ..EqualizerSettings::graphic([4.0, 3.0, 2.0, 0.0, -1.0, -1.0, 1.0, 2.0, 3.0, 4.0])
+    };
+    let manual = EqualizerSettings {
+        enabled: true,
+        preamp: -3.0,
+        ..EqualizerSettings::default()
+    };
+    backend
+        .set_genre_equalizers(HashMap::from([("Rock".to_string(), rock.clone())]))
+        .unwrap();
+
+    backend.set_track_genre(Some("Indie / Rock"));
+    assert_eq!(backend.equalizer(), rock);
+    assert!(events.lock().unwrap().iter().any(|event| matches!(
+        event,
+        PlayerEvent::EqualizerChanged(change) if change.genre.as_deref() == Some("rock")
+    )));
+
+    backend.set_equalizer(manual.clone()).unwrap();
+    backend.set_track_genre(Some("Indie / Rock"));
+    assert_eq!(backend.equalizer(), manual);
+
+    backend.set_track_genre(Some("Jazz"));
+    assert_eq!(backend.equalizer(), manual);
+    backend.set_track_genre(Some("rock"));
+    assert_eq!(backend.equalizer(), rock);
+
+    let invalid = EqualizerSettings {
+        bands: Vec::new(),
+        ..manual
+    };
+    assert!(backend.set_equalizer(invalid).is_err());
+}
+
+#[test]
+fn volume_boost_raises_the_limit_until_switched_off() {
+    let mut backend = MockBackend::new();
+    assert!(backend.set_volume(1.2).is_err());
+
+    let boosted = VolumeSettings {
+        curve: VolumeCurve::Cubic,
+        boost: true,
+        boost_max: 150.0,
+    };
+    backend.set_volume_settings(boosted.clone()).unwrap();
+    assert_eq!(backend.get_status().max_volume, 1.5);
+    backend.set_volume(1.2).unwrap();
+    backend.set_mute(true).unwrap();
+
+    let too_loud = VolumeSettings {
+        boost_max: 300.0,
+        ..boosted.clone()
+    };
+    assert!(backend.set_volume_settings(too_loud).is_err());
+    backend
+        .set_volume_settings(VolumeSettings::default())
+        .unwrap();
+    let status = backend.get_status();
+    assert_eq!((status.volume, status.max_volume), (1.0, 1.0));
+    assert!(status.muted);
+}
+
+#[test]
+fn logs_below_the_requested_level_are_dropped() {
+    let (mut backend, events) = backend_with_events();
+    backend.set_log_level(LogLevel::Warn).unwrap();
+
+    backend.log(LogLevel::Error, "ao/pulse", "Init failed\n");
+    backend.log(LogLevel::Info, "cplayer", "Playing: track.flac\n");
+    backend.log(LogLevel::Warn, "ffmpeg", "Invalid frame\n");
+
+    let texts: Vec<_> = backend.logs().into_iter().map(|entry| entry.text).collect();
+    assert_eq!(texts, vec!["Init failed", "Invalid frame"]);
+    let logged = events
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|event| matches!(event, PlayerEvent::Log(_)))
+        .count();
+    assert_eq!(logged, 2);
+
+    backend.set_log_level(LogLevel::No).unwrap();
+    backend.log(LogLevel::Fatal, "cplayer", "Exiting\n");
+    assert_eq!(backend.logs().len(), 2);
+}
+
+#[test]
+fn crossfade_rejects_out_of_range_duration() {
+    let mut backend = MockBackend::new();
+    let settings = CrossfadeSettings {
+        enabled: true,
+        duration: 8.0,
+        curve: FadeCurve::SCurve,
+        skip_same_album: false,
+    };
+    backend.set_crossfade(settings.clone()).unwrap();
+
+    let too_long = CrossfadeSettings {
+        duration: 30.0,
+        ..settings.clone()
+    };
+    assert!(backend.set_crossfade(too_long).is_err());
+    assert_eq!(backend.crossfade(), &settings);
+
+    for curve in [FadeCurve::Linear, FadeCurve::EqualPower, FadeCurve::SCurve] {
+        assert_eq!(curve.gains(0.0), (1.0, 0.0));
+        let (outgoing, incoming) = curve.gains(1.0);
+        assert!(outgoing.abs() < 1e-9 && (incoming - 1.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,1250 @@
+#![cfg(unix)]
+
+mod support;
+
+use navithingy_lib::backend::{AudioEffects, MpvExtensions, PlaybackBackend};
+use navithingy_lib::mpv::cache::CacheRange;
+use navithingy_lib::mpv::crossfade::CrossfadeSettings;
+use navithingy_lib::mpv::equalizer::EqualizerSettings;
+use navithingy_lib::mpv::events::PlayerEvent;
+use navithingy_lib::mpv::logs::{LogLevel, LOG_CAPACITY};
+use navithingy_lib::mpv::loudness::{NormalizationMode, NormalizationSettings};
+use navithingy_lib::mpv::options::MpvOptions;
+use navithingy_lib::mpv::properties::PropertyAllowlist;
+use navithingy_lib::mpv::repeat::RepeatMode;
+use navithingy_lib::mpv::replaygain::{ReplayGainMode, ReplayGainSettings};
+use navithingy_lib::mpv::sleep_timer::{SleepTimerMode, SleepTimerSettings};
+use navithingy_lib::mpv::speed::PitchCorrection;
+use navithingy_lib::mpv::stream_info::{self, StreamInfo};
+use navithingy_lib::mpv::volume::{VolumeCurve, VolumeSettings};
+use navithingy_lib::mpv::MpvPlayer;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use support::{wait_for, FakeMpv};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn urls(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|i| format!("https://navidrome.local/rest/stream?id={}", i))
+        .collect()
+}
+
+fn start_player(fake: &FakeMpv) -> (MpvPlayer, Arc<Mutex<Vec<PlayerEvent>>>) {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+
+    let mut player = MpvPlayer::new();
+    player.set_event_sink(Arc::new(move |event| sink.lock().unwrap().push(event)));
+    assert_eq!(player.init(Some(fake.executable())), Ok(true));
+
+    (player, events)
+}
+
+#[test]
+fn init_launches_executable_and_observes_properties() {
+    let fake = FakeMpv::start();
+    let (player, _) = start_player(&fake);
+
+    let args = fake.launch_args();
+    assert!(args.contains(&"--idle=yes".to_string()));
+    assert!(args
+        .iter()
+        .any(|arg| arg.starts_with("--input-ipc-server=")));
+
+    let observed = fake.observed_properties();
+    for property in [
+        "time-pos",
+        "duration",
+        "pause",
+        "playlist-pos",
+        "playlist-count",
+    ] {
+        assert!(observed.contains(&property.to_string()), "{}", property);
+    }
+
+    assert_eq!(
+        fake.commands().first(),
+        Some(&vec![json!("get_property"), json!("mpv-version")])
+    );
+    assert!(player.get_status().initialized);
+}
+
+#[test]
+fn init_reports_stderr_when_mpv_exits_during_startup() {
+    let fake = FakeMpv::start();
+    let executable = fake.write_script(
+        "broken-mpv",
+        "echo 'Error parsing option input-ipc-server' >&2\nexit 1",
+    );
+
+    let mut player = MpvPlayer::new();
+    let error = player.init(Some(executable)).unwrap_err();
+
+    assert!(error.contains("exited during startup"), "{}", error);
+    assert!(error.contains("Error parsing option"), "{}", error);
+    assert!(!player.get_status().initialized);
+}
+
+#[test]
+fn init_times_out_when_ipc_never_becomes_ready() {
+    let fake = FakeMpv::start();
+    let executable = fake.write_script("silent-mpv", "exec sleep 60");
+
+    let mut player = MpvPlayer::new();
+    player.set_startup_timeout(Duration::from_millis(300));
+    let started = Instant::now();
+    let error = player.init(Some(executable)).unwrap_err();
+
+    assert!(error.contains("Timed out"), "{}", error);
+    assert!(started.elapsed() < Duration::from_secs(3));
+}
+
+#[test]
+fn load_playlist_optimized_replaces_playlist() {
+    let fake = FakeMpv::start();
+    let (mut player, _) = start_player(&fake);
+
+    player.load_playlist_optimized(urls(2)).unwrap();
+    player.load_playlist_optimized(urls(4)).unwrap();
+
+    assert_eq!(fake.playlist(), urls(4));
+    assert_eq!(fake.playlist_pos(), 0);
+    assert!(wait_for(TIMEOUT, || player.get_status().playlist_count == 4));
+
+    fake.set_property("time-pos", json!(42.0));
+    player.load_playlist_optimized(urls(4)).unwrap();
+    assert_eq!(fake.property("time-pos"), Some(json!(0.0)));
+}
+
+#[test]
+fn playlist_jump_to_index_moves_mpv_position() {
+    let fake = FakeMpv::start();
+    let (mut player, events) = start_player(&fake);
+
+    player.load_playlist_optimized(urls(3)).unwrap();
+    assert!(wait_for(TIMEOUT, || player.get_status().playlist_count == 3));
+
+    player.playlist_jump_to_index(2).unwrap();
+    assert_eq!(fake.playlist_pos(), 2);
+    assert!(wait_for(TIMEOUT, || {
+        events
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|e| matches!(e, PlayerEvent::TrackChanged(c) if c.playlist_pos == 2))
+    }));
+
+    assert!(player.playlist_jump_to_index(3).is_err());
+}
+
+#[test]
+fn playlist_edits_apply_without_reloading() {
+    let fake = FakeMpv::start();
+    let (mut player, events) = start_player(&fake);
+    let urls = urls(4);
+    let order = |indices: &[usize]| indices.iter().map(|&i| urls[i].clone()).collect::<Vec<_>>();
+
+    player.load_playlist_optimized(urls[..3].to_vec()).unwrap();
+    player.playlist_jump_to_index(1).unwrap();
+    let commands_before = fake.commands().len();
+
+    player.playlist_insert(&urls[3], 0).unwrap();
+    assert_eq!(fake.playlist(), order(&[3, 0, 1, 2]));
+    assert_eq!(fake.playlist_pos(), 2);
+
+    player.playlist_move(0, 3).unwrap();
+    assert_eq!(fake.playlist(), order(&[0, 1, 2, 3]));
+    assert_eq!(fake.playlist_pos(), 1);
+
+    player.playlist_remove(0).unwrap();
+    assert_eq!(fake.playlist(), order(&[1, 2, 3]));
+    assert_eq!(fake.playlist_pos(), 0);
+
+    player.playlist_clear_after_current().unwrap();
+    assert_eq!(fake.playlist(), order(&[1]));
+    assert_eq!(fake.playlist_pos(), 0);
+
+    let names = fake.command_names()[commands_before..].to_vec();
+    assert!(!names
+        .iter()
+        .any(|name| name == "playlist-clear" || name == "stop"));
+    assert!(player.playlist_remove(5).is_err());
+
+    let events = events.lock().unwrap();
+    let last_change = events.iter().rev().find_map(|e| match e {
+        PlayerEvent::PlaylistChanged(change) => Some(change),
+        _ => None,
+    });
+    assert_eq!(
+        last_change.map(|c| c.entries.clone()),
+        Some(vec![urls[1].clone()])
+    );
+    assert_eq!(last_change.map(|c| c.playlist_pos), Some(0));
+}
+
+#[test]
+fn sync_playlist_edits_around_current_entry() {
+    let fake = FakeMpv::start();
+    let (mut player, _) = start_player(&fake);
+    let urls = urls(6);
+    let order = |indices: &[usize]| indices.iter().map(|&i| urls[i].clone()).collect::<Vec<_>>();
+
+    player.sync_playlist(order(&[0, 1, 2, 3]), 1).unwrap();
+    assert_eq!(fake.playlist(), order(&[0, 1, 2, 3]));
+    assert_eq!(fake.playlist_pos(), 1);
+    fake.set_property("time-pos", json!(50.0));
+    let commands_before = fake.commands().len();
+
+    player.sync_playlist(order(&[3, 1, 5, 0]), 1).unwrap();
+    assert_eq!(fake.playlist(), order(&[3, 1, 5, 0]));
+    assert_eq!(fake.playlist_pos(), 1);
+    assert_eq!(fake.property("time-pos"), Some(json!(50.0)));
+
+    let names = fake.command_names()[commands_before..].to_vec();
+    for name in [
+        "stop",
+        "playlist-clear",
+        "playlist-play-index",
+        "set_property",
+    ] {
+        assert!(!names.contains(&name.to_string()), "{}", name);
+    }
+
+    player.sync_playlist(order(&[4, 5]), 0).unwrap();
+    assert_eq!(fake.playlist(), order(&[4, 5]));
+    assert_eq!(fake.playlist_pos(), 0);
+    assert!(wait_for(TIMEOUT, || player.get_status().playlist_count == 2));
+}
+
+#[test]
+fn playlist_entries_and_current_url_come_from_mpv() {
+    let fake = FakeMpv::start();
+    let (mut player, _) = start_player(&fake);
+    let urls = urls(3);
+
+    player.load_playlist_optimized(urls.clone()).unwrap();
+    player.playlist_jump_to_index(2).unwrap();
+
+    let entries = player.playlist_entries().unwrap();
+    let filenames: Vec<_> = entries.iter().map(|e| e.filename.clone()).collect();
+    assert_eq!(filenames, urls);
+    assert!(entries[2].current && entries[2].playing);
+    assert!(!entries[0].current);
+    assert!(entries.iter().all(|e| e.id.is_some()));
+
+    assert!(wait_for(TIMEOUT, || {
+        player.get_status().current_url.as_deref() == Some(urls[2].as_str())
+    }));
+}
+
+#[test]
+fn end_file_updates_status_and_emits_events() {
+    let fake = FakeMpv::start();
+    let (mut player, events) = start_player(&fake);
+
+    player.load(&urls(1)[0]).unwrap();
+    fake.end_file("eof");
+
+    assert!(wait_for(TIMEOUT, || player.get_status().state == "ended"));
+    assert!(wait_for(TIMEOUT, || {
+        events
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|e| matches!(e, PlayerEvent::Ended(ended) if ended.reason == "eof"))
+    }));
+    assert!(wait_for(TIMEOUT, || {
+        events
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|e| matches!(e, PlayerEvent::Status(status) if status.state == "ended"))
+    }));
+}
+
+#[test]
+fn last_file_held_open_at_its_end_is_reported_once() {
+    let fake = FakeMpv::start();
+    let (mut player, events) = start_player(&fake);
+    let ended = || {
+        events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| matches!(e, PlayerEvent::Ended(_)))
+            .count()
+    };
+
+    player.load(&urls(1)[0]).unwrap();
+    fake.set_property("eof-reached", json!(true));
+    assert!(wait_for(TIMEOUT, || ended() == 1));
+
+    fake.end_file("eof");
+    fake.set_property("time-pos", json!(1.0));
+    assert!(wait_for(TIMEOUT, || player.get_status().position == 1.0));
+    assert_eq!(ended(), 1);
+}
+
+#[test]
+fn property_changes_update_status() {
+    let fake = FakeMpv::start();
+    let (mut player, _) = start_player(&fake);
+
+    player.load(&urls(1)[0]).unwrap();
+    fake.set_property("time-pos", json!(42.5));
+    fake.set_property("pause", json!(true));
+
+    assert!(wait_for(TIMEOUT, || {
+        let status = player.get_status();
+        status.position == 42.5 && status.state == "paused"
+    }));
+}
+
+#[test]
+fn get_status_fetches_properties_in_one_batch() {
+    let fake = FakeMpv::start();
+    let (mut player, _) = start_player(&fake);
+
+    player.load(&urls(1)[0]).unwrap();
+    assert!(wait_for(TIMEOUT, || player.get_status().duration == 180.0));
+
+    fake.set_property_silently("time-pos", json!(77.0));
+    fake.set_property_silently("media-title", json!("Silent Track"));
+    fake.set_property_silently("chapter", json!(2));
+    let commands_before = fake.commands().len();
+
+    let status = player.get_status();
+    assert_eq!(status.position, 77.0);
+    assert_eq!(status.media_title.as_deref(), Some("Silent Track"));
+    assert_eq!(status.chapter, Some(2));
+
+    let fetched: Vec<String> = fake.commands()[commands_before..]
+        .iter()
+        .filter(|command| command[0] == "get_property")
+        .map(|command| command[1].as_str().unwrap().to_string())
+        .collect();
+    for property in [
+        "time-pos",
+        "duration",
+        "pause",
+        "playlist-pos",
+        "playlist-count",
+    ] {
+        assert!(fetched.contains(&property.to_string()), "{}", property);
+    }
+}
+
+#[test]
+fn audio_device_falls_back_to_auto_when_unplugged() {
+    let fake = FakeMpv::start();
+    let (mut player, events) = start_player(&fake);
+    let all_devices = fake.property("audio-device-list").unwrap();
+
+    let devices = player.audio_devices().unwrap();
+    assert!(devices.iter().any(|device| device.name == "pulse/usb-dac"));
+    assert!(player.set_audio_device("pulse/missing").is_err());
+
+    player.set_audio_device("pulse/usb-dac").unwrap();
+    assert!(wait_for(TIMEOUT, || {
+        player.get_status().audio_device.as_deref() == Some("pulse/usb-dac")
+    }));
+
+    fake.set_property(
+        "audio-device-list",
+        json!([{ "name": "auto", "description": "Autoselect device" }]),
+    );
+    assert!(wait_for(TIMEOUT, || {
+        fake.property("audio-device") == Some(json!("auto"))
+    }));
+    assert!(events.lock().unwrap().iter().any(
+        |e| matches!(e, PlayerEvent::AudioDevicesChanged(change) if change.devices.len() == 1)
+    ));
+
+    fake.set_property("audio-device-list", all_devices);
+    assert!(wait_for(TIMEOUT, || {
+        fake.property("audio-device") == Some(json!("pulse/usb-dac"))
+    }));
+}
+
+#[test]
+fn replaygain_settings_are_applied_and_gain_reported() {
+    let fake = FakeMpv::start();
+    let (mut player, _) = start_player(&fake);
+    let settings = ReplayGainSettings {
+        mode: ReplayGainMode::Track,
+        preamp: 2.0,
+        clip_prevention: true,
+        fallback_gain: -6.0,
+        album_context: false,
+    };
+
+    player.set_replaygain(settings.clone()).unwrap();
+    assert_eq!(fake.property("replaygain"), Some(json!("track")));
+    assert_eq!(fake.property("replaygain-preamp"), Some(json!(2.0)));
+    assert_eq!(fake.property("replaygain-clip"), Some(json!(true)));
+    assert_eq!(fake.property("replaygain-fallback"), Some(json!(-6.0)));
+    assert_eq!(player.get_status().replaygain_gain, Some(-6.0));
+
+    player.load(&urls(1)[0]).unwrap();
+    fake.set_property(
+        "current-tracks/audio",
+        json!({
+            "replaygain-track-gain": -7.5,
+            "replaygain-track-peak": 0.9,
+            "replaygain-album-gain": 1.0,
+            "replaygain-album-peak": 1.2
+        }),
+    );
+    assert!(wait_for(TIMEOUT, || {
+        player.get_status().replaygain_gain == Some(-5.5)
+    }));
+
+    player
+        .set_replaygain(ReplayGainSettings {
+            mode: ReplayGainMode::Auto,
+            album_context: true,
+            ..settings
+        })
+        .unwrap();
+    assert_eq!(fake.property("replaygain"), Some(json!("album")));
+    let gain = player.get_status().replaygain_gain.unwrap();
+    assert!((gain - -20.0 * 1.2f64.log10()).abs() < 1e-9, "{}", gain);
+}
+
+#[test]
+fn normalization_replaces_replaygain_while_active() {
+    let fake = FakeMpv::start();
+    let (mut player, _) = start_player(&fake);
+    player
+        .set_replaygain(ReplayGainSettings {
+            mode: ReplayGainMode::Track,
+            ..ReplayGainSettings::default()
+        })
+        .unwrap();
+
+    let loudnorm = NormalizationSettings {
+        mode: NormalizationMode::Loudnorm,
+        target_lufs: -14.0,
+    };
+    player.set_normalization(loudnorm.clone()).unwrap();
+    assert_eq!(
+        fake.audio_filters(),
+        vec!["@loudness:lavfi=[loudnorm=I=-14:TP=-1.5:LRA=11]".to_string()]
+    );
+    assert_eq!(fake.property("replaygain"), Some(json!("no")));
+    let status = player.get_status();
+    assert_eq!(status.normalization, NormalizationMode::Loudnorm);
+    assert_eq!(status.replaygain_gain, None);
+
+    player
+        .set_normalization(NormalizationSettings {
+            mode: NormalizationMode::Dynaudnorm,
+            ..loudnorm.clone()
+        })
+        .unwrap();
+    let filters = fake.audio_filters();
+    assert_eq!(filters.len(), 1);
+    assert!(
+        filters[0].starts_with("@loudness:lavfi=[dynaudnorm="),
+        "{:?}",
+        filters
+    );
+
+    let error = player
+        .set_normalization(NormalizationSettings {
+            target_lufs: 0.0,
+            ..loudnorm
+        })
+        .unwrap_err();
+    assert!(error.contains("out of range"), "{}", error);
+
+    player
+        .set_normalization(NormalizationSettings::default())
+        .unwrap();
+    assert!(fake.audio_filters().is_empty());
+    assert_eq!(fake.property("replaygain"), Some(json!("track")));
+    assert_eq!(player.get_status().replaygain_gain, Some(0.0));
+}
+
+#[test]
+fn user_options_are_passed_to_mpv_after_its_own() {
+    let fake = FakeMpv::start();
+    let mut player = MpvPlayer::new();
+
+    let option = |name: &str, value: &str| MpvOptions {
+        options: [(name.to_string(), value.to_string())].into(),
+        load_config: false,
+    };
+    assert!(player
+        .set_mpv_options(option("input-ipc-server", "/tmp/other"))
+        .is_err());
+    assert!(player.set_mpv_options(option("no-idle", "")).is_err());
+    assert!(player.set_mpv_options(option("--ao", "alsa")).is_err());
+    let error = player
+        .set_mpv_options(option("script", "/tmp/x.lua"))
+        .unwrap_err();
+    assert!(error.contains("not allowed"), "{}", error);
+    assert!(player
+        .set_mpv_options(option("scripts-append", "/tmp/x.lua"))
+        .is_err());
+    assert!(player
+        .set_mpv_options(option("log-file", "/tmp/mpv.log"))
+        .is_err());
+    assert!(player
+        .set_mpv_options(option("watch-later-dir", "/tmp"))
+        .is_err());
+    assert!(player
+        .set_mpv_options(option("stream-lavf-o-append", "protocol_whitelist=file"))
+        .is_err());
+    assert!(player
+        .set_mpv_options(option("demuxer-lavf-o", "protocol_whitelist=file"))
+        .is_err());
+
+    let options = MpvOptions {
+        options: [
+            ("ao".to_string(), "pipewire".to_string()),
+            ("cache-secs".to_string(), "30".to_string()),
+            (
+                "audio-swresample-o".to_string(),
+                "resampler=soxr".to_string(),
+            ),
+            ("no-audio-exclusive".to_string(), String::new()),
+        ]
+        .into(),
+        load_config: true,
+    };
+    player.set_mpv_options(options).unwrap();
+    assert_eq!(player.init(Some(fake.executable())), Ok(true));
+
+    let args = fake.launch_args();
+    assert!(!args.contains(&"--no-config".to_string()));
+    for arg in [
+        "--ao=pipewire",
+        "--audio-swresample-o=resampler=soxr",
+        "--no-audio-exclusive",
+    ] {
+        assert!(args.contains(&arg.to_string()), "{}", arg);
+    }
+    let position = |arg: &str| args.iter().position(|a| a == arg).unwrap();
+    assert!(position("--cache-secs=30") > position("--cache-secs=10"));
+}
+
+#[test]
+fn repeat_mode_and_ab_loop_are_handed_to_mpv() {
+    let fake = FakeMpv::start();
+    let (mut player, _) = start_player(&fake);
+    player.load(&urls(1)[0]).unwrap();
+
+    player.set_repeat(RepeatMode::One).unwrap();
+    assert_eq!(fake.property("loop-file"), Some(json!("inf")));
+    assert_eq!(fake.property("loop-playlist"), Some(json!("no")));
+    assert_eq!(player.get_status().repeat, RepeatMode::One);
+
+    player.set_repeat(RepeatMode::All).unwrap();
+    assert_eq!(fake.property("loop-file"), Some(json!("no")));
+    assert_eq!(fake.property("loop-playlist"), Some(json!("inf")));
+
+    fake.set_property("loop-playlist", json!(false));
+    assert!(wait_for(TIMEOUT, || player.get_status().repeat == RepeatMode::None));
+
+    player.set_ab_loop(Some(12.5), Some(30.0)).unwrap();
+    assert_eq!(fake.property("ab-loop-a"), Some(json!(12.5)));
+    assert_eq!(fake.property("ab-loop-b"), Some(json!(30.0)));
+    let status = player.get_status();
+    assert_eq!(
+        (status.ab_loop_a, status.ab_loop_b),
+        (Some(12.5), Some(30.0))
+    );
+
+    assert!(player.set_ab_loop(Some(30.0), Some(12.5)).is_err());
+    fake.set_property("ab-loop-b", json!("no"));
+    assert!(wait_for(TIMEOUT, || player
+        .get_status()
+        .ab_loop_b
+        .is_none()));
+    player.set_ab_loop(None, None).unwrap();
+    assert_eq!(fake.property("ab-loop-a"), Some(json!("no")));
+}
+
+#[test]
+fn volume_curve_boost_and_mute_are_handed_to_mpv() {
+    let fake = FakeMpv::start();
+    let (mut player, _) = start_player(&fake);
+    assert_eq!(fake.property("volume-max"), Some(json!(100.0)));
+
+    player.set_volume(0.5).unwrap();
+    assert_eq!(fake.property("volume"), Some(json!(50.0)));
+    assert!(player.set_volume(1.2).is_err());
+
+    player
+        .set_volume_settings(VolumeSettings {
+            curve: VolumeCurve::Logarithmic,
+            boost: true,
+            boost_max: 150.0,
+        })
+        .unwrap();
+    assert_eq!(fake.property("volume-max"), Some(json!(150.0)));
+    // Half way down the slider is 30 dB quieter
+    let volume = fake.property("volume").and_then(|v| v.as_f64()).unwrap();
+    assert!(
+        (volume - 100.0 * 10f64.powf(-0.5)).abs() < 1e-6,
+        "{}",
+        volume
+    );
+
+    player.set_volume(1.2).unwrap();
+    assert_eq!(fake.property("volume"), Some(json!(120.0)));
+    fake.set_property("volume", json!(100.0 * 10f64.powf(-0.5)));
+    assert!(wait_for(TIMEOUT, || {
+        (player.get_status().volume - 0.5).abs() < 1e-6
+    }));
+
+    player.set_mute(true).unwrap();
+    assert_eq!(fake.property("mute"), Some(json!(true)));
+    assert_eq!(
+        fake.property("volume").and_then(|v| v.as_f64()),
+        Some(volume)
+    );
+    fake.set_property("mute", json!(false));
+    assert!(wait_for(TIMEOUT, || !player.get_status().muted));
+}
+
+#[test]
+fn stream_info_reports_the_format_mpv_decodes() {
+    let fake = FakeMpv::start();
+    let (mut player, _) = start_player(&fake);
+    player.load(&urls(1)[0]).unwrap();
+
+    fake.set_property("file-format", json!("flac"));
+    fake.set_property("audio-codec-name", json!("flac"));
+    fake.set_property("audio-bitrate", json!(912345.6));
+    fake.set_property(
+        "audio-params",
+        json!({
+            "format": "s32",
+            "samplerate": 96000,
+            "channels": "stereo",
+            "hr-channels": "stereo",
+            "channel-count": 2
+        }),
+    );
+    fake.set_property("metadata", json!({ "ENCODER": "Lavf60.16.100" }));
+    assert!(wait_for(TIMEOUT, || {
+        player.get_status().stream.encoder.is_some()
+    }));
+    assert_eq!(
+        player.get_status().stream,
+        StreamInfo {
+            codec: Some("flac".to_string()),
+            container: Some("flac".to_string()),
+            bitrate: Some(912346),
+            sample_rate: Some(96000),
+            bit_depth: Some(24),
+            sample_format: Some("s32".to_string()),
+            channels: Some("stereo".to_string()),
+            encoder: Some("Lavf60.16.100".to_string()),
+            transcoded: false,
+        }
+    );
+
+    // An ffmpeg-tagged lossy file is only a transcode if the server was asked for one
+    fake.set_property("audio-codec-name", json!("mp3"));
+    fake.set_property(
+        "audio-params",
+        json!({ "format": "s16", "samplerate": 44100 }),
+    );
+    assert!(wait_for(TIMEOUT, || {
+        player.get_status().stream.bit_depth == Some(16)
+    }));
+    assert!(!player.get_status().stream.transcoded);
+
+    assert!(stream_info::requests_transcode(
+        "https://navidrome.local/rest/stream?id=1&format=opus"
+    ));
+    assert!(!stream_info::requests_transcode(
+        "https://navidrome.local/rest/stream?id=1&format=raw&maxBitRate=0"
+    ));
+}
+
+#[test]
+fn chapter_titles_follow_the_playing_chapter() {
+    let fake = FakeMpv::start();
+    let (mut player, _) = start_player(&fake);
+    player.load(&urls(1)[0]).unwrap();
+
+    fake.set_property(
+        "chapter-list",
+        json!([
+            { "title": "Intro", "time": 0.0 },
+            { "title": "Side B", "time": 1830.5 }
+        ]),
+    );
+    fake.set_property("chapter", json!(0));
+    assert!(wait_for(TIMEOUT, || {
+        player.get_status().chapter_title.as_deref() == Some("Intro")
+    }));
+    let chapters = player.chapters().unwrap();
+    assert_eq!(chapters[1].title.as_deref(), Some("Side B"));
+    assert_eq!(chapters[1].time, 1830.5);
+
+    player.chapter_next().unwrap();
+    assert_eq!(fake.property("chapter"), Some(json!(1)));
+    assert!(wait_for(TIMEOUT, || {
+        player.get_status().chapter_title.as_deref() == Some("Side B")
+    }));
+    player.chapter_prev().unwrap();
+    assert_eq!(fake.property("chapter"), Some(json!(0)));
+
+    player.set_chapter(1).unwrap();
+    assert_eq!(fake.property("chapter"), Some(json!(1)));
+    assert!(player.set_chapter(2).is_err());
+}
+
+#[test]
+fn buffering_reports_cached_ranges_and_counts_stalls() {
+    let fake = FakeMpv::start();
+    let (mut player, events) = start_player(&fake);
+    player.load(&urls(1)[0]).unwrap();
+
+    fake.set_property("demuxer-cache-duration", json!(12.5));
+    fake.set_property(
+        "demuxer-cache-state",
+        json!({
+            "cache-end": 42.0,
+            "seekable-ranges": [{ "start": 0.0, "end": 42.0 }]
+        }),
+    );
+    fake.set_property("paused-for-cache", json!(true));
+    fake.set_property("cache-buffering-state", json!(35));
+    assert!(wait_for(TIMEOUT, || {
+        player.get_status().cache.buffering_state == Some(35)
+    }));
+    let cache = player.get_status().cache;
+    assert!(cache.paused_for_cache);
+    assert_eq!(cache.duration, Some(12.5));
+    assert_eq!(
+        cache.ranges,
+        vec![CacheRange {
+            start: 0.0,
+            end: 42.0
+        }]
+    );
+
+    fake.set_property("paused-for-cache", json!(false));
+    fake.set_property("paused-for-cache", json!(true));
+    assert!(wait_for(TIMEOUT, || player.get_status().cache.stalls == 2));
+    let buffering: Vec<_> = events
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|event| match event {
+            PlayerEvent::Buffering(change) => Some((change.buffering, change.stalls)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(buffering, vec![(true, 1), (false, 1), (true, 2)]);
+}
+
+#[test]
+fn allowlisted_properties_can_be_read_set_and_observed() {
+    let fake = FakeMpv::start();
+    let (mut player, events) = start_player(&fake);
+
+    assert_eq!(
+        player.get_property("mpv-version").unwrap(),
+        json!("mpv 0.38.0-fake")
+    );
+    assert!(player.get_property("script-opts").is_err());
+    assert!(player.get_property("options/script-opts").is_err());
+    assert!(player.set_property("mpv-version", json!("x")).is_err());
+
+    player.set_property("audio-delay", json!(0.25)).unwrap();
+    assert_eq!(fake.property("audio-delay"), Some(json!(0.25)));
+
+    player.observe_property("audio-delay").unwrap();
+    fake.set_property("audio-delay", json!(0.5));
+    let changed = |value: serde_json::Value| {
+        events.lock().unwrap().iter().any(|event| {
+            matches!(event, PlayerEvent::PropertyChanged(change)
+                if change.name == "audio-delay" && change.value == value)
+        })
+    };
+    assert!(wait_for(TIMEOUT, || changed(json!(0.5))));
+
+    player.unobserve_property("audio-delay").unwrap();
+    fake.set_property("audio-delay", json!(0.75));
+    std::thread::sleep(Duration::from_millis(100));
+    assert!(!changed(json!(0.75)));
+
+    let denied = PropertyAllowlist {
+        readable: vec!["options/*".into()],
+        writable: vec![],
+    };
+    assert!(player.set_property_allowlist(denied).is_err());
+    let managed = PropertyAllowlist {
+        readable: vec![],
+        writable: vec!["volume".into()],
+    };
+    assert!(player.set_property_allowlist(managed).is_err());
+
+    player
+        .set_property_allowlist(PropertyAllowlist {
+            readable: vec!["metadata/*".into()],
+            writable: vec![],
+        })
+        .unwrap();
+    assert!(player.get_property("mpv-version").is_err());
+    assert!(player.set_property("audio-delay", json!(0.0)).is_err());
+}
+
+#[test]
+fn mpv_logs_are_captured_from_events_and_output() {
+    let fake = FakeMpv::start();
+    let executable = fake.write_script(
+        "noisy-mpv",
+        &format!(
+            "echo 'ALSA lib: unknown PCM' >&2\nexec \"{}\" \"$@\"",
+            fake.executable()
+        ),
+    );
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+    let mut player = MpvPlayer::new();
+    player.set_event_sink(Arc::new(move |event| sink.lock().unwrap().push(event)));
+    player.init(Some(executable)).unwrap();
+
+    let log_requests = || {
+        fake.commands()
+            .into_iter()
+            .filter(|c| c.first() == Some(&json!("request_log_messages")))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        log_requests(),
+        vec![vec![json!("request_log_messages"), json!("info")]]
+    );
+    assert!(wait_for(TIMEOUT, || {
+        player
+            .logs()
+            .iter()
+            .any(|entry| entry.prefix == "stderr" && entry.text == "ALSA lib: unknown PCM")
+    }));
+
+    fake.emit_event(json!({
+        "event": "log-message",
+        "prefix": "ao/pulse",
+        "level": "error",
+        "text": "Failed to connect to server\n",
+    }));
+    assert!(wait_for(TIMEOUT, || {
+        events.lock().unwrap().iter().any(|event| {
+            matches!(event, PlayerEvent::Log(entries) if entries.iter().any(|entry| {
+                entry.prefix == "ao/pulse" && entry.level == LogLevel::Error
+            }))
+        })
+    }));
+    let entry = player.logs().pop().unwrap();
+    assert_eq!(entry.text, "Failed to connect to server");
+
+    player.set_log_level(LogLevel::Debug).unwrap();
+    assert_eq!(
+        log_requests().last(),
+        Some(&vec![json!("request_log_messages"), json!("debug")])
+    );
+
+    events.lock().unwrap().clear();
+    for i in 0..LOG_CAPACITY {
+        fake.emit_event(json!({
+            "event": "log-message",
+            "prefix": "demux",
+            "level": "debug",
+            "text": format!("packet {}\n", i),
+        }));
+    }
+    let last = format!("packet {}", LOG_CAPACITY - 1);
+    assert!(wait_for(TIMEOUT, || {
+        player.logs().last().is_some_and(|entry| entry.text == last)
+    }));
+    let logs = player.logs();
+    assert_eq!(logs.len(), LOG_CAPACITY);
+    assert_eq!(logs[0].text, "packet 0");
+
+    // A burst reaches the webview in a handful of batches rather than line by line
+    let batches = || {
+        events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|event| match event {
+                PlayerEvent::Log(entries) => Some(entries.len()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+    assert!(wait_for(TIMEOUT, || {
+        batches().iter().sum::<usize>() == LOG_CAPACITY
+    }));
+    assert!(batches().len() < LOG_CAPACITY / 10, "{:?}", batches());
+}
+
+#[test]
+fn sleep_timer_fades_out_and_pauses() {
+    let fake = FakeMpv::start();
+    let (mut player, _) = start_player(&fake);
+    player.load(&urls(1)[0]).unwrap();
+
+    player
+        .set_sleep_timer(SleepTimerSettings {
+            mode: SleepTimerMode::Minutes { minutes: 0.02 },
+            fade_secs: 1.0,
+        })
+        .unwrap();
+    let timer = player.get_status().sleep_timer.unwrap();
+    assert_eq!(timer.remaining_secs, Some(2.0));
+
+    assert!(wait_for(TIMEOUT, || {
+        fake.property("volume")
+            .and_then(|volume| volume.as_f64())
+            .is_some_and(|volume| volume < 100.0)
+    }));
+    assert!(wait_for(TIMEOUT, || {
+        fake.property("pause") == Some(json!(true)) && fake.property("volume") == Some(json!(100.0))
+    }));
+    let status = player.get_status();
+    assert_eq!(status.sleep_timer, None);
+    assert_eq!(status.volume, 1.0);
+}
+
+#[test]
+fn sleep_timer_at_end_of_track_holds_the_file_open_until_cancelled() {
+    let fake = FakeMpv::start();
+    let (mut player, _) = start_player(&fake);
+    player.load_playlist_optimized(urls(2)).unwrap();
+
+    player
+        .set_sleep_timer(SleepTimerSettings {
+            mode: SleepTimerMode::EndOfTrack,
+            fade_secs: 0.0,
+        })
+        .unwrap();
+    assert!(wait_for(TIMEOUT, || {
+        fake.property("keep-open") == Some(json!("always"))
+    }));
+    assert_eq!(
+        player.get_status().sleep_timer.unwrap().remaining_tracks,
+        Some(1)
+    );
+
+    player.cancel_sleep_timer().unwrap();
+    assert_eq!(fake.property("keep-open"), Some(json!("yes")));
+    assert_eq!(player.get_status().sleep_timer, None);
+}
+
+#[test]
+fn speed_and_pitch_pick_the_time_stretching_filter() {
+    let fake = FakeMpv::start();
+    let (mut player, _) = start_player(&fake);
+
+    player.set_speed(1.5, PitchCorrection::Scaletempo2).unwrap();
+    assert_eq!(fake.property("speed"), Some(json!(1.5)));
+    assert_eq!(fake.property("audio-pitch-correction"), Some(json!(true)));
+    assert!(fake.audio_filters().is_empty());
+    assert!(wait_for(TIMEOUT, || player.get_status().speed == 1.5));
+
+    player.set_pitch(12.0).unwrap();
+    assert_eq!(
+        fake.audio_filters(),
+        vec!["@pitch:rubberband=pitch-scale=2".to_string()]
+    );
+    assert_eq!(player.get_status().pitch, 12.0);
+
+    player.set_speed(0.5, PitchCorrection::Off).unwrap();
+    assert_eq!(fake.property("audio-pitch-correction"), Some(json!(false)));
+    assert!(fake.audio_filters().is_empty());
+    assert_eq!(player.get_status().pitch, 12.0);
+    player.set_pitch(0.0).unwrap();
+    assert!(fake.audio_filters().is_empty());
+
+    assert!(player.set_speed(10.0, PitchCorrection::Rubberband).is_err());
+    player.set_speed(2.0, PitchCorrection::Rubberband).unwrap();
+    assert_eq!(
+        fake.audio_filters(),
+        vec!["@pitch:rubberband=pitch-scale=1".to_string()]
+    );
+    assert_eq!(fake.property("speed"), Some(json!(2.0)));
+}
+
+#[test]
+fn equalizer_bypass_updates_running_chain_in_place() {
+    let fake = FakeMpv::start();
+    let (mut player, events) = start_player(&fake);
+    let settings = EqualizerSettings {
+        enabled: true,
+        ..EqualizerSettings::graphic([3.0, 2.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, -2.0])
+    };
+
+    player.set_equalizer(settings.clone()).unwrap();
+    let filters = fake.audio_filters();
+    assert_eq!(filters.len(), 1);
+    assert!(
+        filters[0].starts_with("@eq:lavfi=[volume=0dB,equalizer=f=31:t=q:w=1.41:g=3,"),
+        "{}",
+        filters[0]
+    );
+
+    player.load_playlist_optimized(urls(2)).unwrap();
+    let sent = fake.commands().len();
+    player
+        .set_equalizer(EqualizerSettings {
+            enabled: false,
+            ..settings
+        })
+        .unwrap();
+    let names = fake.command_names();
+    assert_eq!(names.len() - sent, 11);
+    assert!(names[sent..].iter().all(|name| name == "af-command"));
+    assert_eq!(fake.audio_filters(), filters);
+    assert!(events.lock().unwrap().iter().any(
+        |event| matches!(event, PlayerEvent::EqualizerChanged(change) if !change.settings.enabled)
+    ));
+
+    // The next file is built from the af option, so it has to catch up with the bypass
+    player.playlist_next().unwrap();
+    assert!(wait_for(TIMEOUT, || {
+        fake.audio_filters()
+            .first()
+            .is_some_and(|filter| !filter.contains("g=3"))
+    }));
+}
+
+#[test]
+fn genre_preset_is_applied_for_loaded_track() {
+    let fake = FakeMpv::start();
+    let (mut player, events) = start_player(&fake);
+    let rock = EqualizerSettings {
+        enabled: true,
+        ..EqualizerSettings::graphic([4.0, 3.0, 2.0, 0.0, -1.0, -1.0, 1.0, 2.0, 3.0, 4.0])
+    };
+    player
+        .set_genre_equalizers(HashMap::from([("Rock".to_string(), rock.clone())]))
+        .unwrap();
+    assert!(fake.audio_filters().is_empty());
+
+    player.load_playlist_optimized(urls(1)).unwrap();
+    fake.set_property("metadata/by-key/genre", json!("Alternative; rock"));
+    assert!(wait_for(TIMEOUT, || player.equalizer() == rock));
+    assert!(wait_for(TIMEOUT, || {
+        fake.audio_filters()
+            .first()
+            .is_some_and(|filter| filter.contains("g=4"))
+    }));
+    assert!(wait_for(TIMEOUT, || {
+        events.lock().unwrap().iter().any(|event| match event {
+            PlayerEvent::EqualizerChanged(change) => change.genre.as_deref() == Some("rock"),
+            _ => false,
+        })
+    }));
+
+    fake.set_property("metadata/by-key/genre", json!("Jazz"));
+    assert!(wait_for(TIMEOUT, || {
+        player.equalizer() == EqualizerSettings::default()
+    }));
+}
+
+fn enable_crossfade(player: &mut MpvPlayer) {
+    player
+        .set_crossfade(CrossfadeSettings {
+            enabled: true,
+            duration: 2.0,
+            ..CrossfadeSettings::default()
+        })
+        .unwrap();
+}
+
+#[test]
+fn crossfade_hands_over_to_standby_instance() {
+    let fake = FakeMpv::start();
+    let (mut player, events) = start_player(&fake);
+    enable_crossfade(&mut player);
+
+    player.load_playlist_optimized(urls(3)).unwrap();
+    assert!(wait_for(TIMEOUT, || player.get_status().state == "playing"));
+    fake.set_property("time-pos", json!(175.0));
+
+    assert!(wait_for(TIMEOUT, || {
+        fake.instance(1)
+            .is_some_and(|standby| standby.playlist() == urls(3)[1..2])
+    }));
+    let standby = fake.instance(1).unwrap();
+    assert_eq!(standby.property("pause"), Some(json!(true)));
+    standby.set_property("demuxer-cache-idle", json!(true));
+    events.lock().unwrap().clear();
+    fake.set_property("time-pos", json!(178.5));
+
+    assert!(wait_for(TIMEOUT, || {
+        events
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|e| matches!(e, PlayerEvent::TrackChanged(change) if change.playlist_pos == 1))
+    }));
+    assert!(fake.playlist().is_empty());
+    assert_eq!(standby.playlist(), urls(3));
+    assert_eq!(standby.playlist_pos(), 1);
+    assert!(events.lock().unwrap().iter().any(|e| {
+        matches!(e, PlayerEvent::PlaylistChanged(change)
+            if change.entries == urls(3) && change.playlist_pos == 1)
+    }));
+    assert_eq!(standby.property("pause"), Some(json!(false)));
+    assert_eq!(standby.property("volume"), Some(json!(100.0)));
+    assert_eq!(player.get_status().playlist_pos, 1);
+
+    player.set_volume(0.5).unwrap();
+    assert_eq!(standby.property("volume"), Some(json!(50.0)));
+}
+
+#[test]
+fn crossfade_falls_back_to_gapless_when_next_track_is_not_buffered() {
+    let fake = FakeMpv::start();
+    let (mut player, _) = start_player(&fake);
+    enable_crossfade(&mut player);
+
+    player.load_playlist_optimized(urls(3)).unwrap();
+    assert!(wait_for(TIMEOUT, || player.get_status().state == "playing"));
+    fake.set_property("time-pos", json!(175.0));
+    assert!(wait_for(TIMEOUT, || {
+        fake.instance(1)
+            .is_some_and(|standby| standby.playlist() == urls(3)[1..2])
+    }));
+    fake.set_property("time-pos", json!(178.5));
+
+    let standby = fake.instance(1).unwrap();
+    assert!(wait_for(TIMEOUT, || standby.playlist().is_empty()));
+    assert_eq!(fake.playlist(), urls(3));
+    assert_ne!(fake.property("keep-open"), Some(json!("always")));
+
+    player.set_volume(0.5).unwrap();
+    assert_eq!(fake.property("volume"), Some(json!(50.0)));
+}
+
+#[test]
+fn standby_instance_quits_when_crossfade_is_disabled() {
+    let fake = FakeMpv::start();
+    let (mut player, _) = start_player(&fake);
+    enable_crossfade(&mut player);
+
+    player.load_playlist_optimized(urls(3)).unwrap();
+    assert!(wait_for(TIMEOUT, || player.get_status().state == "playing"));
+    fake.set_property("time-pos", json!(175.0));
+    assert!(wait_for(TIMEOUT, || {
+        fake.instance(1)
+            .is_some_and(|standby| standby.playlist() == urls(3)[1..2])
+    }));
+
+    player.set_crossfade(CrossfadeSettings::default()).unwrap();
+    let standby = fake.instance(1).unwrap();
+    assert!(wait_for(TIMEOUT, || standby.quit_received()));
+    assert!(!fake.quit_received());
+}
+
+#[test]
+fn mpv_errors_are_returned_to_caller() {
+    let fake = FakeMpv::start();
+    let (mut player, _) = start_player(&fake);
+
+    let error = player.seek(10.0).unwrap_err();
+    assert!(error.contains("error running command"), "{}", error);
+
+    player.load(&urls(1)[0]).unwrap();
+    fake.fail_next("seek", "property unavailable");
+    let error = player.seek(10.0).unwrap_err();
+    assert!(error.contains("property unavailable"), "{}", error);
+
+    player.seek(10.0).unwrap();
+}
+
+#[test]
+fn shutdown_quits_mpv_and_resets_status() {
+    let fake = FakeMpv::start();
+    let (mut player, _) = start_player(&fake);
+    let socket_path = fake.socket_path().unwrap();
+
+    player.shutdown();
+
+    assert!(fake.quit_received());
+    assert!(!socket_path.exists());
+    assert!(!player.get_status().initialized);
+}
+
+#[test]
+fn crashed_mpv_is_restarted_with_restored_state() {
+    let fake = FakeMpv::start();
+    let (mut player, events) = start_player(&fake);
+
+    player.load_playlist_optimized(urls(3)).unwrap();
+    player.playlist_jump_to_index(1).unwrap();
+    player.pause().unwrap();
+    player.set_repeat(RepeatMode::One).unwrap();
+    player.set_ab_loop(Some(20.0), Some(40.0)).unwrap();
+    fake.set_property("time-pos", json!(30.0));
+    assert!(wait_for(TIMEOUT, || player.get_status().position == 30.0));
+
+    fake.crash();
+
+    assert!(wait_for(TIMEOUT, || {
+        events
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|e| matches!(e, PlayerEvent::BackendCrashed(crash) if crash.will_restart))
+    }));
+    assert!(wait_for(Duration::from_secs(10), || {
+        fake.playlist() == urls(3)
+            && fake.playlist_pos() == 1
+            && fake.property("time-pos") == Some(json!(30.0))
+            && fake.property("pause") == Some(json!(true))
+            && fake.property("loop-file") == Some(json!("inf"))
+            && fake.property("ab-loop-a") == Some(json!(20.0))
+            && fake.property("ab-loop-b") == Some(json!(40.0))
+    }));
+    assert!(wait_for(TIMEOUT, || {
+        let status = player.get_status();
+        status.initialized && status.state == "paused"
+    }));
+    assert!(events.lock().unwrap().iter().any(|e| {
+        matches!(e, PlayerEvent::PlaylistChanged(change)
+            if change.entries == urls(3) && change.playlist_pos == 1)
+    }));
+    assert!(
+        player
+            .logs()
+            .iter()
+            .any(|entry| entry.prefix == "player"
+                && entry.text.starts_with("MPV exited unexpectedly"))
+    );
+}
+
+#[test]
+fn shutdown_does_not_wait_for_a_restart_in_progress() {
+    let fake = FakeMpv::start();
+    let (mut player, events) = start_player(&fake);
+
+    fake.crash();
+    assert!(wait_for(TIMEOUT, || {
+        events
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|e| matches!(e, PlayerEvent::BackendCrashed(_)))
+    }));
+
+    let started = Instant::now();
+    player.shutdown();
+    assert!(started.elapsed() < Duration::from_millis(400));
+
+    std::thread::sleep(Duration::from_secs(2));
+    assert!(!player.get_status().initialized);
+}
@@ -0,0 +1,89 @@
+use navithingy_lib::mpv::playlist_sync::{diff, PlaylistEdit};
+
+fn list(names: &str) -> Vec<String> {
+    names.chars().map(|c| c.to_string()).collect()
+}
+
+// Replays an edit script with mpv's playlist semantics
+fn apply(
+    current: &[String],
+    current_pos: Option<usize>,
+    edits: &[PlaylistEdit],
+) -> (Vec<String>, Option<usize>) {
+    let mut playlist: Vec<(usize, String)> = current.iter().cloned().enumerate().collect();
+    let mut playing = current_pos;
+
+    for (i, edit) in edits.iter().enumerate() {
+        match edit {
+            PlaylistEdit::Remove(index) => {
+                let (id, _) = playlist.remove(*index);
+                if Some(id) == playing {
+                    assert_eq!(i, edits.len() - 1, "removed the playing entry: {:?}", edits);
+                    playing = None;
+                }
+            }
+            PlaylistEdit::Move { from, before } => {
+                let entry = playlist.remove(*from);
+                let index = if from < before { before - 1 } else { *before };
+                playlist.insert(index, entry);
+            }
+            PlaylistEdit::Insert { url, index } => {
+                playlist.insert(*index, (usize::MAX - playlist.len(), url.clone()));
+            }
+            PlaylistEdit::Select(index) => playing = Some(playlist[*index].0),
+        }
+    }
+
+    let pos = playlist.iter().position(|(id, _)| Some(*id) == playing);
+    (playlist.into_iter().map(|(_, url)| url).collect(), pos)
+}
+
+#[test]
+fn diff_produces_desired_playlist() {
+    let cases = [
+        ("ABCD", Some(1), "ABCD", 1),
+        ("ABCD", Some(1), "DBCA", 1),
+        ("ABCD", Some(0), "", 0),
+        ("", None, "XYZ", 2),
+        ("ABCD", Some(2), "XCY", 1),
+        ("ABCD", Some(2), "BDA", 0),
+        ("AABB", Some(1), "BABA", 1),
+        ("ABCDEFG", Some(3), "GFEDCBA", 3),
+        ("ABC", None, "CAB", 0),
+    ];
+
+    for (current, pos, desired, desired_pos) in cases {
+        let (current, desired) = (list(current), list(desired));
+        let edits = diff(&current, pos, &desired, desired_pos);
+        let (result, playing) = apply(&current, pos, &edits);
+
+        assert_eq!(result, desired, "{:?}", edits);
+        if !desired.is_empty() {
+            assert_eq!(playing, Some(desired_pos), "{:?}", edits);
+        }
+    }
+}
+
+#[test]
+fn diff_keeps_playing_entry_untouched() {
+    let current = list("ABCD");
+    let edits = diff(&current, Some(2), &list("CXA"), 0);
+
+    assert!(!edits.iter().any(|e| matches!(e, PlaylistEdit::Select(_))));
+    assert!(!edits.contains(&PlaylistEdit::Remove(2)));
+}
+
+#[test]
+fn diff_moves_only_displaced_entries() {
+    let edits = diff(&list("DABC"), Some(1), &list("ABCD"), 0);
+    assert_eq!(edits, vec![PlaylistEdit::Move { from: 0, before: 4 }]);
+
+    let edits = diff(&list("ABCD"), Some(0), &list("ABCDE"), 0);
+    assert_eq!(
+        edits,
+        vec![PlaylistEdit::Insert {
+            url: "E".to_string(),
+            index: 4
+        }]
+    );
+}
@@ -0,0 +1,800 @@
+#![cfg(unix)]
+#![allow(dead_code)]
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::ops::Deref;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+static SERIAL: Mutex<()> = Mutex::new(());
+
+type ObservedValues = Vec<(i64, String, Value)>;
+
+pub fn wait_for(timeout: Duration, mut condition: impl FnMut() -> bool) -> bool {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if condition() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    condition()
+}
+
+struct Client {
+    stream: UnixStream,
+    observed: Vec<(i64, String)>,
+    last_sent: HashMap<i64, Value>,
+}
+
+struct Entry {
+    id: i64,
+    filename: String,
+}
+
+#[derive(Default)]
+struct FakeState {
+    properties: HashMap<String, Value>,
+    playlist: Vec<Entry>,
+    playlist_pos: i64,
+    next_entry_id: i64,
+    commands: Vec<Vec<Value>>,
+    audio_filters: Vec<String>,
+    failures: HashMap<String, String>,
+    clients: HashMap<usize, Client>,
+    next_client_id: usize,
+    socket_path: Option<PathBuf>,
+    quit: bool,
+}
+
+impl FakeState {
+    fn new() -> Self {
+        let mut properties = HashMap::new();
+        properties.insert("mpv-version".into(), json!("mpv 0.38.0-fake"));
+        properties.insert("pause".into(), json!(false));
+        properties.insert("volume".into(), json!(100.0));
+        properties.insert("eof-reached".into(), json!(false));
+        properties.insert("audio-device".into(), json!("auto"));
+        properties.insert(
+            "audio-device-list".into(),
+            json!([
+                { "name": "auto", "description": "Autoselect device" },
+                { "name": "pulse/usb-dac", "description": "USB DAC" }
+            ]),
+        );
+
+        Self {
+            properties,
+            playlist_pos: -1,
+            next_entry_id: 1,
+            ..Default::default()
+        }
+    }
+
+    fn property(&self, name: &str) -> Option<Value> {
+        let current = usize::try_from(self.playlist_pos)
+            .ok()
+            .and_then(|pos| self.playlist.get(pos));
+
+        match name {
+            "playlist-pos" => Some(json!(self.playlist_pos)),
+            "playlist-count" => Some(json!(self.playlist.len())),
+            "playlist" => Some(Value::Array(
+                self.playlist
+                    .iter()
+                    .enumerate()
+                    .map(|(i, entry)| {
+                        let mut item = json!({ "filename": entry.filename, "id": entry.id });
+                        if i as i64 == self.playlist_pos {
+                            item["current"] = json!(true);
+                            item["playing"] = json!(true);
+                        }
+                        item
+                    })
+                    .collect(),
+            )),
+            "path" | "filename" => current.map(|entry| json!(entry.filename)),
+            "media-title" => self
+                .properties
+                .get(name)
+                .cloned()
+                .or_else(|| current.map(|entry| json!(entry.filename))),
+            "time-pos" | "playback-time" | "duration" if current.is_none() => None,
+            _ => self.properties.get(name).cloned(),
+        }
+    }
+
+    fn send_to(client: &mut Client, message: &Value) {
+        let _ = writeln!(client.stream, "{}", message);
+    }
+
+    fn broadcast(&mut self, message: Value) {
+        for client in self.clients.values_mut() {
+            Self::send_to(client, &message);
+        }
+    }
+
+    fn notify_observers(&mut self) {
+        let values: Vec<(usize, ObservedValues)> = self
+            .clients
+            .iter()
+            .map(|(client_id, client)| {
+                let values = client
+                    .observed
+                    .iter()
+                    .map(|(id, name)| {
+                        (
+                            *id,
+                            name.clone(),
+                            self.property(name).unwrap_or(Value::Null),
+                        )
+                    })
+                    .collect();
+                (*client_id, values)
+            })
+            .collect();
+
+        for (client_id, values) in values {
+            let client = self.clients.get_mut(&client_id).unwrap();
+            for (id, name, value) in values {
+                if client.last_sent.get(&id) == Some(&value) {
+                    continue;
+                }
+                client.last_sent.insert(id, value.clone());
+                let mut event = json!({ "event": "property-change", "id": id, "name": name });
+                if !value.is_null() {
+                    event["data"] = value;
+                }
+                Self::send_to(client, &event);
+            }
+        }
+    }
+
+    fn start_entry(&mut self, pos: i64) {
+        self.playlist_pos = pos;
+        match usize::try_from(pos).ok().and_then(|p| self.playlist.get(p)) {
+            Some(entry) => {
+                let id = entry.id;
+                self.properties.insert("time-pos".into(), json!(0.0));
+                self.properties.insert("playback-time".into(), json!(0.0));
+                self.properties.insert("duration".into(), json!(180.0));
+                self.properties.insert("eof-reached".into(), json!(false));
+                self.broadcast(json!({ "event": "start-file", "playlist_entry_id": id }));
+                self.broadcast(json!({ "event": "file-loaded" }));
+                self.broadcast(json!({ "event": "playback-restart" }));
+            }
+            None => {
+                self.playlist_pos = -1;
+                self.broadcast(json!({ "event": "idle" }));
+            }
+        }
+    }
+
+    fn push_entry(&mut self, filename: &str, at: Option<usize>) {
+        let entry = Entry {
+            id: self.next_entry_id,
+            filename: filename.to_string(),
+        };
+        self.next_entry_id += 1;
+
+        match at {
+            Some(index) if index < self.playlist.len() => {
+                self.playlist.insert(index, entry);
+                if self.playlist_pos >= index as i64 {
+                    self.playlist_pos += 1;
+                }
+            }
+            _ => self.playlist.push(entry),
+        }
+    }
+
+    fn index_arg(&self, value: Option<&Value>) -> Option<usize> {
+        match value {
+            Some(Value::String(s)) if s == "current" => usize::try_from(self.playlist_pos).ok(),
+            Some(Value::String(s)) => s.parse().ok(),
+            Some(v) => v.as_u64().map(|i| i as usize),
+            None => None,
+        }
+    }
+
+    fn execute(&mut self, client_id: usize, command: &[Value]) -> Result<Option<Value>, String> {
+        let name = command.first().and_then(|v| v.as_str()).unwrap_or_default();
+        if let Some(error) = self.failures.remove(name) {
+            return Err(error);
+        }
+
+        match name {
+            "enable_event" | "disable_event" | "request_log_messages" => Ok(None),
+            "observe_property" => {
+                let id = command
+                    .get(1)
+                    .and_then(|v| v.as_i64())
+                    .ok_or("invalid parameter")?;
+                let property = command
+                    .get(2)
+                    .and_then(|v| v.as_str())
+                    .ok_or("invalid parameter")?;
+                if let Some(client) = self.clients.get_mut(&client_id) {
+                    client.observed.push((id, property.to_string()));
+                }
+                Ok(None)
+            }
+            "unobserve_property" => {
+                let id = command
+                    .get(1)
+                    .and_then(|v| v.as_i64())
+                    .ok_or("invalid parameter")?;
+                if let Some(client) = self.clients.get_mut(&client_id) {
+                    client.observed.retain(|(i, _)| *i != id);
+                    client.last_sent.remove(&id);
+                }
+                Ok(None)
+            }
+            "get_property" => {
+                let property = command
+                    .get(1)
+                    .and_then(|v| v.as_str())
+                    .ok_or("invalid parameter")?;
+                self.property(property)
+                    .map(Some)
+                    .ok_or_else(|| "property unavailable".to_string())
+            }
+            "set_property" => {
+                let property = command
+                    .get(1)
+                    .and_then(|v| v.as_str())
+                    .ok_or("invalid parameter")?;
+                let value = command.get(2).cloned().ok_or("invalid parameter")?;
+                match property {
+                    "playlist-pos" => {
+                        let pos = value.as_i64().ok_or("invalid parameter")?;
+                        if pos >= self.playlist.len() as i64 {
+                            return Err("error running command".into());
+                        }
+                        self.start_entry(pos);
+                    }
+                    "time-pos" => {
+                        self.properties.insert("time-pos".into(), value.clone());
+                        self.properties.insert("playback-time".into(), value);
+                        self.broadcast(json!({ "event": "seek" }));
+                        self.broadcast(json!({ "event": "playback-restart" }));
+                    }
+                    _ => {
+                        self.properties.insert(property.to_string(), value);
+                    }
+                }
+                Ok(None)
+            }
+            "add" => {
+                let property = command
+                    .get(1)
+                    .and_then(|v| v.as_str())
+                    .ok_or("invalid parameter")?;
+                let delta = command.get(2).cloned().unwrap_or(json!(1));
+                let current = self
+                    .property(property)
+                    .ok_or_else(|| "property unavailable".to_string())?;
+                let value = match (current.as_i64(), delta.as_i64()) {
+                    (Some(current), Some(delta)) => json!(current + delta),
+                    _ => {
+                        json!(current.as_f64().unwrap_or_default() + delta.as_f64().unwrap_or(1.0))
+                    }
+                };
+                self.properties.insert(property.to_string(), value);
+                Ok(None)
+            }
+            "loadfile" => {
+                let url = command
+                    .get(1)
+                    .and_then(|v| v.as_str())
+                    .ok_or("invalid parameter")?;
+                let mode = command.get(2).and_then(|v| v.as_str()).unwrap_or("replace");
+                match mode {
+                    "replace" => {
+                        self.playlist.clear();
+                        self.push_entry(url, None);
+                        self.start_entry(0);
+                    }
+                    "append" => self.push_entry(url, None),
+                    "append-play" => {
+                        self.push_entry(url, None);
+                        if self.playlist_pos < 0 {
+                            self.start_entry(self.playlist.len() as i64 - 1);
+                        }
+                    }
+                    "insert-at" | "insert-at-play" => {
+                        let index = self.index_arg(command.get(3)).ok_or("invalid parameter")?;
+                        self.push_entry(url, Some(index));
+                        if mode == "insert-at-play" {
+                            self.start_entry(index.min(self.playlist.len() - 1) as i64);
+                        }
+                    }
+                    "insert-next" => {
+                        let index = (self.playlist_pos + 1).max(0) as usize;
+                        self.push_entry(url, Some(index));
+                    }
+                    _ => return Err("invalid parameter".into()),
+                }
+                Ok(None)
+            }
+            "playlist-clear" => {
+                let current = usize::try_from(self.playlist_pos)
+                    .ok()
+                    .filter(|p| *p < self.playlist.len())
+                    .map(|p| self.playlist.remove(p));
+                self.playlist.clear();
+                if let Some(entry) = current {
+                    self.playlist.push(entry);
+                    self.playlist_pos = 0;
+                }
+                Ok(None)
+            }
+            "playlist-remove" => {
+                let index = self.index_arg(command.get(1)).ok_or("invalid parameter")?;
+                if index >= self.playlist.len() {
+                    return Err("error running command".into());
+                }
+                self.playlist.remove(index);
+                let pos = self.playlist_pos;
+                if index as i64 == pos {
+                    self.start_entry(if (pos as usize) < self.playlist.len() {
+                        pos
+                    } else {
+                        -1
+                    });
+                } else if (index as i64) < pos {
+                    self.playlist_pos -= 1;
+                }
+                Ok(None)
+            }
+            "playlist-move" => {
+                let from = self.index_arg(command.get(1)).ok_or("invalid parameter")?;
+                let to = self.index_arg(command.get(2)).ok_or("invalid parameter")?;
+                if from >= self.playlist.len() || to > self.playlist.len() {
+                    return Err("error running command".into());
+                }
+                if from == to || from + 1 == to {
+                    return Ok(None);
+                }
+                let current_id = usize::try_from(self.playlist_pos)
+                    .ok()
+                    .and_then(|p| self.playlist.get(p))
+                    .map(|e| e.id);
+                let entry = self.playlist.remove(from);
+                let target = if to > from { to - 1 } else { to };
+                self.playlist.insert(target, entry);
+                if let Some(id) = current_id {
+                    self.playlist_pos =
+                        self.playlist.iter().position(|e| e.id == id).unwrap() as i64;
+                }
+                Ok(None)
+            }
+            "playlist-next" | "playlist-prev" => {
+                let next = self.playlist_pos + if name == "playlist-next" { 1 } else { -1 };
+                if next < 0 || next >= self.playlist.len() as i64 {
+                    return Err("error running command".into());
+                }
+                self.broadcast(json!({ "event": "end-file", "reason": "stop" }));
+                self.start_entry(next);
+                Ok(None)
+            }
+            "playlist-play-index" => {
+                let index = self.index_arg(command.get(1)).ok_or("invalid parameter")?;
+                if index >= self.playlist.len() {
+                    return Err("error running command".into());
+                }
+                self.start_entry(index as i64);
+                Ok(None)
+            }
+            "seek" => {
+                if self.playlist_pos < 0 {
+                    return Err("error running command".into());
+                }
+                let target = command
+                    .get(1)
+                    .and_then(|v| v.as_f64())
+                    .ok_or("invalid parameter")?;
+                self.properties.insert("time-pos".into(), json!(target));
+                self.properties
+                    .insert("playback-time".into(), json!(target));
+                self.broadcast(json!({ "event": "seek" }));
+                self.broadcast(json!({ "event": "playback-restart" }));
+                Ok(None)
+            }
+            "stop" => {
+                if self.playlist_pos >= 0 {
+                    self.broadcast(json!({ "event": "end-file", "reason": "stop" }));
+                }
+                self.playlist.clear();
+                self.playlist_pos = -1;
+                self.broadcast(json!({ "event": "idle" }));
+                Ok(None)
+            }
+            "af" => {
+                let operation = command.get(1).and_then(|v| v.as_str());
+                let value = command
+                    .get(2)
+                    .and_then(|v| v.as_str())
+                    .ok_or("invalid parameter")?;
+                let label = |filter: &str| filter.split(':').next().unwrap_or_default().to_string();
+                match operation {
+                    Some("add") => {
+                        self.audio_filters.retain(|f| label(f) != label(value));
+                        self.audio_filters.push(value.to_string());
+                    }
+                    Some("clr") => self.audio_filters.clear(),
+                    Some("remove") => {
+                        let count = self.audio_filters.len();
+                        self.audio_filters.retain(|f| label(f) != value);
+                        if self.audio_filters.len() == count {
+                            return Err("error running command".into());
+                        }
+                    }
+                    _ => return Err("invalid parameter".into()),
+                }
+                Ok(None)
+            }
+            "af-command" => {
+                // Only a playing file has a filter chain to send commands to
+                let label = command.get(1).and_then(|v| v.as_str()).unwrap_or_default();
+                let installed = self
+                    .audio_filters
+                    .iter()
+                    .any(|f| f.starts_with(&format!("@{}:", label)));
+                if self.playlist_pos < 0 || !installed {
+                    return Err("error running command".into());
+                }
+                Ok(None)
+            }
+            "quit" => {
+                self.quit = true;
+                Ok(None)
+            }
+            _ => Err("invalid parameter".into()),
+        }
+    }
+}
+
+pub struct FakeMpv {
+    dir: PathBuf,
+    executable: PathBuf,
+    primary: FakeInstance,
+    instances: Arc<Mutex<Vec<FakeInstance>>>,
+    _serial: MutexGuard<'static, ()>,
+}
+
+// One mpv process as seen over its IPC socket, FakeMpv derefs to the first one launched
+#[derive(Clone)]
+pub struct FakeInstance {
+    state: Arc<Mutex<FakeState>>,
+}
+
+impl Deref for FakeMpv {
+    type Target = FakeInstance;
+
+    fn deref(&self) -> &FakeInstance {
+        &self.primary
+    }
+}
+
+impl FakeMpv {
+    pub fn start() -> Self {
+        let serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+
+        let dir = std::env::temp_dir().join(format!("navithingy-fake-mpv-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let args_file = dir.join("args");
+        let executable = dir.join("mpv");
+        fs::write(
+            &executable,
+            format!(
+                "#!/bin/sh\necho $$ > \"{1}\"\nprintf '%s\\n' \"$@\" > \"{0}.tmp\"\ncp \"{0}.tmp\" \"{2}.tmp.$$\"\nmv \"{2}.tmp.$$\" \"{2}-$$\"\nmv \"{0}.tmp\" \"{0}\"\nexec sleep 60\n",
+                args_file.display(),
+                dir.join("pid").display(),
+                dir.join("launch").display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&executable, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let primary = FakeInstance {
+            state: Arc::new(Mutex::new(FakeState::new())),
+        };
+        let instances = Arc::new(Mutex::new(vec![primary.clone()]));
+        let server_dir = dir.clone();
+        let server_instances = Arc::clone(&instances);
+        thread::spawn(move || Self::serve(server_dir, server_instances));
+
+        Self {
+            dir,
+            executable,
+            primary,
+            instances,
+            _serial: serial,
+        }
+    }
+
+    pub fn executable(&self) -> String {
+        self.executable.to_string_lossy().to_string()
+    }
+
+    pub fn write_script(&self, name: &str, body: &str) -> String {
+        let path = self.dir.join(name);
+        fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    pub fn launch_args(&self) -> Vec<String> {
+        fs::read_to_string(self.dir.join("args"))
+            .map(|args| args.lines().map(String::from).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn instance(&self, index: usize) -> Option<FakeInstance> {
+        self.instances.lock().unwrap().get(index).cloned()
+    }
+
+    pub fn crash(&self) {
+        if let Ok(pid) = fs::read_to_string(self.dir.join("pid")) {
+            let _ = std::process::Command::new("kill")
+                .args(["-9", pid.trim()])
+                .status();
+        }
+        self.disconnect_clients();
+
+        let mut state = self.primary.state.lock().unwrap();
+        let fresh = FakeState::new();
+        state.properties = fresh.properties;
+        state.playlist.clear();
+        state.playlist_pos = -1;
+    }
+
+    // Every launch gets its own socket, a relaunch on a known socket reuses that instance
+    fn serve(dir: PathBuf, instances: Arc<Mutex<Vec<FakeInstance>>>) {
+        let mut served: Vec<PathBuf> = Vec::new();
+        while dir.exists() {
+            let launches = fs::read_dir(&dir)
+                .map(|entries| entries.flatten().map(|e| e.path()).collect::<Vec<_>>())
+                .unwrap_or_default();
+            for launch in launches {
+                let is_launch = launch
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("launch-"));
+                let socket_path = fs::read_to_string(&launch).ok().and_then(|args| {
+                    args.lines()
+                        .find_map(|arg| arg.strip_prefix("--input-ipc-server="))
+                        .map(PathBuf::from)
+                });
+                let Some(socket_path) = socket_path.filter(|_| is_launch) else {
+                    continue;
+                };
+                if served.contains(&socket_path) {
+                    continue;
+                }
+
+                let instance = {
+                    let mut instances = instances.lock().unwrap();
+                    if served.is_empty() {
+                        instances[0].clone()
+                    } else {
+                        let instance = FakeInstance {
+                            state: Arc::new(Mutex::new(FakeState::new())),
+                        };
+                        instances.push(instance.clone());
+                        instance
+                    }
+                };
+                served.push(socket_path.clone());
+
+                let listener = UnixListener::bind(&socket_path).unwrap();
+                instance.state.lock().unwrap().socket_path = Some(socket_path);
+                thread::spawn(move || Self::accept(listener, instance.state));
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn accept(listener: UnixListener, state: Arc<Mutex<FakeState>>) {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { break };
+            let writer = stream.try_clone().unwrap();
+
+            let client_id = {
+                let mut state = state.lock().unwrap();
+                if state.quit {
+                    break;
+                }
+                let client_id = state.next_client_id;
+                state.next_client_id += 1;
+                state.clients.insert(
+                    client_id,
+                    Client {
+                        stream: writer,
+                        observed: Vec::new(),
+                        last_sent: HashMap::new(),
+                    },
+                );
+                client_id
+            };
+
+            let client_state = Arc::clone(&state);
+            thread::spawn(move || Self::serve_client(stream, client_id, client_state));
+        }
+    }
+
+    fn serve_client(stream: UnixStream, client_id: usize, state: Arc<Mutex<FakeState>>) {
+        let reader = BufReader::new(stream);
+        for line in reader.lines() {
+            let Ok(line) = line else { break };
+            let Ok(request) = serde_json::from_str::<Value>(&line) else {
+                continue;
+            };
+
+            let command = request["command"].as_array().cloned().unwrap_or_default();
+            let request_id = request.get("request_id").cloned().unwrap_or(json!(0));
+
+            let mut state = state.lock().unwrap();
+            if !state.clients.contains_key(&client_id) {
+                break;
+            }
+            state.commands.push(command.clone());
+
+            let response = match state.execute(client_id, &command) {
+                Ok(data) => {
+                    let mut response = json!({ "request_id": request_id, "error": "success" });
+                    if let Some(data) = data {
+                        response["data"] = data;
+                    }
+                    response
+                }
+                Err(error) => json!({ "request_id": request_id, "error": error }),
+            };
+            if let Some(client) = state.clients.get_mut(&client_id) {
+                FakeState::send_to(client, &response);
+            }
+            state.notify_observers();
+
+            if state.quit {
+                for (_, client) in state.clients.drain() {
+                    let _ = client.stream.shutdown(std::net::Shutdown::Both);
+                }
+                break;
+            }
+        }
+    }
+}
+
+impl FakeInstance {
+    pub fn socket_path(&self) -> Option<PathBuf> {
+        self.state.lock().unwrap().socket_path.clone()
+    }
+
+    pub fn commands(&self) -> Vec<Vec<Value>> {
+        self.state.lock().unwrap().commands.clone()
+    }
+
+    pub fn command_names(&self) -> Vec<String> {
+        self.commands()
+            .iter()
+            .filter_map(|c| c.first().and_then(|v| v.as_str()).map(String::from))
+            .collect()
+    }
+
+    pub fn observed_properties(&self) -> Vec<String> {
+        self.commands()
+            .iter()
+            .filter(|c| c.first().and_then(|v| v.as_str()) == Some("observe_property"))
+            .filter_map(|c| c.get(2).and_then(|v| v.as_str()).map(String::from))
+            .collect()
+    }
+
+    pub fn playlist(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        state.playlist.iter().map(|e| e.filename.clone()).collect()
+    }
+
+    pub fn audio_filters(&self) -> Vec<String> {
+        self.state.lock().unwrap().audio_filters.clone()
+    }
+
+    pub fn playlist_pos(&self) -> i64 {
+        self.state.lock().unwrap().playlist_pos
+    }
+
+    pub fn property(&self, name: &str) -> Option<Value> {
+        self.state.lock().unwrap().property(name)
+    }
+
+    pub fn set_property(&self, name: &str, value: Value) {
+        let mut state = self.state.lock().unwrap();
+        state.properties.insert(name.to_string(), value);
+        state.notify_observers();
+    }
+
+    pub fn set_property_silently(&self, name: &str, value: Value) {
+        let mut state = self.state.lock().unwrap();
+        state.properties.insert(name.to_string(), value.clone());
+        for client in state.clients.values_mut() {
+            let ids: Vec<i64> = client
+                .observed
+                .iter()
+                .filter(|(_, observed)| observed == name)
+                .map(|(id, _)| *id)
+                .collect();
+            for id in ids {
+                client.last_sent.insert(id, value.clone());
+            }
+        }
+    }
+
+    pub fn fail_next(&self, command: &str, error: &str) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .failures
+            .insert(command.to_string(), error.to_string());
+    }
+
+    pub fn emit_event(&self, event: Value) {
+        self.state.lock().unwrap().broadcast(event);
+    }
+
+    pub fn end_file(&self, reason: &str) {
+        let mut state = self.state.lock().unwrap();
+        let entry_id = usize::try_from(state.playlist_pos)
+            .ok()
+            .and_then(|p| state.playlist.get(p))
+            .map(|e| e.id);
+        state.broadcast(json!({
+            "event": "end-file",
+            "reason": reason,
+            "playlist_entry_id": entry_id,
+        }));
+
+        let next = state.playlist_pos + 1;
+        if reason == "eof" && next < state.playlist.len() as i64 {
+            state.start_entry(next);
+        } else {
+            state
+                .properties
+                .insert("eof-reached".into(), json!(reason == "eof"));
+            if reason != "eof" {
+                state.playlist_pos = -1;
+            }
+        }
+        state.notify_observers();
+    }
+
+    pub fn disconnect_clients(&self) {
+        let mut state = self.state.lock().unwrap();
+        for (_, client) in state.clients.drain() {
+            let _ = client.stream.shutdown(std::net::Shutdown::Both);
+        }
+    }
+
+    pub fn quit_received(&self) -> bool {
+        self.state.lock().unwrap().quit
+    }
+}
+
+impl Drop for FakeMpv {
+    fn drop(&mut self) {
+        for instance in self.instances.lock().unwrap().iter() {
+            instance.disconnect_clients();
+            if let Some(path) = instance.socket_path() {
+                let _ = fs::remove_file(path);
+            }
+        }
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
@@ -1,6 +1,7 @@
 import { updateMediaMetadata, updateMediaPlaybackState } from '$lib/mediaSession';
 import { NavidromeClient } from '$lib/navidrome';
 import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import type { Child } from '@vmohammad/subsonic-api';
 import toast from 'svelte-french-toast';
 import { writable } from 'svelte/store';
@@ -8,6 +9,156 @@ import { client as cl } from './client';
 
 export type RepeatMode = 'none' | 'one' | 'all';
 
+export type PitchCorrection = 'off' | 'scaletempo2' | 'rubberband';
+
+export type EqualizerSettings = {
+	enabled: boolean;
+	preamp: number;
+	bands: { frequency: number; gain: number; q?: number }[];
+};
+
+export type SleepTimerMode =
+	| { mode: 'minutes'; minutes: number }
+	| { mode: 'end_of_track' }
+	| { mode: 'tracks'; count: number };
+
+export type SleepTimerSettings = SleepTimerMode & { fade_secs: number };
+
+export type SleepTimerStatus = {
+	mode: SleepTimerMode;
+	remaining_secs: number | null;
+	remaining_tracks: number | null;
+	fading: boolean;
+};
+
+export type NormalizationMode = 'off' | 'loudnorm' | 'dynaudnorm';
+
+export type NormalizationSettings = {
+	mode: NormalizationMode;
+	target_lufs: number;
+};
+
+export type Chapter = {
+	title: string | null;
+	time: number;
+};
+
+export type CacheStatus = {
+	paused_for_cache: boolean;
+	buffering_state: number | null;
+	duration: number | null;
+	ranges: { start: number; end: number }[];
+	stalls: number;
+};
+
+export type StreamInfo = {
+	codec: string | null;
+	container: string | null;
+	bitrate: number | null;
+	sample_rate: number | null;
+	bit_depth: number | null;
+	sample_format: string | null;
+	channels: string | null;
+	encoder: string | null;
+	transcoded: boolean;
+};
+
+export type MpvOptions = {
+	options: Record<string, string>;
+	load_config: boolean;
+};
+
+export type PropertyChange = {
+	name: string;
+	value: unknown;
+};
+
+export type LogLevel =
+	| 'no'
+	| 'fatal'
+	| 'error'
+	| 'warn'
+	| 'info'
+	| 'status'
+	| 'v'
+	| 'debug'
+	| 'trace';
+
+export type LogEntry = {
+	timestamp: number;
+	level: LogLevel;
+	prefix: string;
+	text: string;
+};
+
+export type AudioDevice = {
+	name: string;
+	description: string;
+};
+
+export type EqualizerChange = {
+	settings: EqualizerSettings;
+	genre: string | null;
+};
+
+export type VolumeCurve = 'linear' | 'cubic' | 'logarithmic';
+
+export type VolumeSettings = {
+	curve: VolumeCurve;
+	boost: boolean;
+	boost_max: number;
+};
+
+export type FadeCurve = 'linear' | 'equalpower' | 'scurve';
+
+export type CrossfadeSettings = {
+	enabled: boolean;
+	duration: number;
+	curve: FadeCurve;
+	skip_same_album: boolean;
+};
+
+function savedNormalization(): NormalizationSettings | null {
+	try {
+		return JSON.parse(localStorage.getItem('mpvNormalization') || 'null');
+	} catch {
+		return null;
+	}
+}
+
+function savedMpvOptions(): MpvOptions {
+	const fallback = { options: {}, load_config: false };
+	try {
+		return JSON.parse(localStorage.getItem('mpvOptions') || 'null') ?? fallback;
+	} catch {
+		return fallback;
+	}
+}
+
+function savedVolumeSettings(): VolumeSettings | null {
+	try {
+		return JSON.parse(localStorage.getItem('mpvVolume') || 'null');
+	} catch {
+		return null;
+	}
+}
+
+function savedCrossfade(): CrossfadeSettings | null {
+	try {
+		return JSON.parse(localStorage.getItem('mpvCrossfade') || 'null');
+	} catch {
+		return null;
+	}
+}
+
+function savedEqualizer(): EqualizerSettings | null {
+	try {
+		return JSON.parse(localStorage.getItem('mpvEqualizer') || 'null');
+	} catch {
+		return null;
+	}
+}
+
 export const mpvSettings = writable({
 	enabled: localStorage.getItem('mpvEnabled') === 'true',
 	customPath: localStorage.getItem('mpvCustomPath') || '',
@@ -16,7 +167,17 @@ export const mpvSettings = writable({
 	preciseSeek: localStorage.getItem('mpvPreciseSeek') === 'true' || (true as boolean),
 	cacheSize: parseInt(localStorage.getItem('mpvCacheSize') || '10'),
 	keepOpen: true,
-	nativePlaylist: localStorage.getItem('mpvUseNativePlaylist') !== 'false'
+	nativePlaylist: localStorage.getItem('mpvUseNativePlaylist') !== 'false',
+	audioDevice: localStorage.getItem('mpvAudioDevice') || 'auto',
+	equalizer: savedEqualizer(),
+	speed: Number(localStorage.getItem('mpvSpeed') ?? '1'),
+	pitchCorrection: (localStorage.getItem('mpvPitchCorrection') || 'scaletempo2') as PitchCorrection,
+	pitch: Number(localStorage.getItem('mpvPitch') ?? '0'),
+	crossfade: savedCrossfade(),
+	normalization: savedNormalization(),
+	volume: savedVolumeSettings(),
+	options: savedMpvOptions(),
+	logLevel: (localStorage.getItem('mpvLogLevel') || 'info') as LogLevel
 });
 
 export const mpvStatus = writable({
@@ -24,6 +185,8 @@ export const mpvStatus = writable({
 	position: 0,
 	duration: 0,
 	volume: 1,
+	max_volume: 1,
+	muted: false,
 	state: 'idle',
 	playlist_pos: -1,
 	playlist_count: 0,
@@ -31,22 +194,87 @@ export const mpvStatus = writable({
 	playback_time: null as number | null,
 	pause: null as boolean | null,
 	chapter: null as number | null,
-	chapter_count: null as number | null
+	chapter_count: null as number | null,
+	chapter_title: null as string | null,
+	repeat: 'none' as RepeatMode,
+	ab_loop_a: null as number | null,
+	ab_loop_b: null as number | null,
+	sleep_timer: null as SleepTimerStatus | null,
+	stream: null as StreamInfo | null,
+	cache: null as CacheStatus | null
 });
 
+export const mpvAudioDevices = writable<AudioDevice[]>([]);
+
+// What mpv is running, a genre preset stands in for the saved settings while its track plays
+export const mpvEqualizer = writable<EqualizerChange | null>(null);
+
 async function initMpv() {
 	try {
 		const mpvConfig = get(mpvSettings);
 		if (!mpvConfig.enabled) return false;
 
 		const customPath = mpvConfig.customPath.trim() || undefined;
+		// Launch options only apply when mpv starts, so they go in before it does
+		const options: MpvOptions = {
+			...mpvConfig.options,
+			options: { 'cache-secs': mpvConfig.cacheSize.toString(), ...mpvConfig.options.options }
+		};
+		await invoke('mpv_set_options', { options }).catch((error) =>
+			console.warn('Saved mpv options were rejected:', error)
+		);
+		await invoke('mpv_set_log_level', { level: mpvConfig.logLevel }).catch((error) =>
+			console.warn('Failed to set MPV log level:', error)
+		);
 		const result = await invoke<boolean>('mpv_init', { customPath });
 
 		if (result) {
 			mpvSettings.update((s) => ({ ...s, initialized: true }));
 			console.log('MPV initialized successfully');
 
-			startMpvStatusPolling();
+			// Boost has to be on before a saved level above 100% is synced
+			if (mpvConfig.volume) {
+				await invoke('mpv_set_volume_settings', { settings: mpvConfig.volume }).catch((error) =>
+					console.warn('Saved volume settings were rejected:', error)
+				);
+			}
+
+			if (mpvConfig.audioDevice !== 'auto') {
+				invoke('mpv_set_audio_device', { name: mpvConfig.audioDevice }).catch((error) =>
+					console.warn(`Saved audio device ${mpvConfig.audioDevice} is unavailable:`, error)
+				);
+			}
+
+			if (mpvConfig.equalizer) {
+				invoke('mpv_set_equalizer', { settings: mpvConfig.equalizer }).catch((error) =>
+					console.warn('Saved equalizer settings were rejected:', error)
+				);
+			}
+
+			if (mpvConfig.speed !== 1 || mpvConfig.pitchCorrection !== 'scaletempo2') {
+				invoke('mpv_set_speed', {
+					speed: mpvConfig.speed,
+					pitchCorrection: mpvConfig.pitchCorrection
+				}).catch((error) => console.warn('Saved playback speed was rejected:', error));
+			}
+			if (mpvConfig.pitch !== 0) {
+				invoke('mpv_set_pitch', { semitones: mpvConfig.pitch }).catch((error) =>
+					console.warn('Saved pitch shift was rejected:', error)
+				);
+			}
+
+			if (mpvConfig.normalization) {
+				invoke('mpv_set_normalization', { settings: mpvConfig.normalization }).catch((error) =>
+					console.warn('Saved loudness normalization was rejected:', error)
+				);
+			}
+			if (mpvConfig.crossfade) {
+				invoke('mpv_set_crossfade', { settings: mpvConfig.crossfade }).catch((error) =>
+					console.warn('Saved crossfade settings were rejected:', error)
+				);
+			}
+
+			startMpvStatusEvents();
 			return true;
 		} else {
 			console.error('MPV initialization returned false');
@@ -61,89 +289,158 @@ async function initMpv() {
 	}
 }
 
-let mpvStatusInterval: NodeJS.Timeout | null = null;
-const mpvStatusThrottle = {
-	lastPoll: 0,
-	minInterval: 100,
-	isPolling: false,
-	activeInterval: 250,
-	idleInterval: 1000
+type MpvStatusPayload = {
+	initialized: boolean;
+	position: number;
+	duration: number;
+	volume: number;
+	max_volume?: number;
+	muted?: boolean;
+	state: string;
+	playlist_pos: number;
+	playlist_count: number;
+	media_title?: string | null;
+	playback_time?: number | null;
+	pause?: boolean | null;
+	chapter?: number | null;
+	chapter_count?: number | null;
+	chapter_title?: string | null;
+	current_url?: string | null;
+	audio_device?: string | null;
+	replaygain_gain?: number | null;
+	normalization?: NormalizationMode;
+	repeat?: RepeatMode;
+	ab_loop_a?: number | null;
+	ab_loop_b?: number | null;
+	sleep_timer?: SleepTimerStatus | null;
+	stream?: StreamInfo;
+	cache?: CacheStatus;
+	speed?: number;
+	pitch?: number;
 };
 
-function startMpvStatusPolling() {
-	if (mpvStatusInterval) clearInterval(mpvStatusInterval);
+type MpvTrackChange = {
+	playlist_pos: number;
+	playlist_count: number;
+};
 
-	const updatePoll = async () => {
-		const now = Date.now();
-		if (
-			mpvStatusThrottle.isPolling ||
-			now - mpvStatusThrottle.lastPoll < mpvStatusThrottle.minInterval
-		) {
-			return;
-		}
+type MpvPlaybackEnded = {
+	reason: string;
+	playlist_entry_id: number | null;
+};
 
-		try {
-			mpvStatusThrottle.isPolling = true;
-			mpvStatusThrottle.lastPoll = now;
+type MpvPlaylistChange = {
+	entries: string[];
+	playlist_pos: number;
+};
 
-			if (invoke) {
-				const status = await invoke<{
-					initialized: boolean;
-					position: number;
-					duration: number;
-					volume: number;
-					state: string;
-					playlist_pos: number;
-					playlist_count: number;
-					media_title?: string | null;
-					playback_time?: number | null;
-					pause?: boolean | null;
-					chapter?: number | null;
-					chapter_count?: number | null;
-				}>('mpv_get_status');
-
-				if (status.playlist_pos < 0 && status.playlist_count > 0) {
-					status.playlist_pos = 0;
-				}
+type MpvBackendCrash = {
+	exit_code: number | null;
+	restart_attempt: number;
+	will_restart: boolean;
+};
 
-				mpvStatus.set({
-					initialized: status.initialized,
-					position: status.position,
-					duration: status.duration,
-					volume: status.volume,
-					state: status.state,
-					playlist_pos: status.playlist_pos,
-					playlist_count: status.playlist_count,
-					media_title: null,
-					playback_time: null,
-					pause: null,
-					chapter: null,
-					chapter_count: null
-				});
+type MpvBuffering = {
+	buffering: boolean;
+	stalls: number;
+};
 
-				const isActive = status.state === 'playing';
-				mpvStatusThrottle.activeInterval = isActive ? 250 : 1000;
+let mpvEventUnlisteners: UnlistenFn[] = [];
+// Set by the audio player, the backend announces track changes and track ends itself
+const mpvEventHandlers: {
+	trackChanged?: (change: MpvTrackChange) => void;
+	ended?: (ended: MpvPlaybackEnded) => void;
+	playlistChanged?: (change: MpvPlaylistChange) => void;
+} = {};
+
+function applyMpvStatus(status: MpvStatusPayload) {
+	if (status.playlist_pos < 0 && status.playlist_count > 0) {
+		status.playlist_pos = 0;
+	}
+
+	mpvStatus.set({
+		initialized: status.initialized,
+		position: status.position,
+		duration: status.duration,
+		volume: status.volume,
+		max_volume: status.max_volume ?? 1,
+		muted: status.muted ?? false,
+		state: status.state,
+		playlist_pos: status.playlist_pos,
+		playlist_count: status.playlist_count,
+		media_title: status.media_title ?? null,
+		playback_time: status.playback_time ?? null,
+		pause: status.pause ?? null,
+		chapter: status.chapter ?? null,
+		chapter_count: status.chapter_count ?? null,
+		chapter_title: status.chapter_title ?? null,
+		repeat: status.repeat ?? 'none',
+		ab_loop_a: status.ab_loop_a ?? null,
+		ab_loop_b: status.ab_loop_b ?? null,
+		sleep_timer: status.sleep_timer ?? null,
+		stream: status.stream ?? null,
+		cache: status.cache ?? null
+	});
+}
 
-				if (mpvStatusInterval) {
-					clearInterval(mpvStatusInterval);
-					mpvStatusInterval = setInterval(updatePoll, mpvStatusThrottle.activeInterval);
+async function startMpvStatusEvents() {
+	if (mpvEventUnlisteners.length > 0) return;
+
+	try {
+		mpvEventUnlisteners = await Promise.all([
+			listen<MpvStatusPayload>('player://status', (event) => applyMpvStatus(event.payload)),
+			listen<{ message: string }>('player://error', (event) => {
+				console.error('MPV playback error:', event.payload.message);
+				toast.error(`Playback error: ${event.payload.message}`);
+			}),
+			listen<MpvTrackChange>('player://track-changed', (event) =>
+				mpvEventHandlers.trackChanged?.(event.payload)
+			),
+			listen<MpvPlaybackEnded>('player://ended', (event) =>
+				mpvEventHandlers.ended?.(event.payload)
+			),
+			listen<MpvPlaylistChange>('player://playlist-changed', (event) =>
+				mpvEventHandlers.playlistChanged?.(event.payload)
+			),
+			// Giving up is reported as a playback error
+			listen<MpvBackendCrash>('player://backend-crashed', (event) => {
+				console.error('MPV exited unexpectedly:', event.payload);
+				if (event.payload.will_restart) {
+					toast.error('MPV stopped unexpectedly, restarting it');
 				}
-			}
-		} catch (error: any) {
-			console.error('Failed to get MPV status:', error);
-		} finally {
-			mpvStatusThrottle.isPolling = false;
-		}
-	};
+			}),
+			listen<{ devices: AudioDevice[] }>('player://audio-devices-changed', (event) =>
+				mpvAudioDevices.set(event.payload.devices)
+			),
+			listen<EqualizerChange>('player://equalizer-changed', (event) =>
+				mpvEqualizer.set(event.payload)
+			),
+			listen<MpvBuffering>('player://buffering', (event) =>
+				mpvStatus.update((status) =>
+					status.cache
+						? {
+								...status,
+								cache: {
+									...status.cache,
+									paused_for_cache: event.payload.buffering,
+									stalls: event.payload.stalls
+								}
+							}
+						: status
+				)
+			)
+		]);
 
-	mpvStatusInterval = setInterval(updatePoll, mpvStatusThrottle.activeInterval);
+		applyMpvStatus(await invoke<MpvStatusPayload>('mpv_get_status'));
+		mpvAudioDevices.set(await invoke<AudioDevice[]>('mpv_get_audio_devices'));
+	} catch (error: any) {
+		console.error('Failed to subscribe to MPV status events:', error);
+	}
 }
 
-function stopMpvStatusPolling() {
-	if (mpvStatusInterval) {
-		clearInterval(mpvStatusInterval);
-		mpvStatusInterval = null;
-	}
+function stopMpvStatusEvents() {
+	mpvEventUnlisteners.forEach((unlisten) => unlisten());
+	mpvEventUnlisteners = [];
 }
 
 class AudioPlayer {
@@ -161,6 +458,8 @@ class AudioPlayer {
 	private mpvPlaylistLoaded = false;
 	private lastMpvPlaylistPos = -1;
 	private useNativeMpvPlaylist = true;
+	private streamUrlCache = new Map<string, string>();
+	private mpvQueueEdits: Promise<unknown> = Promise.resolve();
 	private timeUpdateHandler: (() => void) | null = null; // ADDED
 
 	public isMpvEnabled(): boolean {
@@ -174,7 +473,7 @@ class AudioPlayer {
 	constructor(initialState: PlayerState) {
 		this.audio = new Audio();
 		this.volume = Number(localStorage.getItem('volume') ?? '1');
-		this.audio.volume = this.volume;
+		this.audio.volume = Math.min(1, this.volume);
 		this.state = initialState;
 
 		this.useMpv = localStorage.getItem('mpvEnabled') === 'true';
@@ -187,12 +486,16 @@ class AudioPlayer {
 					if (success) {
 						this.syncMpvVolume();
 						if (this.state?.isPlaying) {
-							startMpvStatusPolling();
+							startMpvStatusEvents();
 						}
 					}
 				});
 			}
 
+			mpvEventHandlers.trackChanged = (change) => this.handleMpvTrackChange(change);
+			mpvEventHandlers.ended = () => this.handleMpvEnded();
+			mpvEventHandlers.playlistChanged = (change) => this.handleMpvPlaylistChanged(change);
+
 			const unsubscribe = mpvStatus.subscribe((status) => {
 				this.progress = status.position;
 				this.duration = status.duration;
@@ -203,47 +506,6 @@ class AudioPlayer {
 						this.state.isPlaying = shouldBePlaying;
 					}
 				}
-
-				if (
-					status.playlist_pos !== undefined &&
-					status.playlist_pos !== this.lastMpvPlaylistPos &&
-					status.playlist_pos >= 0 &&
-					this.mpvPlaylistLoaded &&
-					this.state?.playlist &&
-					this.state?.playlist.length > 0
-				) {
-					this.lastMpvPlaylistPos = status.playlist_pos;
-
-					if (this.state && this.state.currentIndex !== status.playlist_pos) {
-						console.log(`MPV changed track to playlist index: ${status.playlist_pos}`);
-
-						if (this.onPlaylistPositionChanged) {
-							this.onPlaylistPositionChanged(status.playlist_pos);
-						}
-					}
-				}
-
-				if (
-					(status.state === 'ended' ||
-						(status.duration > 0 && status.position >= status.duration - 0.5)) &&
-					this.state?.isPlaying
-				) {
-					if (!this.trackEndTriggered) {
-						this.trackEndTriggered = true;
-						if (
-							(this.state.currentIndex === this.state.playlist.length - 1 &&
-								this.state.repeat !== 'all') ||
-							this.state.repeat === 'one'
-						) {
-							if (this.onEndedCallback) {
-								console.log('MPV final track ended or repeat-one active, triggering callback');
-								this.onEndedCallback();
-							}
-						}
-					}
-				} else {
-					this.trackEndTriggered = false;
-				}
 			});
 		}
 
@@ -304,6 +566,44 @@ class AudioPlayer {
 		this.onPlaylistPositionChanged = callback;
 	}
 
+	private handleMpvTrackChange(change: MpvTrackChange) {
+		if (!this.mpvPlaylistLoaded || !this.state?.playlist.length) return;
+
+		this.lastMpvPlaylistPos = change.playlist_pos;
+		this.trackEndTriggered = false;
+		if (this.state.currentIndex !== change.playlist_pos) {
+			console.log(`MPV changed track to playlist index: ${change.playlist_pos}`);
+			this.onPlaylistPositionChanged?.(change.playlist_pos);
+		}
+	}
+
+	// Crash restores and crossfade handovers reload mpv's playlist, and a multi-track edit still
+	// in flight leaves it a different length than the queue
+	private handleMpvPlaylistChanged(change: MpvPlaylistChange) {
+		if (change.entries.length !== this.state?.playlist.length) return;
+		this.handleMpvTrackChange({
+			playlist_pos: change.playlist_pos,
+			playlist_count: change.entries.length
+		});
+	}
+
+	private handleMpvEnded() {
+		if (!this.state?.isPlaying || this.trackEndTriggered) return;
+		this.trackEndTriggered = true;
+
+		// mpv loops on its own once it knows the repeat mode, without a gap
+		const mpvRepeats = get(mpvStatus).repeat !== 'none';
+		if (
+			!mpvRepeats &&
+			((this.state.currentIndex === this.state.playlist.length - 1 &&
+				this.state.repeat !== 'all') ||
+				this.state.repeat === 'one')
+		) {
+			console.log('MPV final track ended or repeat-one active, triggering callback');
+			this.onEndedCallback?.();
+		}
+	}
+
 	private async preloadNext() {
 		if (!this.client || !this.state || this.useMpv) return;
 
@@ -326,7 +626,7 @@ class AudioPlayer {
 			const stream = await this.client.getSongStreamURL(track.id);
 			this.preloadedAudio = new Audio(stream);
 			this.preloadedAudio.preload = 'auto';
-			this.preloadedAudio.volume = this.volume;
+			this.preloadedAudio.volume = Math.min(1, this.volume);
 		} catch (error) {
 			console.error('Preload failed:', error);
 			toast.error(`Failed to preload next track: ${track.title}`);
@@ -350,7 +650,7 @@ class AudioPlayer {
 					if (this.state?.isPlaying && this.state.currentTrack) {
 						this.audio.pause();
 						this.playStream(this.state.currentTrack);
-						startMpvStatusPolling();
+						startMpvStatusEvents();
 					}
 				}
 			});
@@ -358,12 +658,12 @@ class AudioPlayer {
 			if (invoke) {
 				invoke('mpv_stop').catch(console.error);
 			}
-			stopMpvStatusPolling();
+			stopMpvStatusEvents();
 			this.playStream(this.state.currentTrack);
 		}
 
 		if (!enabled) {
-			stopMpvStatusPolling();
+			stopMpvStatusEvents();
 		}
 	}
 
@@ -376,12 +676,36 @@ class AudioPlayer {
 		});
 	}
 
+	private syncMpvReplayGain(track: Child) {
+		if (!this.state) return;
+
+		const { enabled, mode, preAmp } = this.state.replayGain;
+		const index = this.state.playlist.findIndex((t) => t.id === track.id);
+		const neighbours = [this.state.playlist[index - 1], this.state.playlist[index + 1]];
+		const albumContext =
+			!!track.albumId && neighbours.some((t) => t?.albumId === track.albumId);
+
+		invoke('mpv_set_replaygain', {
+			settings: {
+				mode: enabled ? (mode === 'queue' ? 'auto' : mode) : 'off',
+				preamp: preAmp,
+				clip_prevention: true,
+				fallback_gain: 0,
+				album_context: albumContext
+			}
+		}).catch(console.error);
+	}
+
 	public applyReplayGain(track: Child) {
+		if (this.useMpv && this.mpvInitialized && invoke) {
+			// mpv applies ReplayGain itself, so the volume stays untouched
+			this.syncMpvReplayGain(track);
+			invoke('mpv_set_volume', { volume: this.volume }).catch(console.error);
+			return;
+		}
+
 		if (!this.state?.replayGain.enabled || !track.replayGain) {
-			this.audio.volume = this.volume;
-			if (this.useMpv && this.mpvInitialized && invoke) {
-				invoke('mpv_set_volume', { volume: this.volume }).catch(console.error);
-			}
+			this.audio.volume = Math.min(1, this.volume);
 			return;
 		}
 
@@ -408,10 +732,6 @@ class AudioPlayer {
 		const adjustedVolume = Math.min(1.0, this.volume * gain);
 
 		this.audio.volume = adjustedVolume;
-
-		if (this.useMpv && this.mpvInitialized && invoke) {
-			invoke('mpv_set_volume', { volume: adjustedVolume }).catch(console.error);
-		}
 	}
 
 	async loadPlaylist(tracks: Child[], startIndex = 0) {
@@ -430,8 +750,7 @@ class AudioPlayer {
 					console.log(`Loading playlist with ${tracks.length} tracks`);
 					const urlPromises = tracks.map(async (track) => {
 						try {
-							const url = await this.client!.getSongStreamURL(track.id);
-							return { id: track.id, url };
+							return { id: track.id, url: await this.streamUrl(track) };
 						} catch (error) {
 							console.error(`Failed to get stream URL for track ${track.title}:`, error);
 							return null;
@@ -461,11 +780,10 @@ class AudioPlayer {
 
 					console.log(`Prepared ${streamUrls.length} valid URLs for playlist`);
 
-					await invoke('mpv_stop');
-
-					await invoke('mpv_load_playlist_optimized', { urls: streamUrls });
-
-					await new Promise((resolve) => setTimeout(resolve, 150));
+					const currentIndex = startIndex > 0 && startIndex < streamUrls.length ? startIndex : 0;
+					await invoke('mpv_sync_playlist', { urls: streamUrls, currentIndex });
+					// The sync keeps an entry that is already playing, a fresh load starts it over
+					await invoke('mpv_set_playlist_position', { index: currentIndex });
 
 					const status = await invoke<{ playlist_count: number; playlist_pos: number }>(
 						'mpv_get_status'
@@ -477,22 +795,6 @@ class AudioPlayer {
 						);
 					}
 
-					console.log(`Initial playlist position: ${status.playlist_pos}`);
-
-					if (startIndex > 0 && startIndex < streamUrls.length) {
-						console.log(`Setting playlist position to ${startIndex}`);
-						await invoke('mpv_playlist_jump_to_index', { index: startIndex });
-
-						const posStatus = await invoke<{ playlist_pos: number }>('mpv_get_status');
-						console.log(`Playlist position after jump: ${posStatus.playlist_pos}`);
-
-						if (posStatus.playlist_pos !== startIndex) {
-							await invoke('mpv_set_playlist_position', { index: startIndex });
-						}
-					} else {
-						await invoke('mpv_playlist_jump_to_index', { index: 0 });
-					}
-
 					this.mpvPlaylistLoaded = true;
 					this.lastMpvPlaylistPos = startIndex;
 
@@ -564,7 +866,7 @@ class AudioPlayer {
 					this.applyReplayGain(track);
 					await invoke('mpv_play');
 					this.state.isPlaying = true;
-					startMpvStatusPolling();
+					startMpvStatusEvents();
 
 					updateMediaMetadata({
 						track,
@@ -643,6 +945,7 @@ class AudioPlayer {
 
 	setClient(client: NavidromeClient | null) {
 		this.client = client;
+		this.streamUrlCache.clear();
 	}
 
 	pause() {
@@ -777,18 +1080,28 @@ class AudioPlayer {
 	}
 
 	setVolume(value: number) {
-		this.volume = Math.max(0, Math.min(1, value));
+		// Only mpv can boost past 100%
+		const max = this.useMpv && this.mpvInitialized ? get(mpvStatus).max_volume : 1;
+		this.volume = Math.max(0, Math.min(max, value));
 
 		if (this.state?.currentTrack) {
 			this.applyReplayGain(this.state.currentTrack);
 		} else {
-			this.audio.volume = this.volume;
+			this.audio.volume = Math.min(1, this.volume);
 			this.syncMpvVolume();
 		}
 
 		localStorage.setItem('volume', this.volume.toString());
 	}
 
+	setMuted(muted: boolean) {
+		if (this.useMpv && this.mpvInitialized && invoke) {
+			invoke('mpv_set_mute', { muted }).catch(console.error);
+		} else {
+			this.audio.muted = muted;
+		}
+	}
+
 	onEnded(callback: () => void) {
 		this.onEndedCallback = callback;
 	}
@@ -866,6 +1179,54 @@ class AudioPlayer {
 	setUseNativeMpvPlaylist(useNative: boolean) {
 		this.useNativeMpvPlaylist = useNative;
 	}
+
+	// Reuse URLs so mpv_sync_playlist can match entries already in mpv
+	private async streamUrl(track: Child) {
+		let url = this.streamUrlCache.get(track.id);
+		if (!url) {
+			url = await this.client!.getSongStreamURL(track.id);
+			this.streamUrlCache.set(track.id, url);
+		}
+		return url;
+	}
+
+	// Queue edits are passed on to mpv one at a time and in order, so gapless playback sees them
+	private editMpvQueue(description: string, edit: () => Promise<unknown>) {
+		if (!this.useMpv || !this.mpvInitialized || !this.useNativeMpvPlaylist) return;
+		if (!this.mpvPlaylistLoaded || !this.client || !invoke) return;
+
+		this.mpvQueueEdits = this.mpvQueueEdits
+			.then(edit)
+			.catch((error) => console.error(`Failed to ${description} in the MPV playlist:`, error));
+	}
+
+	insertIntoMpvQueue(tracks: Child[], index: number) {
+		this.editMpvQueue('insert tracks', async () => {
+			const urls = await Promise.all(tracks.map((track) => this.streamUrl(track)));
+			for (const [offset, url] of urls.entries()) {
+				await invoke('mpv_playlist_insert', { url, index: index + offset });
+			}
+		});
+	}
+
+	removeFromMpvQueue(index: number) {
+		this.editMpvQueue('remove a track', () => invoke('mpv_playlist_remove', { index }));
+	}
+
+	moveInMpvQueue(from: number, to: number) {
+		this.editMpvQueue('move a track', () => invoke('mpv_playlist_move', { from, to }));
+	}
+
+	clearMpvQueueAfterCurrent() {
+		this.editMpvQueue('clear upcoming tracks', () => invoke('mpv_playlist_clear_after_current'));
+	}
+
+	syncMpvQueue(tracks: Child[], currentIndex: number) {
+		this.editMpvQueue('reorder tracks', async () => {
+			const urls = await Promise.all(tracks.map((track) => this.streamUrl(track)));
+			await invoke('mpv_sync_playlist', { urls, currentIndex });
+		});
+	}
 }
 
 interface PlayerState {
@@ -934,7 +1295,7 @@ function createPlayerStore() {
 						await invoke('mpv_load', { url: stream });
 						await invoke('mpv_pause');
 
-						startMpvStatusPolling();
+						startMpvStatusEvents();
 
 						if (initialPosition > 0) {
 							let seekAttempts = 0;
@@ -1138,29 +1499,6 @@ function createPlayerStore() {
 					}
 				}
 
-				if (
-					status.playlist_pos !== undefined &&
-					status.playlist_pos >= 0 &&
-					status.playlist_pos !== state.currentIndex &&
-					status.playlist_count > 0
-				) {
-					if (status.playlist_pos < state.playlist.length) {
-						const nextTrack = state.playlist[status.playlist_pos];
-						updateMediaMetadata({
-							track: nextTrack,
-							duration: status.duration,
-							position: status.position
-						});
-
-						return {
-							...state,
-							currentIndex: status.playlist_pos,
-							currentTrack: nextTrack,
-							isPlaying: status.state === 'playing'
-						};
-					}
-				}
-
 				if (
 					(status.state === 'paused' && state.isPlaying) ||
 					(status.state === 'playing' && !state.isPlaying)
@@ -1173,15 +1511,6 @@ function createPlayerStore() {
 					};
 				}
 
-				if (status.state === 'ended' || status.state === 'idle') {
-					if (state.currentIndex === state.playlist.length - 1 && state.repeat !== 'all') {
-						return {
-							...state,
-							isPlaying: false
-						};
-					}
-				}
-
 				return state;
 			});
 		}
@@ -1241,9 +1570,44 @@ function createPlayerStore() {
 				const modes: RepeatMode[] = ['none', 'all', 'one'];
 				const currentIndex = modes.indexOf(state.repeat);
 				const newRepeat = modes[(currentIndex + 1) % modes.length];
+				if (get(mpvSettings).initialized) {
+					invoke('mpv_set_repeat', { mode: newRepeat }).catch((error) =>
+						console.warn('Failed to set MPV repeat mode:', error)
+					);
+				}
 				return { ...state, repeat: newRepeat };
 			});
 		},
+		setAbLoop: (a: number | null, b: number | null) => invoke('mpv_set_ab_loop', { a, b }),
+		setSleepTimer: (settings: SleepTimerSettings) => invoke('mpv_set_sleep_timer', { settings }),
+		cancelSleepTimer: () => invoke('mpv_cancel_sleep_timer'),
+		getChapters: () => invoke<Chapter[]>('mpv_get_chapters'),
+		nextChapter: () => invoke('mpv_chapter_next'),
+		prevChapter: () => invoke('mpv_chapter_prev'),
+		setChapter: (index: number) => invoke('mpv_set_chapter', { index }),
+		getProperty: <T = unknown>(name: string) => invoke<T>('mpv_get_property', { name }),
+		setProperty: (name: string, value: unknown) => invoke('mpv_set_property', { name, value }),
+		// Resolves to a function that stops listening and unobserves the property
+		observeProperty: async (name: string, callback: (value: unknown) => void) => {
+			const unlisten = await listen<PropertyChange>('player://property-changed', (event) => {
+				if (event.payload.name === name) callback(event.payload.value);
+			});
+			try {
+				await invoke('mpv_observe_property', { name });
+			} catch (error) {
+				unlisten();
+				throw error;
+			}
+			return async () => {
+				unlisten();
+				await invoke('mpv_unobserve_property', { name });
+			};
+		},
+		getLogs: () => invoke<LogEntry[]>('mpv_get_logs'),
+		// Resolves to a function that stops listening
+		// Lines arrive in batches
+		onLog: (callback: (entries: LogEntry[]) => void) =>
+			listen<LogEntry[]>('player://log', (event) => callback(event.payload)),
 		next: () => {
 			update((state) => {
 				if (!state.playlist.length) return state;
@@ -1445,6 +1809,7 @@ function createPlayerStore() {
 				const newTracks = Array.isArray(track) ? track : [track];
 				const newPlaylist = [...state.playlist, ...newTracks];
 				const newOriginalPlaylist = [...state.originalPlaylist, ...newTracks];
+				audioPlayer.insertIntoMpvQueue(newTracks, state.playlist.length);
 				return {
 					...state,
 					playlist: newPlaylist,
@@ -1452,13 +1817,15 @@ function createPlayerStore() {
 				};
 			});
 		},
-		addToQueueNext: (track: Child) => {
+		addToQueueNext: (track: Child | Child[]) => {
 			update((state) => {
+				const newTracks = Array.isArray(track) ? track : [track];
 				const newPlaylist = [...state.playlist];
 				const newOriginalPlaylist = [...state.originalPlaylist];
 
-				newPlaylist.splice(state.currentIndex + 1, 0, track);
-				newOriginalPlaylist.splice(state.currentIndex + 1, 0, track);
+				newPlaylist.splice(state.currentIndex + 1, 0, ...newTracks);
+				newOriginalPlaylist.splice(state.currentIndex + 1, 0, ...newTracks);
+				audioPlayer.insertIntoMpvQueue(newTracks, state.currentIndex + 1);
 
 				return {
 					...state,
@@ -1467,6 +1834,36 @@ function createPlayerStore() {
 				};
 			});
 		},
+		removeFromQueue: (index: number) => {
+			update((state) => {
+				if (index <= state.currentIndex || index >= state.playlist.length) return state;
+				audioPlayer.removeFromMpvQueue(index);
+				return { ...state, playlist: state.playlist.filter((_, i) => i !== index) };
+			});
+		},
+		moveInQueue: (from: number, to: number) => {
+			update((state) => {
+				const newPlaylist = [...state.playlist];
+				const [item] = newPlaylist.splice(from, 1);
+				newPlaylist.splice(to, 0, item);
+				audioPlayer.moveInMpvQueue(from, to);
+				return { ...state, playlist: newPlaylist };
+			});
+		},
+		// Replaces what comes after the current track, e.g. after a drag and drop
+		setUpcoming: (tracks: Child[]) => {
+			update((state) => {
+				const newPlaylist = [...state.playlist.slice(0, state.currentIndex + 1), ...tracks];
+				audioPlayer.syncMpvQueue(newPlaylist, state.currentIndex);
+				return { ...state, playlist: newPlaylist };
+			});
+		},
+		clearUpcoming: () => {
+			update((state) => {
+				audioPlayer.clearMpvQueueAfterCurrent();
+				return { ...state, playlist: state.playlist.slice(0, state.currentIndex + 1) };
+			});
+		},
 		toggleScrobble: () => {
 			update((state) => {
 				const newScrobble = !state.scrobble;
@@ -1504,6 +1901,9 @@ function createPlayerStore() {
 			audioPlayer.setVolume(volume);
 		},
 		getVolume: () => audioPlayer.volume,
+		setMuted: (muted: boolean) => {
+			audioPlayer.setMuted(muted);
+		},
 		getProgress: () => audioPlayer.progress,
 		getDuration: () => {
 			let duration: number = 0;
@@ -1584,6 +1984,79 @@ function createPlayerStore() {
 			localStorage.setItem('mpvUseNativePlaylist', useNative.toString());
 			mpvSettings.update((s) => ({ ...s, nativePlaylist: useNative }));
 		},
+		setAudioDevice: async (name: string) => {
+			if (get(mpvSettings).initialized) {
+				await invoke('mpv_set_audio_device', { name });
+			}
+			localStorage.setItem('mpvAudioDevice', name);
+			mpvSettings.update((s) => ({ ...s, audioDevice: name }));
+		},
+		setSpeed: async (speed: number, pitchCorrection: PitchCorrection) => {
+			if (get(mpvSettings).initialized) {
+				await invoke('mpv_set_speed', { speed, pitchCorrection });
+			}
+			localStorage.setItem('mpvSpeed', speed.toString());
+			localStorage.setItem('mpvPitchCorrection', pitchCorrection);
+			mpvSettings.update((s) => ({ ...s, speed, pitchCorrection }));
+		},
+		setPitch: async (semitones: number) => {
+			if (get(mpvSettings).initialized) {
+				await invoke('mpv_set_pitch', { semitones });
+			}
+			localStorage.setItem('mpvPitch', semitones.toString());
+			mpvSettings.update((s) => ({ ...s, pitch: semitones }));
+		},
+		setNormalization: async (settings: NormalizationSettings) => {
+			if (get(mpvSettings).initialized) {
+				await invoke('mpv_set_normalization', { settings });
+			}
+			localStorage.setItem('mpvNormalization', JSON.stringify(settings));
+			mpvSettings.update((s) => ({ ...s, normalization: settings }));
+		},
+		setVolumeSettings: async (settings: VolumeSettings) => {
+			if (get(mpvSettings).initialized) {
+				await invoke('mpv_set_volume_settings', { settings });
+			}
+			localStorage.setItem('mpvVolume', JSON.stringify(settings));
+			mpvSettings.update((s) => ({ ...s, volume: settings }));
+		},
+		setLogLevel: async (level: LogLevel) => {
+			await invoke('mpv_set_log_level', { level });
+			localStorage.setItem('mpvLogLevel', level);
+			mpvSettings.update((s) => ({ ...s, logLevel: level }));
+		},
+		// Takes effect the next time mpv is started
+		setMpvOptions: async (options: MpvOptions) => {
+			await invoke('mpv_set_options', { options });
+			localStorage.setItem('mpvOptions', JSON.stringify(options));
+			mpvSettings.update((s) => ({ ...s, options }));
+		},
+		setCrossfade: async (settings: CrossfadeSettings) => {
+			if (get(mpvSettings).initialized) {
+				await invoke('mpv_set_crossfade', { settings });
+			}
+			localStorage.setItem('mpvCrossfade', JSON.stringify(settings));
+			mpvSettings.update((s) => ({ ...s, crossfade: settings }));
+		},
+		setEqualizer: async (settings: EqualizerSettings) => {
+			if (get(mpvSettings).initialized) {
+				await invoke('mpv_set_equalizer', { settings });
+			}
+			localStorage.setItem('mpvEqualizer', JSON.stringify(settings));
+			mpvSettings.update((s) => ({ ...s, equalizer: settings }));
+		},
+		listEqualizerPresets: () => invoke<string[]>('mpv_list_equalizer_presets'),
+		loadEqualizerPreset: async (name: string) => {
+			const settings = await invoke<EqualizerSettings>('mpv_load_equalizer_preset', { name });
+			localStorage.setItem('mpvEqualizer', JSON.stringify(settings));
+			mpvSettings.update((s) => ({ ...s, equalizer: settings }));
+			return settings;
+		},
+		saveEqualizerPreset: (name: string, settings: EqualizerSettings) =>
+			invoke('mpv_save_equalizer_preset', { name, settings }),
+		deleteEqualizerPreset: (name: string) => invoke('mpv_delete_equalizer_preset', { name }),
+		setEqualizerGenrePresets: (presets: Record<string, string>) =>
+			invoke('mpv_set_equalizer_genre_presets', { presets }),
 		setPreciseSeek: (enabled: boolean) => {
 			localStorage.setItem('mpvPreciseSeek', enabled.toString());
 			mpvSettings.update((s) => ({
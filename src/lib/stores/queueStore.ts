@@ -1,5 +1,5 @@
 import type { Child } from '@vmohammad/subsonic-api';
-import { derived, writable } from 'svelte/store';
+import { derived, get, writable } from 'svelte/store';
 import { player } from './player';
 
 export const history = derived<typeof player, Child[]>(player, ($player) => {
@@ -18,42 +18,13 @@ export const queueActions = {
 	toggle: () => queueVisible.update((v) => !v),
 	show: () => queueVisible.set(true),
 	hide: () => queueVisible.set(false),
-	add: (tracks: Child[]) => {
-		player.update((p) => ({
-			...p,
-			playlist: [...p.playlist, ...tracks]
-		}));
-	},
-	addNext: (tracks: Child[]) => {
-		player.update((p) => ({
-			...p,
-			playlist: [
-				...p.playlist.slice(0, p.currentIndex + 1),
-				...tracks,
-				...p.playlist.slice(p.currentIndex + 1)
-			]
-		}));
-	},
-	remove: (index: number) => {
-		player.update((p) => ({
-			...p,
-			playlist: p.playlist.filter((_, i) => i !== index + p.currentIndex + 1)
-		}));
-	},
-	clear: () => {
-		player.update((p) => ({
-			...p,
-			playlist: p.playlist.slice(0, p.currentIndex + 1)
-		}));
-	},
+	add: (tracks: Child[]) => player.addToQueue(tracks),
+	addNext: (tracks: Child[]) => player.addToQueueNext(tracks),
+	// Indices count from the track after the current one
+	remove: (index: number) => player.removeFromQueue(index + get(player).currentIndex + 1),
+	clear: () => player.clearUpcoming(),
 	move: (from: number, to: number) => {
-		player.update((p) => {
-			const actualFrom = from + p.currentIndex + 1;
-			const actualTo = to + p.currentIndex + 1;
-			const newPlaylist = [...p.playlist];
-			const [item] = newPlaylist.splice(actualFrom, 1);
-			newPlaylist.splice(actualTo, 0, item);
-			return { ...p, playlist: newPlaylist };
-		});
+		const offset = get(player).currentIndex + 1;
+		player.moveInQueue(from + offset, to + offset);
 	}
 };